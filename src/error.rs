@@ -17,6 +17,159 @@ pub enum DecenseError {
 
     #[error("Invalid PDA")]
     InvalidPDA,
+
+    #[error("Invalid account owner")]
+    InvalidAccountOwner,
+
+    #[error("Account key mismatch")]
+    AccountKeyMismatch,
+
+    #[error("Account not writable")]
+    AccountNotWritable,
+
+    #[error("Invalid treasury wallet")]
+    InvalidTreasuryWallet,
+
+    #[error("Insufficient vault balance")]
+    InsufficientVaultBalance,
+
+    #[error("Slippage tolerance exceeded")]
+    SlippageExceeded,
+
+    #[error("Invalid order")]
+    InvalidOrder,
+
+    #[error("Order book full")]
+    OrderBookFull,
+
+    #[error("Order not found")]
+    OrderNotFound,
+
+    #[error("Invalid pricing curve")]
+    InvalidPricingCurve,
+
+    #[error("Unauthorized")]
+    Unauthorized,
+
+    #[error("Invalid fee")]
+    InvalidFee,
+
+    #[error("Invalid quote mint")]
+    InvalidQuoteMint,
+
+    #[error("Invalid dividend")]
+    InvalidDividend,
+
+    #[error("Invalid snapshot proof")]
+    InvalidSnapshotProof,
+
+    #[error("Dividend already claimed")]
+    DividendAlreadyClaimed,
+
+    #[error("Invalid proposal")]
+    InvalidProposal,
+
+    #[error("Voting closed")]
+    VotingClosed,
+
+    #[error("Voting in progress")]
+    VotingInProgress,
+
+    #[error("Vote already cast")]
+    VoteAlreadyCast,
+
+    #[error("Invalid vesting schedule")]
+    InvalidVestingSchedule,
+
+    #[error("Nothing to release")]
+    NothingToRelease,
+
+    #[error("Invalid percentage")]
+    InvalidPercentage,
+
+    #[error("Trading halted")]
+    TradingHalted,
+
+    #[error("Invalid circuit breaker")]
+    InvalidCircuitBreaker,
+
+    #[error("Platform paused")]
+    PlatformPaused,
+
+    #[error("Unknown account layout")]
+    UnknownAccountLayout,
+
+    #[error("Account type mismatch")]
+    AccountTypeMismatch,
+
+    #[error("Issue not approved")]
+    IssueNotApproved,
+
+    #[error("Issue limit exceeded")]
+    IssueLimitExceeded,
+
+    #[error("Mint authority not handed over")]
+    MintAuthorityNotHandedOver,
+
+    #[error("Invalid split")]
+    InvalidSplit,
+
+    #[error("Split already claimed")]
+    SplitAlreadyClaimed,
+
+    #[error("Order book not empty")]
+    OrderBookNotEmpty,
+
+    #[error("Invalid buyback")]
+    InvalidBuyback,
+
+    #[error("Listing delisted")]
+    ListingDelisted,
+
+    #[error("Listing not delisted")]
+    ListingNotDelisted,
+
+    #[error("Invalid settlement")]
+    InvalidSettlement,
+
+    #[error("Settlement already funded")]
+    SettlementAlreadyFunded,
+
+    #[error("Redemption not open")]
+    RedemptionNotOpen,
+
+    #[error("Redemption in progress")]
+    RedemptionInProgress,
+
+    #[error("Invalid offering")]
+    InvalidOffering,
+
+    #[error("Offering in progress")]
+    OfferingInProgress,
+
+    #[error("Offering not open")]
+    OfferingNotOpen,
+
+    #[error("Hard cap exceeded")]
+    HardCapExceeded,
+
+    #[error("Wallet limit exceeded")]
+    WalletLimitExceeded,
+
+    #[error("Split not claimed")]
+    SplitNotClaimed,
+
+    #[error("Position exceeded")]
+    PositionExceeded,
+
+    #[error("Dividend expired")]
+    DividendExpired,
+
+    #[error("Dividend not expired")]
+    DividendNotExpired,
+
+    #[error("Unmigratable listing")]
+    UnmigratableListing,
 }
 
 impl From<DecenseError> for ProgramError {
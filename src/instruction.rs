@@ -1,12 +1,134 @@
-use solana_program::program_error::ProgramError;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+use spl_associated_token_account::get_associated_token_address;
 
 use crate::error::DecenseError;
 
+#[derive(Debug, PartialEq, Clone)]
 pub enum DecenseInstruction {
     InitializePlatform,
-    InitializeUser { market_valuation: u64, supply: u64 },
-    Exchange { asked_price: u64, quantity: u64 },
-    SendRecieveToken { action: u64, amount: u64 },
+    InitializeUser {
+        market_valuation: u64,
+        supply: u64,
+        pricing_curve: u64,
+        curve_parameter: u64,
+        vesting_cliff: u64,
+        vesting_duration: u64,
+        vesting_release_interval: u64,
+        user_treasury_percentage: u64,
+        liquidate_percentage: u64,
+    },
+    Exchange {
+        quantity: u64,
+        max_total_cost: u64,
+        min_tokens_out: u64,
+    },
+    SendRecieveToken {
+        action: u64,
+        amount: u64,
+    },
+    SellToPool {
+        quantity: u64,
+        min_sol_out: u64,
+    },
+    PlaceOrder {
+        side: u64,
+        price: u64,
+        quantity: u64,
+    },
+    CancelOrder {
+        side: u64,
+        sequence: u64,
+    },
+    MatchOrders {
+        limit: u64,
+    },
+    UpdatePlatformConfig {
+        listing_fee: u64,
+        buy_fee_bps: u64,
+        sell_fee_bps: u64,
+    },
+    TransferAdmin,
+    AcceptAdmin,
+    DeclareDividend {
+        amount_per_token: u64,
+        eligible_supply: u64,
+        claim_period: u64,
+        snapshot_root: [u8; 32],
+    },
+    ClaimDividend {
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    },
+    CreateProposal {
+        voting_period: u64,
+        quorum: u64,
+        threshold_bps: u64,
+        description_hash: [u8; 32],
+    },
+    CastVote {
+        support: u64,
+        amount: u64,
+    },
+    FinalizeProposal,
+    WithdrawVote,
+    ReleaseVested,
+    ConfigureCircuitBreaker {
+        max_move_bps: u64,
+        window: u64,
+        cooldown: u64,
+    },
+    HaltTrading,
+    ResumeTrading,
+    Pause,
+    Unpause,
+    MigrateAccount,
+    IssueNewShares {
+        amount: u64,
+    },
+    Split {
+        numerator: u64,
+        denominator: u64,
+    },
+    ClaimSplit,
+    Buyback {
+        budget: u64,
+        max_price: u64,
+        burn: u64,
+    },
+    SellToBuyback {
+        quantity: u64,
+    },
+    CloseBuyback,
+    Delist {
+        redemption_window: u64,
+    },
+    DepositSettlement {
+        amount: u64,
+    },
+    RedeemSettlement {
+        quantity: u64,
+    },
+    CloseListing,
+    CreateOffering {
+        price: u64,
+        start_time: u64,
+        end_time: u64,
+        per_wallet_max: u64,
+        soft_cap: u64,
+        hard_cap: u64,
+    },
+    Subscribe {
+        quantity: u64,
+    },
+    FinalizeOffering,
+    ClaimOffering,
+    ReclaimDividend,
 }
 
 impl DecenseInstruction {
@@ -28,6 +150,90 @@ impl DecenseInstruction {
         Ok(amount)
     }
 
+    fn get_third_u64(rest: &[u8]) -> Result<u64, ProgramError> {
+        let amount = rest
+            .get(16..24)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(DecenseError::InvalidNumber)?;
+        Ok(amount)
+    }
+
+    fn get_fourth_u64(rest: &[u8]) -> Result<u64, ProgramError> {
+        let amount = rest
+            .get(24..32)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(DecenseError::InvalidNumber)?;
+        Ok(amount)
+    }
+
+    fn get_fifth_u64(rest: &[u8]) -> Result<u64, ProgramError> {
+        let amount = rest
+            .get(32..40)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(DecenseError::InvalidNumber)?;
+        Ok(amount)
+    }
+
+    fn get_sixth_u64(rest: &[u8]) -> Result<u64, ProgramError> {
+        let amount = rest
+            .get(40..48)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(DecenseError::InvalidNumber)?;
+        Ok(amount)
+    }
+
+    fn get_seventh_u64(rest: &[u8]) -> Result<u64, ProgramError> {
+        let amount = rest
+            .get(48..56)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(DecenseError::InvalidNumber)?;
+        Ok(amount)
+    }
+
+    fn get_eighth_u64(rest: &[u8]) -> Result<u64, ProgramError> {
+        let amount = rest
+            .get(56..64)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(DecenseError::InvalidNumber)?;
+        Ok(amount)
+    }
+
+    fn get_ninth_u64(rest: &[u8]) -> Result<u64, ProgramError> {
+        let amount = rest
+            .get(64..72)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(DecenseError::InvalidNumber)?;
+        Ok(amount)
+    }
+
+    fn get_hash(rest: &[u8], start: usize) -> Result<[u8; 32], ProgramError> {
+        let hash = rest
+            .get(start..start + 32)
+            .and_then(|slice| slice.try_into().ok())
+            .ok_or(DecenseError::InvalidInstruction)?;
+        Ok(hash)
+    }
+
+    fn get_proof(rest: &[u8], start: usize) -> Result<Vec<[u8; 32]>, ProgramError> {
+        let proof = rest.get(start..).ok_or(DecenseError::InvalidInstruction)?;
+
+        if proof.len() % 32 != 0 {
+            return Err(DecenseError::InvalidInstruction.into());
+        }
+
+        proof
+            .chunks_exact(32)
+            .map(|chunk| Self::get_hash(chunk, 0))
+            .collect()
+    }
+
     pub fn unpack_instruction(instruction_data: &[u8]) -> Result<Self, ProgramError> {
         let (ins_no, rest) = instruction_data
             .split_first()
@@ -37,16 +243,1586 @@ impl DecenseInstruction {
             1 => Self::InitializeUser {
                 market_valuation: Self::get_first_u64(rest)?,
                 supply: Self::get_second_u64(rest)?,
+                pricing_curve: Self::get_third_u64(rest)?,
+                curve_parameter: Self::get_fourth_u64(rest)?,
+                vesting_cliff: Self::get_fifth_u64(rest)?,
+                vesting_duration: Self::get_sixth_u64(rest)?,
+                vesting_release_interval: Self::get_seventh_u64(rest)?,
+                user_treasury_percentage: Self::get_eighth_u64(rest)?,
+                liquidate_percentage: Self::get_ninth_u64(rest)?,
             },
             2 => Self::Exchange {
-                asked_price: Self::get_first_u64(rest)?,
-                quantity: Self::get_second_u64(rest)?,
+                quantity: Self::get_first_u64(rest)?,
+                max_total_cost: Self::get_second_u64(rest)?,
+                min_tokens_out: Self::get_third_u64(rest)?,
             },
             3 => Self::SendRecieveToken {
                 action: Self::get_first_u64(rest)?,
                 amount: Self::get_second_u64(rest)?,
             },
+            4 => Self::SellToPool {
+                quantity: Self::get_first_u64(rest)?,
+                min_sol_out: Self::get_second_u64(rest)?,
+            },
+            5 => Self::PlaceOrder {
+                side: Self::get_first_u64(rest)?,
+                price: Self::get_second_u64(rest)?,
+                quantity: Self::get_third_u64(rest)?,
+            },
+            6 => Self::CancelOrder {
+                side: Self::get_first_u64(rest)?,
+                sequence: Self::get_second_u64(rest)?,
+            },
+            7 => Self::MatchOrders {
+                limit: Self::get_first_u64(rest)?,
+            },
+            8 => Self::UpdatePlatformConfig {
+                listing_fee: Self::get_first_u64(rest)?,
+                buy_fee_bps: Self::get_second_u64(rest)?,
+                sell_fee_bps: Self::get_third_u64(rest)?,
+            },
+            9 => Self::TransferAdmin,
+            10 => Self::AcceptAdmin,
+            11 => Self::DeclareDividend {
+                amount_per_token: Self::get_first_u64(rest)?,
+                eligible_supply: Self::get_second_u64(rest)?,
+                claim_period: Self::get_third_u64(rest)?,
+                snapshot_root: Self::get_hash(rest, 24)?,
+            },
+            12 => Self::ClaimDividend {
+                amount: Self::get_first_u64(rest)?,
+                proof: Self::get_proof(rest, 8)?,
+            },
+            13 => Self::CreateProposal {
+                voting_period: Self::get_first_u64(rest)?,
+                quorum: Self::get_second_u64(rest)?,
+                threshold_bps: Self::get_third_u64(rest)?,
+                description_hash: Self::get_hash(rest, 24)?,
+            },
+            14 => Self::CastVote {
+                support: Self::get_first_u64(rest)?,
+                amount: Self::get_second_u64(rest)?,
+            },
+            15 => Self::FinalizeProposal,
+            16 => Self::WithdrawVote,
+            17 => Self::ReleaseVested,
+            18 => Self::ConfigureCircuitBreaker {
+                max_move_bps: Self::get_first_u64(rest)?,
+                window: Self::get_second_u64(rest)?,
+                cooldown: Self::get_third_u64(rest)?,
+            },
+            19 => Self::HaltTrading,
+            20 => Self::ResumeTrading,
+            21 => Self::Pause,
+            22 => Self::Unpause,
+            23 => Self::MigrateAccount,
+            24 => Self::IssueNewShares {
+                amount: Self::get_first_u64(rest)?,
+            },
+            25 => Self::Split {
+                numerator: Self::get_first_u64(rest)?,
+                denominator: Self::get_second_u64(rest)?,
+            },
+            26 => Self::ClaimSplit,
+            27 => Self::Buyback {
+                budget: Self::get_first_u64(rest)?,
+                max_price: Self::get_second_u64(rest)?,
+                burn: Self::get_third_u64(rest)?,
+            },
+            28 => Self::SellToBuyback {
+                quantity: Self::get_first_u64(rest)?,
+            },
+            29 => Self::CloseBuyback,
+            30 => Self::Delist {
+                redemption_window: Self::get_first_u64(rest)?,
+            },
+            31 => Self::DepositSettlement {
+                amount: Self::get_first_u64(rest)?,
+            },
+            32 => Self::RedeemSettlement {
+                quantity: Self::get_first_u64(rest)?,
+            },
+            33 => Self::CloseListing,
+            34 => Self::CreateOffering {
+                price: Self::get_first_u64(rest)?,
+                start_time: Self::get_second_u64(rest)?,
+                end_time: Self::get_third_u64(rest)?,
+                per_wallet_max: Self::get_fourth_u64(rest)?,
+                soft_cap: Self::get_fifth_u64(rest)?,
+                hard_cap: Self::get_sixth_u64(rest)?,
+            },
+            35 => Self::Subscribe {
+                quantity: Self::get_first_u64(rest)?,
+            },
+            36 => Self::FinalizeOffering,
+            37 => Self::ClaimOffering,
+            38 => Self::ReclaimDividend,
             _ => return Err(DecenseError::InvalidInstruction.into()),
         })
     }
+
+    pub fn pack(&self) -> Vec<u8> {
+        let (ins_no, args): (u8, Vec<u64>) = match *self {
+            Self::InitializePlatform => (0, vec![]),
+            Self::InitializeUser {
+                market_valuation,
+                supply,
+                pricing_curve,
+                curve_parameter,
+                vesting_cliff,
+                vesting_duration,
+                vesting_release_interval,
+                user_treasury_percentage,
+                liquidate_percentage,
+            } => (
+                1,
+                vec![
+                    market_valuation,
+                    supply,
+                    pricing_curve,
+                    curve_parameter,
+                    vesting_cliff,
+                    vesting_duration,
+                    vesting_release_interval,
+                    user_treasury_percentage,
+                    liquidate_percentage,
+                ],
+            ),
+            Self::Exchange {
+                quantity,
+                max_total_cost,
+                min_tokens_out,
+            } => (2, vec![quantity, max_total_cost, min_tokens_out]),
+            Self::SendRecieveToken { action, amount } => (3, vec![action, amount]),
+            Self::SellToPool {
+                quantity,
+                min_sol_out,
+            } => (4, vec![quantity, min_sol_out]),
+            Self::PlaceOrder {
+                side,
+                price,
+                quantity,
+            } => (5, vec![side, price, quantity]),
+            Self::CancelOrder { side, sequence } => (6, vec![side, sequence]),
+            Self::MatchOrders { limit } => (7, vec![limit]),
+            Self::UpdatePlatformConfig {
+                listing_fee,
+                buy_fee_bps,
+                sell_fee_bps,
+            } => (8, vec![listing_fee, buy_fee_bps, sell_fee_bps]),
+            Self::TransferAdmin => (9, vec![]),
+            Self::AcceptAdmin => (10, vec![]),
+            Self::DeclareDividend {
+                amount_per_token,
+                eligible_supply,
+                claim_period,
+                ..
+            } => (11, vec![amount_per_token, eligible_supply, claim_period]),
+            Self::ClaimDividend { amount, .. } => (12, vec![amount]),
+            Self::CreateProposal {
+                voting_period,
+                quorum,
+                threshold_bps,
+                ..
+            } => (13, vec![voting_period, quorum, threshold_bps]),
+            Self::CastVote { support, amount } => (14, vec![support, amount]),
+            Self::FinalizeProposal => (15, vec![]),
+            Self::WithdrawVote => (16, vec![]),
+            Self::ReleaseVested => (17, vec![]),
+            Self::ConfigureCircuitBreaker {
+                max_move_bps,
+                window,
+                cooldown,
+            } => (18, vec![max_move_bps, window, cooldown]),
+            Self::HaltTrading => (19, vec![]),
+            Self::ResumeTrading => (20, vec![]),
+            Self::Pause => (21, vec![]),
+            Self::Unpause => (22, vec![]),
+            Self::MigrateAccount => (23, vec![]),
+            Self::IssueNewShares { amount } => (24, vec![amount]),
+            Self::Split {
+                numerator,
+                denominator,
+            } => (25, vec![numerator, denominator]),
+            Self::ClaimSplit => (26, vec![]),
+            Self::Buyback {
+                budget,
+                max_price,
+                burn,
+            } => (27, vec![budget, max_price, burn]),
+            Self::SellToBuyback { quantity } => (28, vec![quantity]),
+            Self::CloseBuyback => (29, vec![]),
+            Self::Delist { redemption_window } => (30, vec![redemption_window]),
+            Self::DepositSettlement { amount } => (31, vec![amount]),
+            Self::RedeemSettlement { quantity } => (32, vec![quantity]),
+            Self::CloseListing => (33, vec![]),
+            Self::CreateOffering {
+                price,
+                start_time,
+                end_time,
+                per_wallet_max,
+                soft_cap,
+                hard_cap,
+            } => (
+                34,
+                vec![
+                    price,
+                    start_time,
+                    end_time,
+                    per_wallet_max,
+                    soft_cap,
+                    hard_cap,
+                ],
+            ),
+            Self::Subscribe { quantity } => (35, vec![quantity]),
+            Self::FinalizeOffering => (36, vec![]),
+            Self::ClaimOffering => (37, vec![]),
+            Self::ReclaimDividend => (38, vec![]),
+        };
+
+        let mut buf = Vec::with_capacity(1 + args.len() * 8);
+        buf.push(ins_no);
+        for arg in args {
+            buf.extend_from_slice(&arg.to_le_bytes());
+        }
+
+        // hashes follow the u64 arguments
+        match self {
+            Self::DeclareDividend { snapshot_root, .. } => buf.extend_from_slice(snapshot_root),
+            Self::CreateProposal {
+                description_hash, ..
+            } => buf.extend_from_slice(description_hash),
+            Self::ClaimDividend { proof, .. } => {
+                for hash in proof {
+                    buf.extend_from_slice(hash);
+                }
+            }
+            _ => {}
+        }
+
+        buf
+    }
+}
+
+// a single platform state per program, so every listing pays the fees the admin configured
+pub fn get_platform_state_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"platform"], program_id).0
+}
+
+// listings and everything they own are keyed by the listed mint, so an issuer can list more
+// than one company
+pub fn get_user_state_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[mint.as_ref(), b"user_state"], program_id).0
+}
+
+// a holder's position in one listing
+pub fn get_buyer_state_address(mint: &Pubkey, buyer: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[mint.as_ref(), buyer.as_ref()], program_id).0
+}
+
+// pda holding the listing's pool tokens
+pub fn get_listing_pda_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[mint.as_ref()], program_id).0
+}
+
+pub fn get_vault_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[mint.as_ref(), b"vault"], program_id).0
+}
+
+pub fn get_order_book_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[mint.as_ref(), b"order_book"], program_id).0
+}
+
+pub fn get_bid_vault_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[mint.as_ref(), b"bid_vault"], program_id).0
+}
+
+pub fn get_ask_vault_authority_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[mint.as_ref(), b"ask_vault"], program_id).0
+}
+
+pub fn get_buyback_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[mint.as_ref(), b"buyback"], program_id).0
+}
+
+pub fn get_settlement_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[mint.as_ref(), b"settlement"], program_id).0
+}
+
+pub fn get_offering_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[mint.as_ref(), b"offering"], program_id).0
+}
+
+pub fn get_subscription_address(
+    offering: &Pubkey,
+    subscriber: &Pubkey,
+    program_id: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(&[offering.as_ref(), subscriber.as_ref()], program_id).0
+}
+
+pub fn get_dividend_address(mint: &Pubkey, index: u64, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[mint.as_ref(), b"dividend", &index.to_le_bytes()],
+        program_id,
+    )
+    .0
+}
+
+pub fn get_dividend_claim_address(
+    dividend: &Pubkey,
+    holder: &Pubkey,
+    program_id: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(&[dividend.as_ref(), holder.as_ref()], program_id).0
+}
+
+// pda holding the mint's vesting share and its schedule
+pub fn get_split_address(mint: &Pubkey, index: u64, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[mint.as_ref(), b"split", &index.to_le_bytes()], program_id).0
+}
+
+pub fn get_vesting_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[mint.as_ref(), b"vesting"], program_id).0
+}
+
+pub fn get_proposal_address(mint: &Pubkey, index: u64, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[mint.as_ref(), b"proposal", &index.to_le_bytes()],
+        program_id,
+    )
+    .0
+}
+
+pub fn get_vote_record_address(proposal: &Pubkey, voter: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[proposal.as_ref(), voter.as_ref()], program_id).0
+}
+
+pub fn initialize_platform(program_id: &Pubkey, admin: &Pubkey, treasury: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(get_platform_state_address(program_id), false),
+            AccountMeta::new_readonly(*treasury, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: DecenseInstruction::InitializePlatform.pack(),
+    }
+}
+
+// the mint account must already exist and be owned by the token program. listings priced in
+// lamports pass no quote mint, a vesting_duration of 0 leaves the issuer's share liquid.
+// user_treasury_percentage of the supply stays with the issuer and liquidate_percentage of every
+// pool buy stays in the vault to pay for sells back to the pool
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_user(
+    program_id: &Pubkey,
+    issuer: &Pubkey,
+    mint: &Pubkey,
+    platform_state: &Pubkey,
+    treasury: &Pubkey,
+    quote_mint: Option<&Pubkey>,
+    market_valuation: u64,
+    supply: u64,
+    pricing_curve: u64,
+    curve_parameter: u64,
+    vesting_cliff: u64,
+    vesting_duration: u64,
+    vesting_release_interval: u64,
+    user_treasury_percentage: u64,
+    liquidate_percentage: u64,
+) -> Instruction {
+    let pda = get_listing_pda_address(mint, program_id);
+    let vault = get_vault_address(mint, program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new(*issuer, true),
+        AccountMeta::new(*mint, false),
+        AccountMeta::new(get_user_state_address(mint, program_id), false),
+        AccountMeta::new_readonly(*platform_state, false),
+        AccountMeta::new(*treasury, false),
+        AccountMeta::new_readonly(pda, false),
+        AccountMeta::new(get_associated_token_address(issuer, mint), false),
+        AccountMeta::new(get_associated_token_address(&pda, mint), false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new(get_buyer_state_address(mint, issuer, program_id), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    if vesting_duration > 0 {
+        let vesting = get_vesting_address(mint, program_id);
+
+        accounts.extend([
+            AccountMeta::new(vesting, false),
+            AccountMeta::new(get_associated_token_address(&vesting, mint), false),
+        ]);
+    }
+
+    if let Some(quote_mint) = quote_mint {
+        accounts.extend([
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new(get_associated_token_address(&vault, quote_mint), false),
+            AccountMeta::new(get_associated_token_address(issuer, quote_mint), false),
+        ]);
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: DecenseInstruction::InitializeUser {
+            market_valuation,
+            supply,
+            pricing_curve,
+            curve_parameter,
+            vesting_cliff,
+            vesting_duration,
+            vesting_release_interval,
+            user_treasury_percentage,
+            liquidate_percentage,
+        }
+        .pack(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn exchange(
+    program_id: &Pubkey,
+    exchanger: &Pubkey,
+    issuer: &Pubkey,
+    mint: &Pubkey,
+    platform_state: &Pubkey,
+    fee_recipient: &Pubkey,
+    quote_mint: Option<&Pubkey>,
+    quantity: u64,
+    max_total_cost: u64,
+    min_tokens_out: u64,
+) -> Instruction {
+    let pda = get_listing_pda_address(mint, program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new(*exchanger, true),
+        AccountMeta::new(get_buyer_state_address(mint, exchanger, program_id), false),
+        AccountMeta::new(get_associated_token_address(exchanger, mint), false),
+        AccountMeta::new(*issuer, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(get_user_state_address(mint, program_id), false),
+        AccountMeta::new_readonly(pda, false),
+        AccountMeta::new(get_associated_token_address(&pda, mint), false),
+        AccountMeta::new(get_vault_address(mint, program_id), false),
+        AccountMeta::new_readonly(*platform_state, false),
+        AccountMeta::new(*fee_recipient, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(quote_accounts(
+        program_id,
+        exchanger,
+        mint,
+        fee_recipient,
+        quote_mint,
+    ));
+
+    // spl quoted buys pay the issuer's share into the issuer's quote token account
+    if let Some(quote_mint) = quote_mint {
+        accounts.push(AccountMeta::new(
+            get_associated_token_address(issuer, quote_mint),
+            false,
+        ));
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: DecenseInstruction::Exchange {
+            quantity,
+            max_total_cost,
+            min_tokens_out,
+        }
+        .pack(),
+    }
+}
+
+// action 0 returns tokens to the pool, action 1 sends pool tokens to the exchanger and needs
+// the issuer's signature. the exchanger always signs since it funds its buyer state account
+pub fn send_receive_token(
+    program_id: &Pubkey,
+    issuer: &Pubkey,
+    mint: &Pubkey,
+    exchanger: &Pubkey,
+    platform_state: &Pubkey,
+    action: u64,
+    amount: u64,
+) -> Instruction {
+    let pda = get_listing_pda_address(mint, program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*issuer, action == 1),
+            AccountMeta::new(get_user_state_address(mint, program_id), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*exchanger, true),
+            AccountMeta::new(get_buyer_state_address(mint, exchanger, program_id), false),
+            AccountMeta::new(get_associated_token_address(exchanger, mint), false),
+            AccountMeta::new_readonly(pda, false),
+            AccountMeta::new(get_associated_token_address(&pda, mint), false),
+            AccountMeta::new_readonly(*platform_state, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: DecenseInstruction::SendRecieveToken { action, amount }.pack(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn sell_to_pool(
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    issuer: &Pubkey,
+    mint: &Pubkey,
+    platform_state: &Pubkey,
+    fee_recipient: &Pubkey,
+    quote_mint: Option<&Pubkey>,
+    quantity: u64,
+    min_sol_out: u64,
+) -> Instruction {
+    let pda = get_listing_pda_address(mint, program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new(*seller, true),
+        AccountMeta::new(get_buyer_state_address(mint, seller, program_id), false),
+        AccountMeta::new(get_associated_token_address(seller, mint), false),
+        AccountMeta::new_readonly(*issuer, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(get_user_state_address(mint, program_id), false),
+        AccountMeta::new_readonly(pda, false),
+        AccountMeta::new(get_associated_token_address(&pda, mint), false),
+        AccountMeta::new(get_vault_address(mint, program_id), false),
+        AccountMeta::new_readonly(*platform_state, false),
+        AccountMeta::new(*fee_recipient, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(quote_accounts(
+        program_id,
+        seller,
+        mint,
+        fee_recipient,
+        quote_mint,
+    ));
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: DecenseInstruction::SellToPool {
+            quantity,
+            min_sol_out,
+        }
+        .pack(),
+    }
+}
+
+// trailing accounts settling a pool trade in an spl quote currency
+fn quote_accounts(
+    program_id: &Pubkey,
+    trader: &Pubkey,
+    mint: &Pubkey,
+    fee_recipient: &Pubkey,
+    quote_mint: Option<&Pubkey>,
+) -> Vec<AccountMeta> {
+    let quote_mint = match quote_mint {
+        Some(quote_mint) => quote_mint,
+        None => return vec![],
+    };
+
+    let vault = get_vault_address(mint, program_id);
+
+    vec![
+        AccountMeta::new_readonly(*quote_mint, false),
+        AccountMeta::new(get_associated_token_address(trader, quote_mint), false),
+        AccountMeta::new(get_associated_token_address(&vault, quote_mint), false),
+        AccountMeta::new(
+            get_associated_token_address(fee_recipient, quote_mint),
+            false,
+        ),
+    ]
+}
+
+// accounts shared by the order book instructions, up to and including the ask vault ata
+fn order_book_accounts(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    issuer: &Pubkey,
+    mint: &Pubkey,
+) -> Vec<AccountMeta> {
+    let ask_vault_authority = get_ask_vault_authority_address(mint, program_id);
+
+    vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(get_associated_token_address(owner, mint), false),
+        AccountMeta::new_readonly(*issuer, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(get_user_state_address(mint, program_id), false),
+        AccountMeta::new(get_order_book_address(mint, program_id), false),
+        AccountMeta::new(get_bid_vault_address(mint, program_id), false),
+        AccountMeta::new_readonly(ask_vault_authority, false),
+        AccountMeta::new(
+            get_associated_token_address(&ask_vault_authority, mint),
+            false,
+        ),
+    ]
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn place_order(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    issuer: &Pubkey,
+    mint: &Pubkey,
+    platform_state: &Pubkey,
+    side: u64,
+    price: u64,
+    quantity: u64,
+) -> Instruction {
+    let mut accounts = order_book_accounts(program_id, owner, issuer, mint);
+    accounts.extend([
+        AccountMeta::new(get_buyer_state_address(mint, owner, program_id), false),
+        AccountMeta::new_readonly(*platform_state, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ]);
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: DecenseInstruction::PlaceOrder {
+            side,
+            price,
+            quantity,
+        }
+        .pack(),
+    }
+}
+
+pub fn cancel_order(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    issuer: &Pubkey,
+    mint: &Pubkey,
+    side: u64,
+    sequence: u64,
+) -> Instruction {
+    let mut accounts = order_book_accounts(program_id, owner, issuer, mint);
+    accounts.extend([
+        AccountMeta::new(get_buyer_state_address(mint, owner, program_id), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ]);
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: DecenseInstruction::CancelOrder { side, sequence }.pack(),
+    }
+}
+
+// fills holds the (bidder, asker) wallets of every match the crank expects, in book order
+pub fn match_orders(
+    program_id: &Pubkey,
+    issuer: &Pubkey,
+    mint: &Pubkey,
+    platform_state: &Pubkey,
+    fee_recipient: &Pubkey,
+    fills: &[(Pubkey, Pubkey)],
+) -> Instruction {
+    let ask_vault_authority = get_ask_vault_authority_address(mint, program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*issuer, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(get_user_state_address(mint, program_id), false),
+        AccountMeta::new(get_order_book_address(mint, program_id), false),
+        AccountMeta::new(get_bid_vault_address(mint, program_id), false),
+        AccountMeta::new_readonly(ask_vault_authority, false),
+        AccountMeta::new(
+            get_associated_token_address(&ask_vault_authority, mint),
+            false,
+        ),
+        AccountMeta::new_readonly(*platform_state, false),
+        AccountMeta::new(*fee_recipient, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    for (bidder, asker) in fills {
+        accounts.extend([
+            AccountMeta::new(*bidder, false),
+            AccountMeta::new(get_associated_token_address(bidder, mint), false),
+            AccountMeta::new(get_buyer_state_address(mint, bidder, program_id), false),
+            AccountMeta::new(*asker, false),
+            AccountMeta::new(get_buyer_state_address(mint, asker, program_id), false),
+        ]);
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: DecenseInstruction::MatchOrders {
+            limit: fills.len() as u64,
+        }
+        .pack(),
+    }
+}
+
+pub fn update_platform_config(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    platform_state: &Pubkey,
+    fee_recipient: &Pubkey,
+    listing_fee: u64,
+    buy_fee_bps: u64,
+    sell_fee_bps: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(*platform_state, false),
+            AccountMeta::new_readonly(*fee_recipient, false),
+        ],
+        data: DecenseInstruction::UpdatePlatformConfig {
+            listing_fee,
+            buy_fee_bps,
+            sell_fee_bps,
+        }
+        .pack(),
+    }
+}
+
+pub fn transfer_admin(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    platform_state: &Pubkey,
+    new_admin: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(*platform_state, false),
+            AccountMeta::new_readonly(*new_admin, false),
+        ],
+        data: DecenseInstruction::TransferAdmin.pack(),
+    }
+}
+
+pub fn accept_admin(
+    program_id: &Pubkey,
+    new_admin: &Pubkey,
+    platform_state: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*new_admin, true),
+            AccountMeta::new(*platform_state, false),
+        ],
+        data: DecenseInstruction::AcceptAdmin.pack(),
+    }
+}
+
+// index is the listing's current dividend_count, claims close claim_period seconds after the
+// declaration
+#[allow(clippy::too_many_arguments)]
+pub fn declare_dividend(
+    program_id: &Pubkey,
+    issuer: &Pubkey,
+    mint: &Pubkey,
+    index: u64,
+    quote_mint: Option<&Pubkey>,
+    amount_per_token: u64,
+    eligible_supply: u64,
+    claim_period: u64,
+    snapshot_root: [u8; 32],
+) -> Instruction {
+    let dividend = get_dividend_address(mint, index, program_id);
+    let vesting = get_vesting_address(mint, program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new(*issuer, true),
+        AccountMeta::new(get_user_state_address(mint, program_id), false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(
+            get_associated_token_address(&get_listing_pda_address(mint, program_id), mint),
+            false,
+        ),
+        AccountMeta::new_readonly(vesting, false),
+        AccountMeta::new_readonly(get_associated_token_address(&vesting, mint), false),
+        AccountMeta::new(dividend, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    if let Some(quote_mint) = quote_mint {
+        accounts.extend([
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new(get_associated_token_address(issuer, quote_mint), false),
+            AccountMeta::new(get_associated_token_address(&dividend, quote_mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ]);
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: DecenseInstruction::DeclareDividend {
+            amount_per_token,
+            eligible_supply,
+            claim_period,
+            snapshot_root,
+        }
+        .pack(),
+    }
+}
+
+// amount is the holder's balance in the snapshot, proof its path to the snapshot root
+pub fn claim_dividend(
+    program_id: &Pubkey,
+    holder: &Pubkey,
+    mint: &Pubkey,
+    index: u64,
+    quote_mint: Option<&Pubkey>,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let dividend = get_dividend_address(mint, index, program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new(*holder, true),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(dividend, false),
+        AccountMeta::new(
+            get_dividend_claim_address(&dividend, holder, program_id),
+            false,
+        ),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    if let Some(quote_mint) = quote_mint {
+        accounts.extend([
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new(get_associated_token_address(holder, quote_mint), false),
+            AccountMeta::new(get_associated_token_address(&dividend, quote_mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ]);
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: DecenseInstruction::ClaimDividend { amount, proof }.pack(),
+    }
+}
+
+// closes an expired dividend and returns what holders did not claim to the issuer
+pub fn reclaim_dividend(
+    program_id: &Pubkey,
+    issuer: &Pubkey,
+    mint: &Pubkey,
+    index: u64,
+    quote_mint: Option<&Pubkey>,
+) -> Instruction {
+    let dividend = get_dividend_address(mint, index, program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new(*issuer, true),
+        AccountMeta::new_readonly(get_user_state_address(mint, program_id), false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(dividend, false),
+    ];
+
+    if let Some(quote_mint) = quote_mint {
+        accounts.extend([
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new(get_associated_token_address(issuer, quote_mint), false),
+            AccountMeta::new(get_associated_token_address(&dividend, quote_mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ]);
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: DecenseInstruction::ReclaimDividend.pack(),
+    }
+}
+
+// index is the listing's current proposal_count
+#[allow(clippy::too_many_arguments)]
+pub fn create_proposal(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    issuer: &Pubkey,
+    mint: &Pubkey,
+    index: u64,
+    voting_period: u64,
+    quorum: u64,
+    threshold_bps: u64,
+    description_hash: [u8; 32],
+) -> Instruction {
+    let proposal = get_proposal_address(mint, index, program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*proposer, true),
+            AccountMeta::new_readonly(get_associated_token_address(proposer, mint), false),
+            AccountMeta::new_readonly(*issuer, false),
+            AccountMeta::new(get_user_state_address(mint, program_id), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(proposal, false),
+            AccountMeta::new(get_associated_token_address(&proposal, mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: DecenseInstruction::CreateProposal {
+            voting_period,
+            quorum,
+            threshold_bps,
+            description_hash,
+        }
+        .pack(),
+    }
+}
+
+fn vote_accounts(
+    program_id: &Pubkey,
+    voter: &Pubkey,
+    mint: &Pubkey,
+    index: u64,
+) -> Vec<AccountMeta> {
+    let proposal = get_proposal_address(mint, index, program_id);
+
+    vec![
+        AccountMeta::new(*voter, true),
+        AccountMeta::new(get_associated_token_address(voter, mint), false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(proposal, false),
+        AccountMeta::new(get_associated_token_address(&proposal, mint), false),
+        AccountMeta::new(get_vote_record_address(&proposal, voter, program_id), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ]
+}
+
+// support is 1 to vote in favour and 0 to vote against, amount is locked until voting ends
+pub fn cast_vote(
+    program_id: &Pubkey,
+    voter: &Pubkey,
+    mint: &Pubkey,
+    index: u64,
+    support: u64,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vote_accounts(program_id, voter, mint, index),
+        data: DecenseInstruction::CastVote { support, amount }.pack(),
+    }
+}
+
+pub fn finalize_proposal(program_id: &Pubkey, mint: &Pubkey, index: u64) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(
+            get_proposal_address(mint, index, program_id),
+            false,
+        )],
+        data: DecenseInstruction::FinalizeProposal.pack(),
+    }
+}
+
+pub fn withdraw_vote(
+    program_id: &Pubkey,
+    voter: &Pubkey,
+    mint: &Pubkey,
+    index: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vote_accounts(program_id, voter, mint, index),
+        data: DecenseInstruction::WithdrawVote.pack(),
+    }
+}
+
+pub fn release_vested(program_id: &Pubkey, issuer: &Pubkey, mint: &Pubkey) -> Instruction {
+    let vesting = get_vesting_address(mint, program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*issuer, true),
+            AccountMeta::new(get_associated_token_address(issuer, mint), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(vesting, false),
+            AccountMeta::new(get_associated_token_address(&vesting, mint), false),
+            AccountMeta::new(get_buyer_state_address(mint, issuer, program_id), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: DecenseInstruction::ReleaseVested.pack(),
+    }
+}
+
+// a max_move_bps of 0 turns the breaker off
+pub fn configure_circuit_breaker(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    platform_state: &Pubkey,
+    mint: &Pubkey,
+    max_move_bps: u64,
+    window: u64,
+    cooldown: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(*platform_state, false),
+            AccountMeta::new(get_user_state_address(mint, program_id), false),
+        ],
+        data: DecenseInstruction::ConfigureCircuitBreaker {
+            max_move_bps,
+            window,
+            cooldown,
+        }
+        .pack(),
+    }
+}
+
+// authority is the platform admin or the issuer
+pub fn halt_trading(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    platform_state: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*platform_state, false),
+            AccountMeta::new(get_user_state_address(mint, program_id), false),
+        ],
+        data: DecenseInstruction::HaltTrading.pack(),
+    }
+}
+
+pub fn resume_trading(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    platform_state: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*platform_state, false),
+            AccountMeta::new(get_user_state_address(mint, program_id), false),
+        ],
+        data: DecenseInstruction::ResumeTrading.pack(),
+    }
+}
+
+pub fn pause(program_id: &Pubkey, admin: &Pubkey, platform_state: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(*platform_state, false),
+        ],
+        data: DecenseInstruction::Pause.pack(),
+    }
+}
+
+pub fn unpause(program_id: &Pubkey, admin: &Pubkey, platform_state: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(*platform_state, false),
+        ],
+        data: DecenseInstruction::Unpause.pack(),
+    }
+}
+
+// rewrites the platform state the admin's wallet created under an older program in the current
+// layout at the platform pda
+pub fn migrate_platform_state(program_id: &Pubkey, admin: &Pubkey) -> Instruction {
+    let legacy_platform_state =
+        Pubkey::create_with_seed(admin, "DECENSE PLATFORM", program_id).unwrap();
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(legacy_platform_state, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(get_platform_state_address(program_id), false),
+        ],
+        data: DecenseInstruction::MigrateAccount.pack(),
+    }
+}
+
+// moves a baseline listing to the pdas of its mint, the issuer signs for its old state and
+// its mint and freeze authority
+pub fn migrate_listing(program_id: &Pubkey, issuer: &Pubkey, mint: &Pubkey) -> Instruction {
+    let legacy_user_state = Pubkey::create_with_seed(issuer, "DECENSE USER", program_id).unwrap();
+    let (legacy_pda, _) = Pubkey::find_program_address(&[issuer.as_ref()], program_id);
+    let pda = get_listing_pda_address(mint, program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*issuer, true),
+            AccountMeta::new(legacy_user_state, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(get_user_state_address(mint, program_id), false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(legacy_pda, false),
+            AccountMeta::new(get_associated_token_address(&legacy_pda, mint), false),
+            AccountMeta::new_readonly(pda, false),
+            AccountMeta::new(get_associated_token_address(&pda, mint), false),
+            AccountMeta::new(get_vault_address(mint, program_id), false),
+            AccountMeta::new_readonly(get_associated_token_address(issuer, mint), false),
+            AccountMeta::new(get_buyer_state_address(mint, issuer, program_id), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ],
+        data: DecenseInstruction::MigrateAccount.pack(),
+    }
+}
+
+// moves the position the holder's wallet created under an older program to their position in
+// the listing of mint
+pub fn migrate_position(program_id: &Pubkey, holder: &Pubkey, mint: &Pubkey) -> Instruction {
+    let legacy_buyer_state = Pubkey::create_with_seed(holder, "DECENSE BUYER", program_id).unwrap();
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*holder, true),
+            AccountMeta::new(legacy_buyer_state, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(get_user_state_address(mint, program_id), false),
+            AccountMeta::new(get_buyer_state_address(mint, holder, program_id), false),
+            AccountMeta::new_readonly(get_associated_token_address(holder, mint), false),
+        ],
+        data: DecenseInstruction::MigrateAccount.pack(),
+    }
+}
+
+// amount is in whole tokens and has to be what the passed proposal at index approved
+// the platform admin co-signs every issue, the new shares are minted into the pool
+pub fn issue_new_shares(
+    program_id: &Pubkey,
+    issuer: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    platform_state: &Pubkey,
+    index: u64,
+    amount: u64,
+) -> Instruction {
+    let pda = get_listing_pda_address(mint, program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*issuer, true),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(get_user_state_address(mint, program_id), false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(pda, false),
+            AccountMeta::new(get_associated_token_address(&pda, mint), false),
+            AccountMeta::new(get_proposal_address(mint, index, program_id), false),
+            AccountMeta::new_readonly(*platform_state, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: DecenseInstruction::IssueNewShares { amount }.pack(),
+    }
+}
+
+// index is the listing's current split_count, positions only sell again once they claimed it
+pub fn split(
+    program_id: &Pubkey,
+    issuer: &Pubkey,
+    mint: &Pubkey,
+    platform_state: &Pubkey,
+    index: u64,
+    numerator: u64,
+    denominator: u64,
+) -> Instruction {
+    let pda = get_listing_pda_address(mint, program_id);
+    let split = get_split_address(mint, index, program_id);
+    let vesting = get_vesting_address(mint, program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*issuer, true),
+            AccountMeta::new(get_user_state_address(mint, program_id), false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(pda, false),
+            AccountMeta::new(get_associated_token_address(&pda, mint), false),
+            AccountMeta::new(split, false),
+            AccountMeta::new(get_associated_token_address(&split, mint), false),
+            AccountMeta::new_readonly(get_order_book_address(mint, program_id), false),
+            AccountMeta::new(vesting, false),
+            AccountMeta::new(get_associated_token_address(&vesting, mint), false),
+            AccountMeta::new_readonly(*platform_state, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: DecenseInstruction::Split {
+            numerator,
+            denominator,
+        }
+        .pack(),
+    }
+}
+
+// converts the holder's position, splits are claimed in order of their index
+pub fn claim_split(program_id: &Pubkey, holder: &Pubkey, mint: &Pubkey, index: u64) -> Instruction {
+    let split = get_split_address(mint, index, program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*holder, true),
+            AccountMeta::new(get_associated_token_address(holder, mint), false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(split, false),
+            AccountMeta::new(get_associated_token_address(&split, mint), false),
+            AccountMeta::new(get_buyer_state_address(mint, holder, program_id), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: DecenseInstruction::ClaimSplit.pack(),
+    }
+}
+
+// funds the buyback and fills the best asks at or below max_price, one asker wallet and its
+// position per fill
+#[allow(clippy::too_many_arguments)]
+pub fn buyback(
+    program_id: &Pubkey,
+    issuer: &Pubkey,
+    mint: &Pubkey,
+    platform_state: &Pubkey,
+    budget: u64,
+    max_price: u64,
+    burn: bool,
+    askers: &[Pubkey],
+) -> Instruction {
+    let pda = get_listing_pda_address(mint, program_id);
+    let ask_vault_authority = get_ask_vault_authority_address(mint, program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new(*issuer, true),
+        AccountMeta::new(get_user_state_address(mint, program_id), false),
+        AccountMeta::new(*mint, false),
+        AccountMeta::new(get_associated_token_address(&pda, mint), false),
+        AccountMeta::new(get_buyback_address(mint, program_id), false),
+        AccountMeta::new(get_order_book_address(mint, program_id), false),
+        AccountMeta::new_readonly(ask_vault_authority, false),
+        AccountMeta::new(
+            get_associated_token_address(&ask_vault_authority, mint),
+            false,
+        ),
+        AccountMeta::new_readonly(*platform_state, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    for asker in askers {
+        accounts.extend([
+            AccountMeta::new(*asker, false),
+            AccountMeta::new(get_buyer_state_address(mint, asker, program_id), false),
+        ]);
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: DecenseInstruction::Buyback {
+            budget,
+            max_price,
+            burn: burn as u64,
+        }
+        .pack(),
+    }
+}
+
+pub fn sell_to_buyback(
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    mint: &Pubkey,
+    platform_state: &Pubkey,
+    quantity: u64,
+) -> Instruction {
+    let pda = get_listing_pda_address(mint, program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*seller, true),
+            AccountMeta::new(get_buyer_state_address(mint, seller, program_id), false),
+            AccountMeta::new(get_associated_token_address(seller, mint), false),
+            AccountMeta::new(get_user_state_address(mint, program_id), false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new(get_associated_token_address(&pda, mint), false),
+            AccountMeta::new(get_buyback_address(mint, program_id), false),
+            AccountMeta::new_readonly(*platform_state, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: DecenseInstruction::SellToBuyback { quantity }.pack(),
+    }
+}
+
+pub fn close_buyback(program_id: &Pubkey, issuer: &Pubkey, mint: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*issuer, true),
+            AccountMeta::new_readonly(get_user_state_address(mint, program_id), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(get_buyback_address(mint, program_id), false),
+        ],
+        data: DecenseInstruction::CloseBuyback.pack(),
+    }
+}
+
+// halts the listing for good, authority is the platform admin or the issuer
+pub fn delist(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    platform_state: &Pubkey,
+    mint: &Pubkey,
+    redemption_window: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(*platform_state, false),
+            AccountMeta::new(get_user_state_address(mint, program_id), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(get_settlement_address(mint, program_id), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: DecenseInstruction::Delist { redemption_window }.pack(),
+    }
+}
+
+// the liquidity vault is swept into the settlement on top of amount
+pub fn deposit_settlement(
+    program_id: &Pubkey,
+    issuer: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let pda = get_listing_pda_address(mint, program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*issuer, true),
+            AccountMeta::new_readonly(get_user_state_address(mint, program_id), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(get_associated_token_address(&pda, mint), false),
+            AccountMeta::new(get_vault_address(mint, program_id), false),
+            AccountMeta::new(get_settlement_address(mint, program_id), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: DecenseInstruction::DepositSettlement { amount }.pack(),
+    }
+}
+
+pub fn redeem_settlement(
+    program_id: &Pubkey,
+    holder: &Pubkey,
+    mint: &Pubkey,
+    quantity: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*holder, true),
+            AccountMeta::new(get_associated_token_address(holder, mint), false),
+            AccountMeta::new(get_buyer_state_address(mint, holder, program_id), false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new(get_settlement_address(mint, program_id), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: DecenseInstruction::RedeemSettlement { quantity }.pack(),
+    }
+}
+
+// the issuer receives what is left of the settlement and the rent of the closed accounts
+pub fn close_listing(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    platform_state: &Pubkey,
+    issuer: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let pda = get_listing_pda_address(mint, program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(*platform_state, false),
+            AccountMeta::new(get_user_state_address(mint, program_id), false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(pda, false),
+            AccountMeta::new(get_associated_token_address(&pda, mint), false),
+            AccountMeta::new(get_settlement_address(mint, program_id), false),
+            AccountMeta::new(*issuer, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: DecenseInstruction::CloseListing.pack(),
+    }
+}
+
+// sells part of the pool at a fixed price before trading opens, send it in the listing's
+// transaction so that nothing trades before the offering
+#[allow(clippy::too_many_arguments)]
+pub fn create_offering(
+    program_id: &Pubkey,
+    issuer: &Pubkey,
+    mint: &Pubkey,
+    platform_state: &Pubkey,
+    price: u64,
+    start_time: u64,
+    end_time: u64,
+    per_wallet_max: u64,
+    soft_cap: u64,
+    hard_cap: u64,
+) -> Instruction {
+    let pda = get_listing_pda_address(mint, program_id);
+    let offering = get_offering_address(mint, program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*issuer, true),
+            AccountMeta::new(get_user_state_address(mint, program_id), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(get_associated_token_address(&pda, mint), false),
+            AccountMeta::new(offering, false),
+            AccountMeta::new(get_associated_token_address(&offering, mint), false),
+            AccountMeta::new_readonly(*platform_state, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: DecenseInstruction::CreateOffering {
+            price,
+            start_time,
+            end_time,
+            per_wallet_max,
+            soft_cap,
+            hard_cap,
+        }
+        .pack(),
+    }
+}
+
+pub fn subscribe(
+    program_id: &Pubkey,
+    subscriber: &Pubkey,
+    mint: &Pubkey,
+    platform_state: &Pubkey,
+    quantity: u64,
+) -> Instruction {
+    let offering = get_offering_address(mint, program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*subscriber, true),
+            AccountMeta::new(
+                get_subscription_address(&offering, subscriber, program_id),
+                false,
+            ),
+            AccountMeta::new(offering, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(get_user_state_address(mint, program_id), false),
+            AccountMeta::new_readonly(*platform_state, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: DecenseInstruction::Subscribe { quantity }.pack(),
+    }
+}
+
+// anyone can finalize once the offering ended or sold out
+pub fn finalize_offering(program_id: &Pubkey, issuer: &Pubkey, mint: &Pubkey) -> Instruction {
+    let pda = get_listing_pda_address(mint, program_id);
+    let offering = get_offering_address(mint, program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(get_user_state_address(mint, program_id), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(pda, false),
+            AccountMeta::new(get_associated_token_address(&pda, mint), false),
+            AccountMeta::new(offering, false),
+            AccountMeta::new(get_associated_token_address(&offering, mint), false),
+            AccountMeta::new(*issuer, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: DecenseInstruction::FinalizeOffering.pack(),
+    }
+}
+
+// delivers the subscribed tokens of a successful offering or refunds a failed one
+pub fn claim_offering(program_id: &Pubkey, subscriber: &Pubkey, mint: &Pubkey) -> Instruction {
+    let offering = get_offering_address(mint, program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*subscriber, true),
+            AccountMeta::new(get_buyer_state_address(mint, subscriber, program_id), false),
+            AccountMeta::new(get_associated_token_address(subscriber, mint), false),
+            AccountMeta::new(get_user_state_address(mint, program_id), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(
+                get_subscription_address(&offering, subscriber, program_id),
+                false,
+            ),
+            AccountMeta::new(offering, false),
+            AccountMeta::new(get_associated_token_address(&offering, mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: DecenseInstruction::ClaimOffering.pack(),
+    }
 }
@@ -3,6 +3,8 @@ pub mod entrypoint;
 
 pub mod error;
 pub mod instruction;
+pub mod pricing;
 pub mod processor;
+pub mod snapshot;
 pub mod state;
 pub mod validation;
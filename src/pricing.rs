@@ -0,0 +1,287 @@
+// bonding curves quoting trades against a listing's pool. prices are in lamports per whole token,
+// quantities in base units, and quotes only depend on the pool balance so clients can compute
+// them off-chain
+
+pub const TOKEN_UNIT: u64 = 10000;
+
+const BPS_DENOMINATOR: u128 = 10000;
+
+const FIXED_ONE: u128 = 1_000_000_000_000_000_000;
+
+const LN_2: u128 = 693_147_180_559_945_309;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum CurveKind {
+    // price = base_price * (initial_pool / pool)^2
+    ConstantProduct,
+    // price = base_price + parameter * tokens sold from the pool, never below base_price
+    Linear,
+    // price = base_price * e^(parameter bps * tokens sold from the pool)
+    Exponential,
+}
+
+impl CurveKind {
+    pub fn from_u8(kind: u8) -> Option<Self> {
+        match kind {
+            0 => Some(CurveKind::ConstantProduct),
+            1 => Some(CurveKind::Linear),
+            2 => Some(CurveKind::Exponential),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct PricingCurve {
+    pub kind: CurveKind,
+    pub parameter: u64,
+    pub base_price: u64,
+    pub initial_pool: u64,
+}
+
+impl PricingCurve {
+    pub fn spot_price(&self, pool: u64) -> Option<u64> {
+        let price = match self.kind {
+            CurveKind::ConstantProduct => mul_div(
+                self.base_price as u128 * self.initial_pool as u128,
+                self.initial_pool as u128,
+                pool as u128 * pool as u128,
+                false,
+            )?,
+            CurveKind::Linear => {
+                let sold = self.initial_pool.saturating_sub(pool) as u128;
+                (self.parameter as u128)
+                    .checked_mul(sold)?
+                    .checked_div(TOKEN_UNIT as u128)?
+                    .checked_add(self.base_price as u128)?
+            }
+            CurveKind::Exponential => mul_div(
+                self.base_price as u128,
+                exp_fixed(self.exponent(pool)?)?,
+                FIXED_ONE,
+                false,
+            )?,
+        };
+
+        u64::try_from(price).ok()
+    }
+
+    // the curve after every balance was multiplied by numerator / denominator, so prices per
+    // whole token move by the inverse ratio; None when the parameter does not scale exactly
+    pub fn split(&self, numerator: u64, denominator: u64) -> Option<Self> {
+        let (numerator, denominator) = (numerator as u128, denominator as u128);
+
+        // the linear slope is per whole token sold, so it scales with the price and the count
+        let (parameter_numerator, parameter_denominator) = match self.kind {
+            CurveKind::ConstantProduct => (1, 1),
+            CurveKind::Linear => (denominator * denominator, numerator * numerator),
+            CurveKind::Exponential => (denominator, numerator),
+        };
+
+        let parameter = (self.parameter as u128).checked_mul(parameter_numerator)?;
+
+        if parameter % parameter_denominator != 0 {
+            return None;
+        }
+
+        Some(PricingCurve {
+            kind: self.kind,
+            parameter: u64::try_from(parameter / parameter_denominator).ok()?,
+            base_price: u64::try_from(mul_div(
+                self.base_price as u128,
+                denominator,
+                numerator,
+                false,
+            )?)
+            .ok()?,
+            initial_pool: u64::try_from(mul_div(
+                self.initial_pool as u128,
+                numerator,
+                denominator,
+                false,
+            )?)
+            .ok()?,
+        })
+    }
+
+    // lamports a buyer pays to take quantity out of the pool, rounded up
+    pub fn quote_buy(&self, pool: u64, quantity: u64) -> Option<u64> {
+        self.area(pool.checked_sub(quantity)?, pool, true)
+    }
+
+    // lamports a seller receives for putting quantity into the pool, rounded down
+    pub fn quote_sell(&self, pool: u64, quantity: u64) -> Option<u64> {
+        self.area(pool, pool.checked_add(quantity)?, false)
+    }
+
+    // lamports under the price curve between two pool balances
+    fn area(&self, low: u64, high: u64, round_up: bool) -> Option<u64> {
+        if low == high {
+            return Some(0);
+        }
+
+        let area = match self.kind {
+            CurveKind::ConstantProduct => {
+                if low == 0 {
+                    return None;
+                }
+
+                // base_price * initial_pool^2 * (1 / low - 1 / high) / TOKEN_UNIT, dividing in two
+                // steps as low * high * TOKEN_UNIT outgrows u128 for large pools
+                let area = mul_div(
+                    self.base_price as u128 * self.initial_pool as u128,
+                    self.initial_pool as u128 * (high - low) as u128,
+                    low as u128 * high as u128,
+                    round_up,
+                )?;
+
+                div_rounding(area, TOKEN_UNIT as u128, round_up)?
+            }
+            CurveKind::Linear => {
+                let numerator = self
+                    .linear_antiderivative(low)?
+                    .checked_sub(self.linear_antiderivative(high)?)?;
+
+                div_rounding(
+                    u128::try_from(numerator).ok()?,
+                    2 * TOKEN_UNIT as u128 * TOKEN_UNIT as u128,
+                    round_up,
+                )?
+            }
+            CurveKind::Exponential => {
+                if self.parameter == 0 {
+                    return u64::try_from(mul_div(
+                        self.base_price as u128,
+                        (high - low) as u128,
+                        TOKEN_UNIT as u128,
+                        round_up,
+                    )?)
+                    .ok();
+                }
+
+                // base_price * BPS_DENOMINATOR / parameter * (e^x(low) - e^x(high))
+                let growth = exp_fixed(self.exponent(low)?)?
+                    .checked_sub(exp_fixed(self.exponent(high)?)?)?;
+
+                mul_div(
+                    self.base_price as u128 * BPS_DENOMINATOR,
+                    growth,
+                    self.parameter as u128 * FIXED_ONE,
+                    round_up,
+                )?
+            }
+        };
+
+        u64::try_from(area).ok()
+    }
+
+    // area between the initial pool and pool scaled by 2 * TOKEN_UNIT^2, negative once the
+    // pool holds more than it started with
+    fn linear_antiderivative(&self, pool: u64) -> Option<i128> {
+        let sold = self.initial_pool as i128 - pool as i128;
+        let base_area = (2 * TOKEN_UNIT as i128 * self.base_price as i128).checked_mul(sold)?;
+
+        if sold <= 0 {
+            return Some(base_area);
+        }
+
+        base_area.checked_add((self.parameter as i128).checked_mul(sold.checked_mul(sold)?)?)
+    }
+
+    // parameter bps per whole token sold, as a signed fixed point exponent
+    fn exponent(&self, pool: u64) -> Option<i128> {
+        let sold = self.initial_pool as i128 - pool as i128;
+
+        (self.parameter as i128)
+            .checked_mul(sold)?
+            .checked_mul(FIXED_ONE as i128 / (BPS_DENOMINATOR as i128 * TOKEN_UNIT as i128))
+    }
+}
+
+fn div_rounding(numerator: u128, denominator: u128, round_up: bool) -> Option<u128> {
+    let quotient = numerator.checked_div(denominator)?;
+
+    if round_up && quotient * denominator < numerator {
+        return quotient.checked_add(1);
+    }
+
+    Some(quotient)
+}
+
+// a * b / denominator with a 256 bit intermediate product
+fn mul_div(a: u128, b: u128, denominator: u128, round_up: bool) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+
+    const MASK: u128 = u64::MAX as u128;
+
+    let (a_high, a_low) = (a >> 64, a & MASK);
+    let (b_high, b_low) = (b >> 64, b & MASK);
+
+    let low_low = a_low * b_low;
+    let high_low = a_high * b_low;
+    let low_high = a_low * b_high;
+    let high_high = a_high * b_high;
+
+    let middle = (low_low >> 64) + (high_low & MASK) + (low_high & MASK);
+    let low = (middle << 64) | (low_low & MASK);
+    let high = high_high + (high_low >> 64) + (low_high >> 64) + (middle >> 64);
+
+    if high >= denominator {
+        return None;
+    }
+
+    let mut remainder = high;
+    let mut quotient = 0u128;
+
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        quotient <<= 1;
+
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1;
+        }
+    }
+
+    if round_up && remainder != 0 {
+        return quotient.checked_add(1);
+    }
+
+    Some(quotient)
+}
+
+// e^x for a fixed point x, computed as 2^n * e^r with r in [0, ln 2)
+fn exp_fixed(x: i128) -> Option<u128> {
+    if x < 0 {
+        let inverse = exp_fixed(x.checked_neg()?);
+
+        return Some(match inverse {
+            Some(inverse) => FIXED_ONE * FIXED_ONE / inverse,
+            None => 0,
+        });
+    }
+
+    let x = x as u128;
+    let doublings = x / LN_2;
+    let remainder = x % LN_2;
+
+    let mut term = FIXED_ONE;
+    let mut sum = FIXED_ONE;
+
+    for k in 1..=24u128 {
+        term = term * remainder / FIXED_ONE / k;
+        if term == 0 {
+            break;
+        }
+        sum += term;
+    }
+
+    if doublings >= 128 || sum.leading_zeros() as u128 <= doublings {
+        return None;
+    }
+
+    Some(sum << doublings)
+}
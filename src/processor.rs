@@ -7,12 +7,26 @@ use solana_program::{
     program_pack::Pack,
     pubkey::Pubkey,
     system_instruction,
-    sysvar::rent::Rent,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
 };
 
-use spl_associated_token_account::instruction::create_associated_token_account;
+use spl_associated_token_account::instruction::{
+    create_associated_token_account, create_associated_token_account_idempotent,
+};
 
-use crate::state::{PlatformState, UserState};
+use crate::pricing::CurveKind;
+use crate::snapshot;
+use crate::state::{
+    Buyback, Dividend, DividendClaim, HaltReason, Offering, OfferingStatus, Order, OrderBook,
+    PlatformState, Proposal, ProposalStatus, Settlement, Split, Subscription, UserState, Versioned,
+    Vesting, VoteRecord, MAX_ISSUE_BPS, MAX_TOTAL_ISSUE_BPS,
+};
+use crate::validation::{
+    assert_associated_token_account, assert_associated_token_program, assert_keys_equal,
+    assert_mint_authority, assert_not_paused, assert_owned_by, assert_pda, assert_platform_admin,
+    assert_platform_state, assert_rent_sysvar, assert_signer, assert_system_program,
+    assert_token_program, assert_writable,
+};
 use crate::{error::DecenseError, instruction::DecenseInstruction, state::BuyerState};
 
 pub struct Processor;
@@ -32,23 +46,287 @@ impl Processor {
             DecenseInstruction::InitializeUser {
                 market_valuation,
                 supply,
+                pricing_curve,
+                curve_parameter,
+                vesting_cliff,
+                vesting_duration,
+                vesting_release_interval,
+                user_treasury_percentage,
+                liquidate_percentage,
             } => {
                 msg!("Instruction: InitializeUser");
-                Self::process_initialize_user(program_id, accounts, market_valuation, supply)?;
+                Self::process_initialize_user(
+                    program_id,
+                    accounts,
+                    market_valuation,
+                    supply,
+                    pricing_curve,
+                    curve_parameter,
+                    vesting_cliff,
+                    vesting_duration,
+                    vesting_release_interval,
+                    user_treasury_percentage,
+                    liquidate_percentage,
+                )?;
             }
 
             DecenseInstruction::Exchange {
-                asked_price,
                 quantity,
+                max_total_cost,
+                min_tokens_out,
             } => {
                 msg!("Instruction: Exchange");
-                Self::process_exchange(program_id, accounts, asked_price, quantity)?;
+                Self::process_exchange(
+                    program_id,
+                    accounts,
+                    quantity,
+                    max_total_cost,
+                    min_tokens_out,
+                )?;
             }
 
             DecenseInstruction::SendRecieveToken { action, amount } => {
                 msg!("Instruction: SendRecieveToken");
                 Self::process_send_receive_tokens(program_id, accounts, action, amount)?;
             }
+
+            DecenseInstruction::SellToPool {
+                quantity,
+                min_sol_out,
+            } => {
+                msg!("Instruction: SellToPool");
+                Self::process_sell_to_pool(program_id, accounts, quantity, min_sol_out)?;
+            }
+
+            DecenseInstruction::PlaceOrder {
+                side,
+                price,
+                quantity,
+            } => {
+                msg!("Instruction: PlaceOrder");
+                Self::process_place_order(program_id, accounts, side, price, quantity)?;
+            }
+
+            DecenseInstruction::CancelOrder { side, sequence } => {
+                msg!("Instruction: CancelOrder");
+                Self::process_cancel_order(program_id, accounts, side, sequence)?;
+            }
+
+            DecenseInstruction::MatchOrders { limit } => {
+                msg!("Instruction: MatchOrders");
+                Self::process_match_orders(program_id, accounts, limit)?;
+            }
+
+            DecenseInstruction::UpdatePlatformConfig {
+                listing_fee,
+                buy_fee_bps,
+                sell_fee_bps,
+            } => {
+                msg!("Instruction: UpdatePlatformConfig");
+                Self::process_update_platform_config(
+                    program_id,
+                    accounts,
+                    listing_fee,
+                    buy_fee_bps,
+                    sell_fee_bps,
+                )?;
+            }
+
+            DecenseInstruction::TransferAdmin => {
+                msg!("Instruction: TransferAdmin");
+                Self::process_transfer_admin(program_id, accounts)?;
+            }
+
+            DecenseInstruction::AcceptAdmin => {
+                msg!("Instruction: AcceptAdmin");
+                Self::process_accept_admin(program_id, accounts)?;
+            }
+
+            DecenseInstruction::DeclareDividend {
+                amount_per_token,
+                eligible_supply,
+                claim_period,
+                snapshot_root,
+            } => {
+                msg!("Instruction: DeclareDividend");
+                Self::process_declare_dividend(
+                    program_id,
+                    accounts,
+                    amount_per_token,
+                    eligible_supply,
+                    claim_period,
+                    snapshot_root,
+                )?;
+            }
+
+            DecenseInstruction::ClaimDividend { amount, proof } => {
+                msg!("Instruction: ClaimDividend");
+                Self::process_claim_dividend(program_id, accounts, amount, &proof)?;
+            }
+
+            DecenseInstruction::CreateProposal {
+                voting_period,
+                quorum,
+                threshold_bps,
+                description_hash,
+            } => {
+                msg!("Instruction: CreateProposal");
+                Self::process_create_proposal(
+                    program_id,
+                    accounts,
+                    voting_period,
+                    quorum,
+                    threshold_bps,
+                    description_hash,
+                )?;
+            }
+
+            DecenseInstruction::CastVote { support, amount } => {
+                msg!("Instruction: CastVote");
+                Self::process_cast_vote(program_id, accounts, support, amount)?;
+            }
+
+            DecenseInstruction::FinalizeProposal => {
+                msg!("Instruction: FinalizeProposal");
+                Self::process_finalize_proposal(program_id, accounts)?;
+            }
+
+            DecenseInstruction::WithdrawVote => {
+                msg!("Instruction: WithdrawVote");
+                Self::process_withdraw_vote(program_id, accounts)?;
+            }
+
+            DecenseInstruction::ReleaseVested => {
+                msg!("Instruction: ReleaseVested");
+                Self::process_release_vested(program_id, accounts)?;
+            }
+
+            DecenseInstruction::ConfigureCircuitBreaker {
+                max_move_bps,
+                window,
+                cooldown,
+            } => {
+                msg!("Instruction: ConfigureCircuitBreaker");
+                Self::process_configure_circuit_breaker(
+                    program_id,
+                    accounts,
+                    max_move_bps,
+                    window,
+                    cooldown,
+                )?;
+            }
+
+            DecenseInstruction::HaltTrading => {
+                msg!("Instruction: HaltTrading");
+                Self::process_halt_trading(program_id, accounts)?;
+            }
+
+            DecenseInstruction::ResumeTrading => {
+                msg!("Instruction: ResumeTrading");
+                Self::process_resume_trading(program_id, accounts)?;
+            }
+
+            DecenseInstruction::Pause => {
+                msg!("Instruction: Pause");
+                Self::process_set_paused(program_id, accounts, true)?;
+            }
+
+            DecenseInstruction::Unpause => {
+                msg!("Instruction: Unpause");
+                Self::process_set_paused(program_id, accounts, false)?;
+            }
+
+            DecenseInstruction::MigrateAccount => {
+                msg!("Instruction: MigrateAccount");
+                Self::process_migrate_account(program_id, accounts)?;
+            }
+
+            DecenseInstruction::IssueNewShares { amount } => {
+                msg!("Instruction: IssueNewShares");
+                Self::process_issue_new_shares(program_id, accounts, amount)?;
+            }
+
+            DecenseInstruction::Split {
+                numerator,
+                denominator,
+            } => {
+                msg!("Instruction: Split");
+                Self::process_split(program_id, accounts, numerator, denominator)?;
+            }
+
+            DecenseInstruction::ClaimSplit => {
+                msg!("Instruction: ClaimSplit");
+                Self::process_claim_split(program_id, accounts)?;
+            }
+            DecenseInstruction::Buyback {
+                budget,
+                max_price,
+                burn,
+            } => {
+                msg!("Instruction: Buyback");
+                Self::process_buyback(program_id, accounts, budget, max_price, burn)?;
+            }
+            DecenseInstruction::SellToBuyback { quantity } => {
+                msg!("Instruction: SellToBuyback");
+                Self::process_sell_to_buyback(program_id, accounts, quantity)?;
+            }
+            DecenseInstruction::CloseBuyback => {
+                msg!("Instruction: CloseBuyback");
+                Self::process_close_buyback(program_id, accounts)?;
+            }
+            DecenseInstruction::Delist { redemption_window } => {
+                msg!("Instruction: Delist");
+                Self::process_delist(program_id, accounts, redemption_window)?;
+            }
+            DecenseInstruction::DepositSettlement { amount } => {
+                msg!("Instruction: DepositSettlement");
+                Self::process_deposit_settlement(program_id, accounts, amount)?;
+            }
+            DecenseInstruction::RedeemSettlement { quantity } => {
+                msg!("Instruction: RedeemSettlement");
+                Self::process_redeem_settlement(program_id, accounts, quantity)?;
+            }
+            DecenseInstruction::CloseListing => {
+                msg!("Instruction: CloseListing");
+                Self::process_close_listing(program_id, accounts)?;
+            }
+            DecenseInstruction::CreateOffering {
+                price,
+                start_time,
+                end_time,
+                per_wallet_max,
+                soft_cap,
+                hard_cap,
+            } => {
+                msg!("Instruction: CreateOffering");
+                Self::process_create_offering(
+                    program_id,
+                    accounts,
+                    price,
+                    start_time,
+                    end_time,
+                    per_wallet_max,
+                    soft_cap,
+                    hard_cap,
+                )?;
+            }
+            DecenseInstruction::Subscribe { quantity } => {
+                msg!("Instruction: Subscribe");
+                Self::process_subscribe(program_id, accounts, quantity)?;
+            }
+            DecenseInstruction::FinalizeOffering => {
+                msg!("Instruction: FinalizeOffering");
+                Self::process_finalize_offering(program_id, accounts)?;
+            }
+            DecenseInstruction::ClaimOffering => {
+                msg!("Instruction: ClaimOffering");
+                Self::process_claim_offering(program_id, accounts)?;
+            }
+
+            DecenseInstruction::ReclaimDividend => {
+                msg!("Instruction: ReclaimDividend");
+                Self::process_reclaim_dividend(program_id, accounts)?;
+            }
         }
 
         Ok(())
@@ -65,32 +343,47 @@ impl Processor {
 
         let system_program_account = next_account_info(account_info_iter)?;
 
+        assert_signer(admin_account)?;
+        assert_writable(platform_state_account)?;
+        assert_system_program(system_program_account)?;
+
+        let platform_state_bump_seeds =
+            assert_pda(platform_state_account, &[b"platform"], program_id)?;
+
         if platform_state_account.data_is_empty() {
-            let create_platform_state_account_ix = system_instruction::create_account_with_seed(
+            let create_platform_state_account_ix = system_instruction::create_account(
                 admin_account.key,
                 platform_state_account.key,
-                admin_account.key,
-                "DECENSE PLATFORM",
                 Rent::default().minimum_balance(PlatformState::LEN),
                 PlatformState::LEN as u64,
                 program_id,
             );
 
-            invoke(
+            invoke_signed(
                 &create_platform_state_account_ix,
                 &[
                     admin_account.clone(),
                     platform_state_account.clone(),
                     system_program_account.clone(),
                 ],
+                &[&[b"platform", &[platform_state_bump_seeds]]],
             )?;
         }
 
+        assert_platform_state(platform_state_account, program_id)?;
+
         let mut unpacked_platform_state_account =
             PlatformState::unpack_unchecked(&platform_state_account.try_borrow_data()?)?;
 
+        if unpacked_platform_state_account.is_initialized {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
         unpacked_platform_state_account.is_initialized = true;
+        unpacked_platform_state_account.admin = *admin_account.key;
         unpacked_platform_state_account.platform_treasury_sol_wallet = *sol_treasury_wallet.key;
+        unpacked_platform_state_account.listing_fee = 1000000000;
+        unpacked_platform_state_account.fee_recipient = *sol_treasury_wallet.key;
 
         PlatformState::pack(
             unpacked_platform_state_account,
@@ -100,11 +393,19 @@ impl Processor {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn process_initialize_user(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
         market_valuation: u64,
         supply: u64,
+        pricing_curve: u64,
+        curve_parameter: u64,
+        vesting_cliff: u64,
+        vesting_duration: u64,
+        vesting_release_interval: u64,
+        user_treasury_percentage: u64,
+        liquidate_percentage: u64,
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
 
@@ -124,6 +425,10 @@ impl Processor {
 
         let pda_ata = next_account_info(account_info_iter)?;
 
+        let vault_account = next_account_info(account_info_iter)?;
+
+        let user_buyer_state = next_account_info(account_info_iter)?;
+
         let token_program_account = next_account_info(account_info_iter)?;
 
         let rent_sysvar_account = next_account_info(account_info_iter)?;
@@ -132,20 +437,80 @@ impl Processor {
 
         let system_program_account = next_account_info(account_info_iter)?;
 
+        assert_signer(user_account)?;
+        assert_writable(user_state_account)?;
+        assert_writable(user_buyer_state)?;
+        assert_owned_by(user_mint, &spl_token::id())?;
+        assert_platform_state(platform_state_account, program_id)?;
+        let bump_seeds = assert_pda(pda_account, &[user_mint.key.as_ref()], program_id)?;
+        let user_state_bump_seeds = assert_pda(
+            user_state_account,
+            &[user_mint.key.as_ref(), b"user_state"],
+            program_id,
+        )?;
+        assert_associated_token_account(user_ata, user_account.key, user_mint.key)?;
+        assert_associated_token_account(pda_ata, pda_account.key, user_mint.key)?;
+        assert_pda(
+            vault_account,
+            &[user_mint.key.as_ref(), b"vault"],
+            program_id,
+        )?;
+        let user_buyer_state_bump_seeds = assert_pda(
+            user_buyer_state,
+            &[user_mint.key.as_ref(), user_account.key.as_ref()],
+            program_id,
+        )?;
+        assert_token_program(token_program_account)?;
+        assert_rent_sysvar(rent_sysvar_account)?;
+        assert_associated_token_program(associated_token_account_program_account)?;
+        assert_system_program(system_program_account)?;
+
+        let pricing_curve = u8::try_from(pricing_curve)
+            .ok()
+            .filter(|kind| CurveKind::from_u8(*kind).is_some())
+            .ok_or(DecenseError::InvalidPricingCurve)?;
+
+        // a zero duration leaves the issuer's share liquid and takes no other parameter
+        let is_vested = vesting_duration > 0;
+        let is_valid_schedule = if is_vested {
+            vesting_release_interval > 0
+                && vesting_release_interval <= vesting_duration
+                && vesting_cliff <= vesting_duration
+                && i64::try_from(vesting_duration).is_ok()
+        } else {
+            vesting_cliff == 0 && vesting_release_interval == 0
+        };
+
+        if !is_valid_schedule {
+            return Err(DecenseError::InvalidVestingSchedule.into());
+        }
+
+        if user_treasury_percentage > 100 || liquidate_percentage > 100 {
+            return Err(DecenseError::InvalidPercentage.into());
+        }
+
+        // user_treasury_percentage of the supply stays with the issuer, the rest goes to the pool
+        let token_amount = supply.checked_mul(10000).ok_or(DecenseError::MathError)?;
+        let user_token_amount =
+            (token_amount as u128 * user_treasury_percentage as u128 / 100) as u64;
+        let pool_token_amount = token_amount - user_token_amount;
+
         let unpacked_platform_state_account =
             PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
 
+        assert_not_paused(&unpacked_platform_state_account)?;
+
         if unpacked_platform_state_account.platform_treasury_sol_wallet
             != *platform_sol_treasury_wallet_account.key
         {
-            return Err(ProgramError::Custom(22));
+            return Err(DecenseError::InvalidTreasuryWallet.into());
         }
 
-        // transfer 1 sol from user to platform as a part of initialization fees
+        // transfer the listing fee from user to platform as a part of initialization fees
         let transfer_initialize_amount_to_platform_ix = system_instruction::transfer(
             user_account.key,
             platform_sol_treasury_wallet_account.key,
-            1000000000,
+            unpacked_platform_state_account.listing_fee,
         );
 
         invoke(
@@ -159,46 +524,49 @@ impl Processor {
         )?;
 
         // create user state account
-        let create_user_state_account_ix = system_instruction::create_account_with_seed(
+        let create_user_state_account_ix = system_instruction::create_account(
             user_account.key,
             user_state_account.key,
-            user_account.key,
-            "DECENSE USER",
             Rent::default().minimum_balance(UserState::LEN),
             UserState::LEN as u64,
             program_id,
         );
 
-        invoke(
+        invoke_signed(
             &create_user_state_account_ix,
             &[
                 user_account.clone(),
                 user_state_account.clone(),
                 system_program_account.clone(),
             ],
+            &[&[
+                user_mint.key.as_ref(),
+                b"user_state",
+                &[user_state_bump_seeds],
+            ]],
         )?;
 
-        // initialize user mint
+        // initialize user mint, only the listing pda can mint more and nobody can freeze holders
         let initialize_mint_ix = spl_token::instruction::initialize_mint2(
             &spl_token::id(),
             user_mint.key,
-            user_account.key,
-            Some(user_account.key),
+            pda_account.key,
+            None,
             4,
         )?;
 
         invoke(
             &initialize_mint_ix,
-            &[
-                user_mint.clone(),
-                user_account.clone(),
-                token_program_account.clone(),
-            ],
+            &[user_mint.clone(), token_program_account.clone()],
         )?;
 
         // create mint ata for user
-        let create_user_ata_ix =
-            create_associated_token_account(user_account.key, user_account.key, user_mint.key);
+        let create_user_ata_ix = create_associated_token_account(
+            user_account.key,
+            user_account.key,
+            user_mint.key,
+            &spl_token::id(),
+        );
 
         invoke(
             &create_user_ata_ix,
@@ -215,8 +583,12 @@ impl Processor {
         )?;
 
         // create mint ata for pda
-        let create_user_ata_ix =
-            create_associated_token_account(user_account.key, pda_ata.key, user_mint.key);
+        let create_user_ata_ix = create_associated_token_account(
+            user_account.key,
+            pda_account.key,
+            user_mint.key,
+            &spl_token::id(),
+        );
 
         invoke(
             &create_user_ata_ix,
@@ -237,23 +609,24 @@ impl Processor {
             &spl_token::id(),
             user_mint.key,
             user_ata.key,
-            user_account.key,
+            pda_account.key,
             &[],
-            supply * 10000,
+            token_amount,
             4,
         )?;
 
-        invoke(
+        invoke_signed(
             &mint_tokens_ix,
             &[
                 user_mint.clone(),
-                user_account.clone(),
                 user_ata.clone(),
+                pda_account.clone(),
                 token_program_account.clone(),
             ],
+            &[&[user_mint.key.as_ref(), &[bump_seeds]]],
         )?;
 
-        // transfer 50% amount of token mint to pda ata
+        // transfer the pool's share of the token mint to pda ata
         let transfer_tokens_to_pda_ata_ix = spl_token::instruction::transfer_checked(
             &spl_token::id(),
             user_ata.key,
@@ -261,7 +634,7 @@ impl Processor {
             pda_ata.key,
             user_account.key,
             &[],
-            (supply / 2) * 10000,
+            pool_token_amount,
             4,
         )?;
 
@@ -276,11 +649,226 @@ impl Processor {
             ],
         )?;
 
+        // the issuer's position holds the share it keeps at no cost, a vested share joins it as
+        // it is released
+        let create_user_buyer_state_ix = system_instruction::create_account(
+            user_account.key,
+            user_buyer_state.key,
+            Rent::default().minimum_balance(BuyerState::LEN),
+            BuyerState::LEN as u64,
+            program_id,
+        );
+
+        invoke_signed(
+            &create_user_buyer_state_ix,
+            &[
+                user_account.clone(),
+                user_buyer_state.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[
+                user_mint.key.as_ref(),
+                user_account.key.as_ref(),
+                &[user_buyer_state_bump_seeds],
+            ]],
+        )?;
+
+        let mut unpacked_user_buyer_state =
+            BuyerState::unpack_unchecked(&user_buyer_state.try_borrow_data()?)?;
+
+        unpacked_user_buyer_state.is_initialized = true;
+        unpacked_user_buyer_state.buyer = *user_account.key;
+        unpacked_user_buyer_state.mint = *user_mint.key;
+        if !is_vested {
+            unpacked_user_buyer_state.current_holding_in_tokens = user_token_amount;
+        }
+
+        BuyerState::pack(
+            unpacked_user_buyer_state,
+            &mut user_buyer_state.try_borrow_mut_data()?,
+        )?;
+
+        // vested listings pass the vesting pda and its token account after the system program,
+        // the issuer's share is moved there instead of staying in user_ata
+        if is_vested {
+            let vesting_account = next_account_info(account_info_iter)?;
+
+            let vesting_ata = next_account_info(account_info_iter)?;
+
+            assert_writable(vesting_account)?;
+            let vesting_bump_seeds = assert_pda(
+                vesting_account,
+                &[user_mint.key.as_ref(), b"vesting"],
+                program_id,
+            )?;
+            assert_associated_token_account(vesting_ata, vesting_account.key, user_mint.key)?;
+
+            // create vesting account
+            let create_vesting_account_ix = system_instruction::create_account(
+                user_account.key,
+                vesting_account.key,
+                Rent::default().minimum_balance(Vesting::LEN),
+                Vesting::LEN as u64,
+                program_id,
+            );
+
+            invoke_signed(
+                &create_vesting_account_ix,
+                &[
+                    user_account.clone(),
+                    vesting_account.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[user_mint.key.as_ref(), b"vesting", &[vesting_bump_seeds]]],
+            )?;
+
+            // create mint ata for the vesting pda
+            let create_vesting_ata_ix = create_associated_token_account(
+                user_account.key,
+                vesting_account.key,
+                user_mint.key,
+                &spl_token::id(),
+            );
+
+            invoke(
+                &create_vesting_ata_ix,
+                &[
+                    user_account.clone(),
+                    vesting_ata.clone(),
+                    vesting_account.clone(),
+                    user_mint.clone(),
+                    system_program_account.clone(),
+                    token_program_account.clone(),
+                    rent_sysvar_account.clone(),
+                    associated_token_account_program_account.clone(),
+                ],
+            )?;
+
+            Self::transfer_tokens(
+                user_ata,
+                user_mint,
+                vesting_ata,
+                user_account,
+                token_program_account,
+                user_token_amount,
+                &[],
+            )?;
+
+            Vesting::pack(
+                Vesting {
+                    is_initialized: true,
+                    user_token_mint: *user_mint.key,
+                    beneficiary: *user_account.key,
+                    start_time: Clock::get()?.unix_timestamp,
+                    cliff: vesting_cliff,
+                    duration: vesting_duration,
+                    release_interval: vesting_release_interval,
+                    total_amount: user_token_amount,
+                    released_amount: 0,
+                },
+                &mut vesting_account.try_borrow_mut_data()?,
+            )?;
+        }
+
+        let mut quote_mint = Pubkey::default();
+        let mut quote_unit: u64 = 1000000000;
+
+        // listings quoted in an spl token pass the quote mint, the vault's and the issuer's quote
+        // token accounts last
+        if let Some(quote_mint_account) = account_info_iter.next() {
+            let quote_vault_account = next_account_info(account_info_iter)?;
+
+            let user_quote_account = next_account_info(account_info_iter)?;
+
+            assert_owned_by(quote_mint_account, &spl_token::id())?;
+            assert_associated_token_account(
+                quote_vault_account,
+                vault_account.key,
+                quote_mint_account.key,
+            )?;
+            assert_associated_token_account(
+                user_quote_account,
+                user_account.key,
+                quote_mint_account.key,
+            )?;
+
+            // create quote token account for the vault
+            let create_quote_vault_ix = create_associated_token_account(
+                user_account.key,
+                vault_account.key,
+                quote_mint_account.key,
+                &spl_token::id(),
+            );
+
+            invoke(
+                &create_quote_vault_ix,
+                &[
+                    user_account.clone(),
+                    quote_vault_account.clone(),
+                    vault_account.clone(),
+                    quote_mint_account.clone(),
+                    system_program_account.clone(),
+                    token_program_account.clone(),
+                    rent_sysvar_account.clone(),
+                    associated_token_account_program_account.clone(),
+                ],
+            )?;
+
+            // the issuer's share of buys is paid here, it may exist already
+            let create_user_quote_account_ix = create_associated_token_account_idempotent(
+                user_account.key,
+                user_account.key,
+                quote_mint_account.key,
+                &spl_token::id(),
+            );
+
+            invoke(
+                &create_user_quote_account_ix,
+                &[
+                    user_account.clone(),
+                    user_quote_account.clone(),
+                    user_account.clone(),
+                    quote_mint_account.clone(),
+                    system_program_account.clone(),
+                    token_program_account.clone(),
+                    rent_sysvar_account.clone(),
+                    associated_token_account_program_account.clone(),
+                ],
+            )?;
+
+            let unpacked_quote_mint =
+                spl_token::state::Mint::unpack(&quote_mint_account.try_borrow_data()?)?;
+
+            quote_mint = *quote_mint_account.key;
+            quote_unit = 10u64
+                .checked_pow(unpacked_quote_mint.decimals as u32)
+                .ok_or(DecenseError::MathError)?;
+        } else {
+            // keep the lamport vault rent exempt so any share of a buy can be deposited into it
+            let vault_rent = Rent::default()
+                .minimum_balance(0)
+                .saturating_sub(vault_account.lamports());
+
+            if vault_rent > 0 {
+                let fund_vault_ix =
+                    system_instruction::transfer(user_account.key, vault_account.key, vault_rent);
+
+                invoke(
+                    &fund_vault_ix,
+                    &[
+                        user_account.clone(),
+                        vault_account.clone(),
+                        system_program_account.clone(),
+                    ],
+                )?;
+            }
+        }
+
         let mut unpacked_user_state_account =
-            UserState::unpack(&user_state_account.try_borrow_data()?)?;
+            UserState::unpack_unchecked(&user_state_account.try_borrow_data()?)?;
 
         unpacked_user_state_account.is_initialized = true;
-        unpacked_user_state_account.user_treasury_percentage = 50;
+        unpacked_user_state_account.user_treasury_percentage = user_treasury_percentage as u8;
         unpacked_user_state_account.user_token_mint = *user_mint.key;
         unpacked_user_state_account.user_ata = *user_ata.key;
         unpacked_user_state_account.user = *user_account.key;
@@ -290,9 +878,15 @@ impl Processor {
         unpacked_user_state_account.cmp = market_valuation
             .checked_div(supply)
             .ok_or(DecenseError::MathError)?
-            .checked_mul(1000000000)
+            .checked_mul(quote_unit)
             .ok_or(DecenseError::MathError)?;
-        unpacked_user_state_account.liquidate_percentage = 50;
+        unpacked_user_state_account.liquidate_percentage = liquidate_percentage as u8;
+        unpacked_user_state_account.pricing_curve = pricing_curve;
+        unpacked_user_state_account.curve_parameter = curve_parameter;
+        unpacked_user_state_account.base_price = unpacked_user_state_account.cmp;
+        unpacked_user_state_account.initial_pool = pool_token_amount;
+        unpacked_user_state_account.platform_state = *platform_state_account.key;
+        unpacked_user_state_account.quote_mint = quote_mint;
 
         UserState::pack(
             unpacked_user_state_account,
@@ -305,8 +899,9 @@ impl Processor {
     fn process_exchange(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
-        asked_price: u64,
         quantity: u64,
+        max_total_cost: u64,
+        min_tokens_out: u64,
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
 
@@ -326,6 +921,12 @@ impl Processor {
 
         let pda_token_ata = next_account_info(account_info_iter)?;
 
+        let vault_account = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        let fee_recipient_account = next_account_info(account_info_iter)?;
+
         let token_program_account = next_account_info(account_info_iter)?;
 
         let rent_sysvar_account = next_account_info(account_info_iter)?;
@@ -334,43 +935,69 @@ impl Processor {
 
         let system_program_account = next_account_info(account_info_iter)?;
 
-        let (pda, bump_seeds) =
-            Pubkey::find_program_address(&[sk_account.key.as_ref()], program_id);
+        assert_signer(exchanger_account)?;
+        assert_platform_state(platform_state_account, program_id)?;
+        assert_writable(exchanger_state)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_associated_token_account(exchanger_token_ata, exchanger_account.key, sk_mint.key)?;
+        assert_token_program(token_program_account)?;
+        assert_rent_sysvar(rent_sysvar_account)?;
+        assert_associated_token_program(associated_token_account_program_account)?;
+        assert_system_program(system_program_account)?;
+
+        let bump_seeds = assert_pda(pda_account, &[sk_mint.key.as_ref()], program_id)?;
+        assert_pda(vault_account, &[sk_mint.key.as_ref(), b"vault"], program_id)?;
+        let exchanger_state_bump_seeds = assert_pda(
+            exchanger_state,
+            &[sk_mint.key.as_ref(), exchanger_account.key.as_ref()],
+            program_id,
+        )?;
+
+        {
+            let unpacked_sk_state_account =
+                UserState::unpack(&sk_state_account.try_borrow_data()?)?;
 
-        if pda != *pda_account.key {
-            return Err(DecenseError::InvalidPDA.into());
+            assert_keys_equal(sk_account.key, &unpacked_sk_state_account.user)?;
+            assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+            assert_keys_equal(pda_token_ata.key, &unpacked_sk_state_account.pda_ata)?;
         }
 
         if exchanger_state.data_is_empty() {
-            // create user state account
-            let create_user_state_account_ix = system_instruction::create_account_with_seed(
+            // create buyer state account
+            let create_buyer_state_account_ix = system_instruction::create_account(
                 exchanger_account.key,
                 exchanger_state.key,
-                exchanger_account.key,
-                "DECENSE BUYER",
                 Rent::default().minimum_balance(BuyerState::LEN),
                 BuyerState::LEN as u64,
                 program_id,
             );
 
-            invoke(
-                &create_user_state_account_ix,
+            invoke_signed(
+                &create_buyer_state_account_ix,
                 &[
                     exchanger_account.clone(),
                     exchanger_state.clone(),
                     system_program_account.clone(),
                 ],
+                &[&[
+                    sk_mint.key.as_ref(),
+                    exchanger_account.key.as_ref(),
+                    &[exchanger_state_bump_seeds],
+                ]],
             )?;
 
             let mut unpacked_exchanger_state =
-                BuyerState::unpack(&exchanger_state.try_borrow_data()?)?;
+                BuyerState::unpack_unchecked(&exchanger_state.try_borrow_data()?)?;
 
             unpacked_exchanger_state.is_initialized = true;
             unpacked_exchanger_state.buyer = *exchanger_account.key;
+            // a new position holds nothing from before the listing's splits
+            unpacked_exchanger_state.split_count =
+                UserState::unpack(&sk_state_account.try_borrow_data()?)?.split_count;
 
             BuyerState::pack(
                 unpacked_exchanger_state,
-                &mut exchanger_account.try_borrow_mut_data()?,
+                &mut exchanger_state.try_borrow_mut_data()?,
             )?;
         }
 
@@ -380,6 +1007,7 @@ impl Processor {
                 exchanger_account.key,
                 exchanger_account.key,
                 sk_mint.key,
+                &spl_token::id(),
             );
 
             invoke(
@@ -397,60 +1025,206 @@ impl Processor {
             )?;
         }
 
+        assert_owned_by(exchanger_state, program_id)?;
+
         let mut unpacked_sk_state_account =
             UserState::unpack(&sk_state_account.try_borrow_data()?)?;
 
+        let now = Clock::get()?.unix_timestamp;
+        Self::assert_trading(&mut unpacked_sk_state_account, now)?;
+
         let mut unpacked_exchanger_state = BuyerState::unpack(&exchanger_state.try_borrow_data()?)?;
 
+        assert_keys_equal(exchanger_account.key, &unpacked_exchanger_state.buyer)?;
+
         let unpacked_pda_token_ata =
             spl_token::state::Account::unpack(&pda_token_ata.try_borrow_data()?)?;
 
-        if quantity > unpacked_pda_token_ata.amount {
+        // fill as much of the order as the pool holds, within the buyer's bounds
+        let quantity = quantity.min(unpacked_pda_token_ata.amount);
+
+        if quantity == 0 {
             return Err(DecenseError::InsufficientTokenBalance.into());
         }
 
-        let transfer_sol =
-            system_instruction::transfer(exchanger_account.key, sk_account.key, asked_price);
+        if quantity < min_tokens_out {
+            return Err(DecenseError::SlippageExceeded.into());
+        }
 
-        invoke(
-            &transfer_sol,
-            &[
-                exchanger_account.clone(),
-                sk_account.clone(),
-                system_program_account.clone(),
-            ],
-        )?;
+        let pricing_curve = unpacked_sk_state_account
+            .pricing_curve()
+            .ok_or(DecenseError::InvalidPricingCurve)?;
 
-        let mut new_cmp = (asked_price.checked_sub(unpacked_sk_state_account.cmp))
-            .ok_or(DecenseError::MathError)?
-            .checked_div(unpacked_pda_token_ata.amount)
-            .ok_or(DecenseError::MathError)?
-            .checked_mul(quantity)
+        let total_cost = pricing_curve
+            .quote_buy(unpacked_pda_token_ata.amount, quantity)
             .ok_or(DecenseError::MathError)?;
 
-        if asked_price > unpacked_sk_state_account.cmp {
-            new_cmp = unpacked_sk_state_account
-                .cmp
-                .checked_add(new_cmp)
-                .ok_or(DecenseError::MathError)?;
-        } else {
-            new_cmp = unpacked_sk_state_account
-                .cmp
-                .checked_sub(new_cmp)
-                .ok_or(DecenseError::MathError)?;
-        }
+        let unpacked_platform_state_account =
+            PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
 
-        let unpacked_exchanger_token_ata =
-            spl_token::state::Account::unpack(&exchanger_token_ata.try_borrow_data()?)?;
+        assert_keys_equal(
+            platform_state_account.key,
+            &unpacked_sk_state_account.platform_state,
+        )?;
+        assert_not_paused(&unpacked_platform_state_account)?;
+        assert_keys_equal(
+            fee_recipient_account.key,
+            &unpacked_platform_state_account.fee_recipient,
+        )?;
 
-        if unpacked_exchanger_token_ata.amount == 0 {
-            unpacked_sk_state_account.holders = unpacked_sk_state_account
-                .holders
-                .checked_add(1)
-                .ok_or(DecenseError::MathError)?;
+        let fee = Self::platform_fee(total_cost, unpacked_platform_state_account.buy_fee_bps)?;
+
+        if total_cost.checked_add(fee).ok_or(DecenseError::MathError)? > max_total_cost {
+            return Err(DecenseError::SlippageExceeded.into());
         }
 
-        let transfer_token_to_user = spl_token::instruction::transfer_checked(
+        // liquidate_percentage of the cost stays in the vault to pay for sells back to the pool
+        let liquidity_share = (total_cost as u128
+            * unpacked_sk_state_account.liquidate_percentage as u128
+            / 100) as u64;
+        let issuer_share = total_cost - liquidity_share;
+
+        if unpacked_sk_state_account.quote_mint == Pubkey::default() {
+            if issuer_share > 0 {
+                let transfer_sol = system_instruction::transfer(
+                    exchanger_account.key,
+                    sk_account.key,
+                    issuer_share,
+                );
+
+                invoke(
+                    &transfer_sol,
+                    &[
+                        exchanger_account.clone(),
+                        sk_account.clone(),
+                        system_program_account.clone(),
+                    ],
+                )?;
+            }
+
+            if liquidity_share > 0 {
+                let transfer_liquidity = system_instruction::transfer(
+                    exchanger_account.key,
+                    vault_account.key,
+                    liquidity_share,
+                );
+
+                invoke(
+                    &transfer_liquidity,
+                    &[
+                        exchanger_account.clone(),
+                        vault_account.clone(),
+                        system_program_account.clone(),
+                    ],
+                )?;
+            }
+
+            if fee > 0 {
+                let transfer_fee = system_instruction::transfer(
+                    exchanger_account.key,
+                    fee_recipient_account.key,
+                    fee,
+                );
+
+                invoke(
+                    &transfer_fee,
+                    &[
+                        exchanger_account.clone(),
+                        fee_recipient_account.clone(),
+                        system_program_account.clone(),
+                    ],
+                )?;
+            }
+        } else {
+            // buys of spl quoted listings are paid into the vault's and the issuer's quote token
+            // accounts
+            let quote_mint_account = next_account_info(account_info_iter)?;
+
+            let exchanger_quote_account = next_account_info(account_info_iter)?;
+
+            let quote_vault_account = next_account_info(account_info_iter)?;
+
+            let fee_recipient_quote_account = next_account_info(account_info_iter)?;
+
+            let sk_quote_account = next_account_info(account_info_iter)?;
+
+            assert_keys_equal(
+                quote_mint_account.key,
+                &unpacked_sk_state_account.quote_mint,
+            )?;
+            assert_associated_token_account(
+                exchanger_quote_account,
+                exchanger_account.key,
+                quote_mint_account.key,
+            )?;
+            assert_associated_token_account(
+                quote_vault_account,
+                vault_account.key,
+                quote_mint_account.key,
+            )?;
+            assert_associated_token_account(
+                fee_recipient_quote_account,
+                fee_recipient_account.key,
+                quote_mint_account.key,
+            )?;
+            assert_associated_token_account(
+                sk_quote_account,
+                sk_account.key,
+                quote_mint_account.key,
+            )?;
+
+            if issuer_share > 0 {
+                Self::transfer_tokens(
+                    exchanger_quote_account,
+                    quote_mint_account,
+                    sk_quote_account,
+                    exchanger_account,
+                    token_program_account,
+                    issuer_share,
+                    &[],
+                )?;
+            }
+
+            if liquidity_share > 0 {
+                Self::transfer_tokens(
+                    exchanger_quote_account,
+                    quote_mint_account,
+                    quote_vault_account,
+                    exchanger_account,
+                    token_program_account,
+                    liquidity_share,
+                    &[],
+                )?;
+            }
+
+            if fee > 0 {
+                Self::transfer_tokens(
+                    exchanger_quote_account,
+                    quote_mint_account,
+                    fee_recipient_quote_account,
+                    exchanger_account,
+                    token_program_account,
+                    fee,
+                    &[],
+                )?;
+            }
+        }
+
+        let new_cmp = pricing_curve
+            .spot_price(unpacked_pda_token_ata.amount - quantity)
+            .ok_or(DecenseError::MathError)?;
+
+        let unpacked_exchanger_token_ata =
+            spl_token::state::Account::unpack(&exchanger_token_ata.try_borrow_data()?)?;
+
+        if unpacked_exchanger_token_ata.amount == 0 {
+            unpacked_sk_state_account.holders = unpacked_sk_state_account
+                .holders
+                .checked_add(1)
+                .ok_or(DecenseError::MathError)?;
+        }
+
+        let transfer_token_to_user = spl_token::instruction::transfer_checked(
             &spl_token::id(),
             pda_token_ata.key,
             sk_mint.key,
@@ -465,20 +1239,27 @@ impl Processor {
             &transfer_token_to_user,
             &[
                 pda_token_ata.clone(),
+                sk_mint.clone(),
                 exchanger_token_ata.clone(),
                 pda_account.clone(),
                 token_program_account.clone(),
             ],
-            &[&[sk_account.key.as_ref(), &[bump_seeds]]],
+            &[&[sk_mint.key.as_ref(), &[bump_seeds]]],
         )?;
 
-        unpacked_sk_state_account.cmp = new_cmp;
+        Self::record_trade_price(&mut unpacked_sk_state_account, new_cmp, now)?;
         UserState::pack(
             unpacked_sk_state_account,
             &mut sk_state_account.try_borrow_mut_data()?,
         )?;
 
-        unpacked_exchanger_state.current_holding_in_tokens = quantity;
+        unpacked_exchanger_state.mint = *sk_mint.key;
+        unpacked_exchanger_state
+            .add_to_position(
+                quantity,
+                total_cost.checked_add(fee).ok_or(DecenseError::MathError)?,
+            )
+            .ok_or(DecenseError::MathError)?;
         BuyerState::pack(
             unpacked_exchanger_state,
             &mut exchanger_state.try_borrow_mut_data()?,
@@ -510,19 +1291,59 @@ impl Processor {
 
         let pda_token_ata = next_account_info(account_info_iter)?;
 
+        let platform_state_account = next_account_info(account_info_iter)?;
+
         let token_program_account = next_account_info(account_info_iter)?;
 
         let system_program_account = next_account_info(account_info_iter)?;
 
-        let (pda, bump_seeds) =
-            Pubkey::find_program_address(&[sk_account.key.as_ref()], program_id);
+        assert_writable(exchanger_state)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_platform_state(platform_state_account, program_id)?;
+        assert_associated_token_account(exchanger_token_ata, exchanger_account.key, sk_mint.key)?;
+        assert_token_program(token_program_account)?;
+        assert_system_program(system_program_account)?;
+
+        let bump_seeds = assert_pda(pda_account, &[sk_mint.key.as_ref()], program_id)?;
+        let exchanger_state_bump_seeds = assert_pda(
+            exchanger_state,
+            &[sk_mint.key.as_ref(), exchanger_account.key.as_ref()],
+            program_id,
+        )?;
+
+        let split_count = {
+            let unpacked_sk_state_account =
+                UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+            assert_keys_equal(sk_account.key, &unpacked_sk_state_account.user)?;
+            assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+            assert_keys_equal(pda_token_ata.key, &unpacked_sk_state_account.pda_ata)?;
+            assert_keys_equal(
+                platform_state_account.key,
+                &unpacked_sk_state_account.platform_state,
+            )?;
+
+            unpacked_sk_state_account.split_count
+        };
+
+        let unpacked_platform_state_account =
+            PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
+
+        assert_not_paused(&unpacked_platform_state_account)?;
+
+        if !exchanger_state.data_is_empty() {
+            assert_owned_by(exchanger_state, program_id)?;
 
-        if *pda_account.key != pda {
-            return Err(DecenseError::InvalidPDA.into());
+            let unpacked_exchanger_state = BuyerState::unpack(&exchanger_state.try_borrow_data()?)?;
+
+            assert_keys_equal(exchanger_account.key, &unpacked_exchanger_state.buyer)?;
         }
 
         match action {
             0 => {
+                assert_signer(exchanger_account)?;
+                Self::assert_split_claimed(exchanger_state, split_count, program_id)?;
+
                 let transfer_token_to_user = spl_token::instruction::transfer_checked(
                     &spl_token::id(),
                     exchanger_token_ata.key,
@@ -538,58 +1359,28 @@ impl Processor {
                     &transfer_token_to_user,
                     &[
                         exchanger_token_ata.clone(),
+                        sk_mint.clone(),
                         pda_token_ata.clone(),
                         exchanger_account.clone(),
                         token_program_account.clone(),
                     ],
                 )?;
 
+                // only tokens the holder's position accounts for go back to the pool
                 if exchanger_state.data_is_empty() {
-                    // create user state account
-                    let create_user_state_account_ix = system_instruction::create_account_with_seed(
-                        exchanger_account.key,
-                        exchanger_state.key,
-                        exchanger_account.key,
-                        "DECENSE BUYER",
-                        Rent::default().minimum_balance(BuyerState::LEN),
-                        BuyerState::LEN as u64,
-                        program_id,
-                    );
-
-                    invoke(
-                        &create_user_state_account_ix,
-                        &[
-                            exchanger_account.clone(),
-                            exchanger_state.clone(),
-                            system_program_account.clone(),
-                        ],
-                    )?;
-
-                    let mut unpacked_exchanger_state =
-                        BuyerState::unpack(&exchanger_state.try_borrow_data()?)?;
-
-                    unpacked_exchanger_state.is_initialized = true;
-                    unpacked_exchanger_state.buyer = *exchanger_account.key;
-
-                    BuyerState::pack(
-                        unpacked_exchanger_state,
-                        &mut exchanger_account.try_borrow_mut_data()?,
-                    )?;
+                    return Err(DecenseError::PositionExceeded.into());
                 }
 
                 let mut unpacked_exchanger_state =
                     BuyerState::unpack(&exchanger_state.try_borrow_data()?)?;
 
-                if unpacked_exchanger_state.current_holding_in_tokens != 0 {
-                    unpacked_exchanger_state.current_holding_in_tokens = unpacked_exchanger_state
-                        .current_holding_in_tokens
-                        .checked_sub(amount)
-                        .ok_or(DecenseError::MathError)?;
-                }
+                // tokens given back to the pool realize their share of the cost basis as a loss
+                unpacked_exchanger_state.mint = *sk_mint.key;
+                Self::remove_from_position(&mut unpacked_exchanger_state, amount, 0)?;
 
                 BuyerState::pack(
                     unpacked_exchanger_state,
-                    &mut exchanger_account.try_borrow_mut_data()?,
+                    &mut exchanger_state.try_borrow_mut_data()?,
                 )?;
 
                 let unpacked_exchanger_token_ata =
@@ -611,50 +1402,60 @@ impl Processor {
             }
 
             1 => {
+                // tokens leave the pool only with the issuer's approval
+                assert_signer(sk_account)?;
+
                 if exchanger_state.data_is_empty() {
-                    // create user state account
-                    let create_user_state_account_ix = system_instruction::create_account_with_seed(
+                    // create buyer state account
+                    let create_buyer_state_account_ix = system_instruction::create_account(
                         exchanger_account.key,
                         exchanger_state.key,
-                        exchanger_account.key,
-                        "DECENSE BUYER",
                         Rent::default().minimum_balance(BuyerState::LEN),
                         BuyerState::LEN as u64,
                         program_id,
                     );
 
-                    invoke(
-                        &create_user_state_account_ix,
+                    invoke_signed(
+                        &create_buyer_state_account_ix,
                         &[
                             exchanger_account.clone(),
                             exchanger_state.clone(),
                             system_program_account.clone(),
                         ],
+                        &[&[
+                            sk_mint.key.as_ref(),
+                            exchanger_account.key.as_ref(),
+                            &[exchanger_state_bump_seeds],
+                        ]],
                     )?;
 
                     let mut unpacked_exchanger_state =
-                        BuyerState::unpack(&exchanger_state.try_borrow_data()?)?;
+                        BuyerState::unpack_unchecked(&exchanger_state.try_borrow_data()?)?;
 
                     unpacked_exchanger_state.is_initialized = true;
                     unpacked_exchanger_state.buyer = *exchanger_account.key;
+                    // a new position holds nothing from before the listing's splits
+                    unpacked_exchanger_state.split_count =
+                        UserState::unpack(&sk_state_account.try_borrow_data()?)?.split_count;
 
                     BuyerState::pack(
                         unpacked_exchanger_state,
-                        &mut exchanger_account.try_borrow_mut_data()?,
+                        &mut exchanger_state.try_borrow_mut_data()?,
                     )?;
                 }
 
                 let mut unpacked_exchanger_state =
                     BuyerState::unpack(&exchanger_state.try_borrow_data()?)?;
 
-                unpacked_exchanger_state.current_holding_in_tokens = unpacked_exchanger_state
-                    .current_holding_in_tokens
-                    .checked_add(amount)
+                // tokens sent by the issuer cost the holder nothing
+                unpacked_exchanger_state.mint = *sk_mint.key;
+                unpacked_exchanger_state
+                    .add_to_position(amount, 0)
                     .ok_or(DecenseError::MathError)?;
 
                 BuyerState::pack(
                     unpacked_exchanger_state,
-                    &mut exchanger_account.try_borrow_mut_data()?,
+                    &mut exchanger_state.try_borrow_mut_data()?,
                 )?;
 
                 let unpacked_exchanger_token_ata =
@@ -689,11 +1490,12 @@ impl Processor {
                     &transfer_token_to_user,
                     &[
                         pda_token_ata.clone(),
+                        sk_mint.clone(),
                         exchanger_token_ata.clone(),
                         pda_account.clone(),
                         token_program_account.clone(),
                     ],
-                    &[&[sk_account.key.as_ref(), &[bump_seeds]]],
+                    &[&[sk_mint.key.as_ref(), &[bump_seeds]]],
                 )?;
             }
 
@@ -702,4 +1504,5190 @@ impl Processor {
 
         Ok(())
     }
+
+    fn process_sell_to_pool(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        quantity: u64,
+        min_sol_out: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let seller_account = next_account_info(account_info_iter)?;
+
+        let seller_state = next_account_info(account_info_iter)?;
+
+        let seller_token_ata = next_account_info(account_info_iter)?;
+
+        let sk_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let pda_account = next_account_info(account_info_iter)?;
+
+        let pda_token_ata = next_account_info(account_info_iter)?;
+
+        let vault_account = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        let fee_recipient_account = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(seller_account)?;
+        assert_platform_state(platform_state_account, program_id)?;
+        assert_writable(seller_state)?;
+        assert_writable(sk_state_account)?;
+        assert_writable(vault_account)?;
+        assert_owned_by(seller_state, program_id)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_associated_token_account(seller_token_ata, seller_account.key, sk_mint.key)?;
+        assert_token_program(token_program_account)?;
+        assert_system_program(system_program_account)?;
+
+        assert_pda(pda_account, &[sk_mint.key.as_ref()], program_id)?;
+        let vault_bump_seeds =
+            assert_pda(vault_account, &[sk_mint.key.as_ref(), b"vault"], program_id)?;
+        assert_pda(
+            seller_state,
+            &[sk_mint.key.as_ref(), seller_account.key.as_ref()],
+            program_id,
+        )?;
+
+        let mut unpacked_sk_state_account =
+            UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_account.key, &unpacked_sk_state_account.user)?;
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+        assert_keys_equal(pda_token_ata.key, &unpacked_sk_state_account.pda_ata)?;
+
+        Self::assert_split_claimed(
+            seller_state,
+            unpacked_sk_state_account.split_count,
+            program_id,
+        )?;
+
+        let mut unpacked_seller_state = BuyerState::unpack(&seller_state.try_borrow_data()?)?;
+
+        assert_keys_equal(seller_account.key, &unpacked_seller_state.buyer)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        Self::assert_trading(&mut unpacked_sk_state_account, now)?;
+
+        let unpacked_seller_token_ata =
+            spl_token::state::Account::unpack(&seller_token_ata.try_borrow_data()?)?;
+
+        if quantity == 0 || quantity > unpacked_seller_token_ata.amount {
+            return Err(DecenseError::InsufficientTokenBalance.into());
+        }
+
+        let unpacked_pda_token_ata =
+            spl_token::state::Account::unpack(&pda_token_ata.try_borrow_data()?)?;
+
+        let pricing_curve = unpacked_sk_state_account
+            .pricing_curve()
+            .ok_or(DecenseError::InvalidPricingCurve)?;
+
+        let sol_out = pricing_curve
+            .quote_sell(unpacked_pda_token_ata.amount, quantity)
+            .ok_or(DecenseError::MathError)?;
+
+        let unpacked_platform_state_account =
+            PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(
+            platform_state_account.key,
+            &unpacked_sk_state_account.platform_state,
+        )?;
+        assert_not_paused(&unpacked_platform_state_account)?;
+        assert_keys_equal(
+            fee_recipient_account.key,
+            &unpacked_platform_state_account.fee_recipient,
+        )?;
+
+        let fee = Self::platform_fee(sol_out, unpacked_platform_state_account.sell_fee_bps)?;
+
+        if sol_out - fee < min_sol_out {
+            return Err(DecenseError::SlippageExceeded.into());
+        }
+
+        unpacked_seller_state.mint = *sk_mint.key;
+        Self::remove_from_position(&mut unpacked_seller_state, quantity, sol_out - fee)?;
+
+        let new_cmp = pricing_curve
+            .spot_price(unpacked_pda_token_ata.amount + quantity)
+            .ok_or(DecenseError::MathError)?;
+
+        let transfer_token_to_pda = spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            seller_token_ata.key,
+            sk_mint.key,
+            pda_token_ata.key,
+            seller_account.key,
+            &[],
+            quantity,
+            4,
+        )?;
+
+        invoke(
+            &transfer_token_to_pda,
+            &[
+                seller_token_ata.clone(),
+                sk_mint.clone(),
+                pda_token_ata.clone(),
+                seller_account.clone(),
+                token_program_account.clone(),
+            ],
+        )?;
+
+        let vault_seeds: &[&[u8]] = &[sk_mint.key.as_ref(), b"vault", &[vault_bump_seeds]];
+
+        if unpacked_sk_state_account.quote_mint == Pubkey::default() {
+            // the vault has to stay rent exempt after paying out
+            let available_sol = vault_account
+                .lamports()
+                .saturating_sub(Rent::default().minimum_balance(0));
+
+            if sol_out > available_sol {
+                return Err(DecenseError::InsufficientVaultBalance.into());
+            }
+
+            let transfer_sol =
+                system_instruction::transfer(vault_account.key, seller_account.key, sol_out - fee);
+
+            invoke_signed(
+                &transfer_sol,
+                &[
+                    vault_account.clone(),
+                    seller_account.clone(),
+                    system_program_account.clone(),
+                ],
+                &[vault_seeds],
+            )?;
+
+            if fee > 0 {
+                let transfer_fee =
+                    system_instruction::transfer(vault_account.key, fee_recipient_account.key, fee);
+
+                invoke_signed(
+                    &transfer_fee,
+                    &[
+                        vault_account.clone(),
+                        fee_recipient_account.clone(),
+                        system_program_account.clone(),
+                    ],
+                    &[vault_seeds],
+                )?;
+            }
+        } else {
+            let quote_mint_account = next_account_info(account_info_iter)?;
+
+            let seller_quote_account = next_account_info(account_info_iter)?;
+
+            let quote_vault_account = next_account_info(account_info_iter)?;
+
+            let fee_recipient_quote_account = next_account_info(account_info_iter)?;
+
+            assert_keys_equal(
+                quote_mint_account.key,
+                &unpacked_sk_state_account.quote_mint,
+            )?;
+            assert_associated_token_account(
+                seller_quote_account,
+                seller_account.key,
+                quote_mint_account.key,
+            )?;
+            assert_associated_token_account(
+                quote_vault_account,
+                vault_account.key,
+                quote_mint_account.key,
+            )?;
+            assert_associated_token_account(
+                fee_recipient_quote_account,
+                fee_recipient_account.key,
+                quote_mint_account.key,
+            )?;
+
+            let unpacked_quote_vault =
+                spl_token::state::Account::unpack(&quote_vault_account.try_borrow_data()?)?;
+
+            if sol_out > unpacked_quote_vault.amount {
+                return Err(DecenseError::InsufficientVaultBalance.into());
+            }
+
+            Self::transfer_tokens(
+                quote_vault_account,
+                quote_mint_account,
+                seller_quote_account,
+                vault_account,
+                token_program_account,
+                sol_out - fee,
+                &[vault_seeds],
+            )?;
+
+            if fee > 0 {
+                Self::transfer_tokens(
+                    quote_vault_account,
+                    quote_mint_account,
+                    fee_recipient_quote_account,
+                    vault_account,
+                    token_program_account,
+                    fee,
+                    &[vault_seeds],
+                )?;
+            }
+        }
+
+        if unpacked_seller_token_ata.amount == quantity {
+            unpacked_sk_state_account.holders = unpacked_sk_state_account
+                .holders
+                .checked_sub(1)
+                .ok_or(DecenseError::MathError)?;
+        }
+
+        Self::record_trade_price(&mut unpacked_sk_state_account, new_cmp, now)?;
+        UserState::pack(
+            unpacked_sk_state_account,
+            &mut sk_state_account.try_borrow_mut_data()?,
+        )?;
+
+        BuyerState::pack(
+            unpacked_seller_state,
+            &mut seller_state.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    // lamports owed for quantity base units (4 decimals) at price lamports per whole token
+    fn order_cost(price: u64, quantity: u64, round_up: bool) -> Result<u64, ProgramError> {
+        let mut cost = (price as u128)
+            .checked_mul(quantity as u128)
+            .ok_or(DecenseError::MathError)?;
+
+        if round_up {
+            cost = cost.checked_add(9999).ok_or(DecenseError::MathError)?;
+        }
+
+        let cost = cost.checked_div(10000).ok_or(DecenseError::MathError)?;
+
+        Ok(u64::try_from(cost).map_err(|_| DecenseError::MathError)?)
+    }
+
+    // lamports a bid escrows for quantity at its limit price, buy fee included
+    fn bid_escrow(price: u64, quantity: u64, fee_bps: u16) -> Result<u64, ProgramError> {
+        let cost = Self::order_cost(price, quantity, true)?;
+
+        Ok(cost
+            .checked_add(Self::platform_fee(cost, fee_bps)?)
+            .ok_or(DecenseError::MathError)?)
+    }
+
+    // positions only hold what the program saw their holder acquire, tokens that came from
+    // anywhere else can not be sold out of them
+    fn remove_from_position(
+        buyer_state: &mut BuyerState,
+        quantity: u64,
+        proceeds: u64,
+    ) -> ProgramResult {
+        let released_basis = buyer_state
+            .release_basis(quantity)
+            .ok_or(DecenseError::PositionExceeded)?;
+
+        buyer_state
+            .realize(proceeds, released_basis)
+            .ok_or(DecenseError::MathError)?;
+
+        Ok(())
+    }
+
+    fn process_place_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        side: u64,
+        price: u64,
+        quantity: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let owner_account = next_account_info(account_info_iter)?;
+
+        let owner_token_ata = next_account_info(account_info_iter)?;
+
+        let sk_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let order_book_account = next_account_info(account_info_iter)?;
+
+        let bid_vault_account = next_account_info(account_info_iter)?;
+
+        let ask_vault_authority = next_account_info(account_info_iter)?;
+
+        let ask_vault_ata = next_account_info(account_info_iter)?;
+
+        let owner_state = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        let rent_sysvar_account = next_account_info(account_info_iter)?;
+
+        let associated_token_account_program_account = next_account_info(account_info_iter)?;
+
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(owner_account)?;
+        assert_writable(order_book_account)?;
+        assert_writable(owner_state)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_platform_state(platform_state_account, program_id)?;
+        assert_associated_token_account(owner_token_ata, owner_account.key, sk_mint.key)?;
+        assert_token_program(token_program_account)?;
+        assert_rent_sysvar(rent_sysvar_account)?;
+        assert_associated_token_program(associated_token_account_program_account)?;
+        assert_system_program(system_program_account)?;
+
+        let order_book_bump_seeds = assert_pda(
+            order_book_account,
+            &[sk_mint.key.as_ref(), b"order_book"],
+            program_id,
+        )?;
+        assert_pda(
+            bid_vault_account,
+            &[sk_mint.key.as_ref(), b"bid_vault"],
+            program_id,
+        )?;
+        assert_pda(
+            ask_vault_authority,
+            &[sk_mint.key.as_ref(), b"ask_vault"],
+            program_id,
+        )?;
+        assert_associated_token_account(ask_vault_ata, ask_vault_authority.key, sk_mint.key)?;
+        let owner_state_bump_seeds = assert_pda(
+            owner_state,
+            &[sk_mint.key.as_ref(), owner_account.key.as_ref()],
+            program_id,
+        )?;
+
+        let mut unpacked_sk_state_account =
+            UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_account.key, &unpacked_sk_state_account.user)?;
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+        assert_keys_equal(
+            platform_state_account.key,
+            &unpacked_sk_state_account.platform_state,
+        )?;
+
+        let unpacked_platform_state_account =
+            PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
+
+        assert_not_paused(&unpacked_platform_state_account)?;
+
+        // bids escrow lamports, listings quoted in an spl token only trade against the pool
+        if unpacked_sk_state_account.quote_mint != Pubkey::default() {
+            return Err(DecenseError::InvalidQuoteMint.into());
+        }
+
+        if price == 0 || quantity == 0 {
+            return Err(DecenseError::InvalidOrder.into());
+        }
+
+        if order_book_account.data_is_empty() {
+            // create order book account
+            let create_order_book_account_ix = system_instruction::create_account(
+                owner_account.key,
+                order_book_account.key,
+                Rent::default().minimum_balance(OrderBook::LEN),
+                OrderBook::LEN as u64,
+                program_id,
+            );
+
+            invoke_signed(
+                &create_order_book_account_ix,
+                &[
+                    owner_account.clone(),
+                    order_book_account.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[
+                    sk_mint.key.as_ref(),
+                    b"order_book",
+                    &[order_book_bump_seeds],
+                ]],
+            )?;
+
+            let mut unpacked_order_book =
+                OrderBook::unpack_unchecked(&order_book_account.try_borrow_data()?)?;
+
+            unpacked_order_book.is_initialized = true;
+            unpacked_order_book.user_token_mint = *sk_mint.key;
+
+            OrderBook::pack(
+                unpacked_order_book,
+                &mut order_book_account.try_borrow_mut_data()?,
+            )?;
+        }
+
+        assert_owned_by(order_book_account, program_id)?;
+
+        let mut unpacked_order_book = OrderBook::unpack(&order_book_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_mint.key, &unpacked_order_book.user_token_mint)?;
+
+        if side == 0 && owner_state.data_is_empty() {
+            // create owner state account so that matched tokens join a position
+            let create_owner_state_account_ix = system_instruction::create_account(
+                owner_account.key,
+                owner_state.key,
+                Rent::default().minimum_balance(BuyerState::LEN),
+                BuyerState::LEN as u64,
+                program_id,
+            );
+
+            invoke_signed(
+                &create_owner_state_account_ix,
+                &[
+                    owner_account.clone(),
+                    owner_state.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[
+                    sk_mint.key.as_ref(),
+                    owner_account.key.as_ref(),
+                    &[owner_state_bump_seeds],
+                ]],
+            )?;
+
+            let mut unpacked_owner_state =
+                BuyerState::unpack_unchecked(&owner_state.try_borrow_data()?)?;
+
+            unpacked_owner_state.is_initialized = true;
+            unpacked_owner_state.buyer = *owner_account.key;
+            unpacked_owner_state.mint = *sk_mint.key;
+            // a new position holds nothing from before the listing's splits
+            unpacked_owner_state.split_count = unpacked_sk_state_account.split_count;
+
+            BuyerState::pack(
+                unpacked_owner_state,
+                &mut owner_state.try_borrow_mut_data()?,
+            )?;
+        }
+
+        // splits need an empty book, so both sides of every resting order are converted positions
+        Self::assert_split_claimed(
+            owner_state,
+            unpacked_sk_state_account.split_count,
+            program_id,
+        )?;
+
+        if owner_state.data_is_empty() {
+            return Err(DecenseError::PositionExceeded.into());
+        }
+
+        assert_owned_by(owner_state, program_id)?;
+
+        let mut unpacked_owner_state = BuyerState::unpack(&owner_state.try_borrow_data()?)?;
+
+        assert_keys_equal(owner_account.key, &unpacked_owner_state.buyer)?;
+
+        let mut order = Order {
+            owner: *owner_account.key,
+            price,
+            quantity,
+            sequence: unpacked_order_book.next_sequence,
+            cost_basis: 0,
+            fee_bps: 0,
+        };
+
+        unpacked_order_book.next_sequence = unpacked_order_book
+            .next_sequence
+            .checked_add(1)
+            .ok_or(DecenseError::MathError)?;
+
+        match side {
+            0 => {
+                order.fee_bps = unpacked_platform_state_account.buy_fee_bps;
+
+                if !unpacked_order_book.insert_bid(order) {
+                    return Err(DecenseError::OrderBookFull.into());
+                }
+
+                if owner_token_ata.data_is_empty() {
+                    // create owner ata so that matched tokens can be delivered
+                    let create_owner_ata_ix = create_associated_token_account(
+                        owner_account.key,
+                        owner_account.key,
+                        sk_mint.key,
+                        &spl_token::id(),
+                    );
+
+                    invoke(
+                        &create_owner_ata_ix,
+                        &[
+                            owner_account.clone(),
+                            owner_token_ata.clone(),
+                            owner_account.clone(),
+                            sk_mint.clone(),
+                            system_program_account.clone(),
+                            token_program_account.clone(),
+                            rent_sysvar_account.clone(),
+                            associated_token_account_program_account.clone(),
+                        ],
+                    )?;
+                }
+
+                // bids escrow lamports at their limit price plus the buy fee, topping the vault up
+                // to rent exemption
+                let escrow = Self::bid_escrow(price, quantity, order.fee_bps)?
+                    .checked_add(
+                        Rent::default()
+                            .minimum_balance(0)
+                            .saturating_sub(bid_vault_account.lamports()),
+                    )
+                    .ok_or(DecenseError::MathError)?;
+
+                let transfer_sol =
+                    system_instruction::transfer(owner_account.key, bid_vault_account.key, escrow);
+
+                invoke(
+                    &transfer_sol,
+                    &[
+                        owner_account.clone(),
+                        bid_vault_account.clone(),
+                        system_program_account.clone(),
+                    ],
+                )?;
+            }
+
+            1 => {
+                // the escrowed tokens leave the position with their cost basis and come back on
+                // cancel, so a resting ask always fills against tokens its owner accounted for
+                unpacked_owner_state.mint = *sk_mint.key;
+                order.cost_basis = unpacked_owner_state
+                    .release_basis(quantity)
+                    .ok_or(DecenseError::PositionExceeded)?;
+
+                BuyerState::pack(
+                    unpacked_owner_state,
+                    &mut owner_state.try_borrow_mut_data()?,
+                )?;
+
+                if !unpacked_order_book.insert_ask(order) {
+                    return Err(DecenseError::OrderBookFull.into());
+                }
+
+                let unpacked_owner_token_ata =
+                    spl_token::state::Account::unpack(&owner_token_ata.try_borrow_data()?)?;
+
+                if quantity > unpacked_owner_token_ata.amount {
+                    return Err(DecenseError::InsufficientTokenBalance.into());
+                }
+
+                if ask_vault_ata.data_is_empty() {
+                    // create mint ata for the ask vault
+                    let create_ask_vault_ata_ix = create_associated_token_account(
+                        owner_account.key,
+                        ask_vault_authority.key,
+                        sk_mint.key,
+                        &spl_token::id(),
+                    );
+
+                    invoke(
+                        &create_ask_vault_ata_ix,
+                        &[
+                            owner_account.clone(),
+                            ask_vault_ata.clone(),
+                            ask_vault_authority.clone(),
+                            sk_mint.clone(),
+                            system_program_account.clone(),
+                            token_program_account.clone(),
+                            rent_sysvar_account.clone(),
+                            associated_token_account_program_account.clone(),
+                        ],
+                    )?;
+                }
+
+                let transfer_token_to_vault = spl_token::instruction::transfer_checked(
+                    &spl_token::id(),
+                    owner_token_ata.key,
+                    sk_mint.key,
+                    ask_vault_ata.key,
+                    owner_account.key,
+                    &[],
+                    quantity,
+                    4,
+                )?;
+
+                invoke(
+                    &transfer_token_to_vault,
+                    &[
+                        owner_token_ata.clone(),
+                        sk_mint.clone(),
+                        ask_vault_ata.clone(),
+                        owner_account.clone(),
+                        token_program_account.clone(),
+                    ],
+                )?;
+
+                if owner_account.key != sk_account.key
+                    && unpacked_owner_token_ata.amount == quantity
+                {
+                    unpacked_sk_state_account.holders = unpacked_sk_state_account
+                        .holders
+                        .checked_sub(1)
+                        .ok_or(DecenseError::MathError)?;
+
+                    UserState::pack(
+                        unpacked_sk_state_account,
+                        &mut sk_state_account.try_borrow_mut_data()?,
+                    )?;
+                }
+            }
+
+            _ => return Err(DecenseError::InvalidInstruction.into()),
+        }
+
+        OrderBook::pack(
+            unpacked_order_book,
+            &mut order_book_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_cancel_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        side: u64,
+        sequence: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let owner_account = next_account_info(account_info_iter)?;
+
+        let owner_token_ata = next_account_info(account_info_iter)?;
+
+        let sk_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let order_book_account = next_account_info(account_info_iter)?;
+
+        let bid_vault_account = next_account_info(account_info_iter)?;
+
+        let ask_vault_authority = next_account_info(account_info_iter)?;
+
+        let ask_vault_ata = next_account_info(account_info_iter)?;
+
+        let owner_state = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(owner_account)?;
+        assert_writable(order_book_account)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_owned_by(order_book_account, program_id)?;
+        assert_associated_token_account(owner_token_ata, owner_account.key, sk_mint.key)?;
+        assert_token_program(token_program_account)?;
+        assert_system_program(system_program_account)?;
+
+        assert_pda(
+            order_book_account,
+            &[sk_mint.key.as_ref(), b"order_book"],
+            program_id,
+        )?;
+        let bid_vault_bump_seeds = assert_pda(
+            bid_vault_account,
+            &[sk_mint.key.as_ref(), b"bid_vault"],
+            program_id,
+        )?;
+        let ask_vault_bump_seeds = assert_pda(
+            ask_vault_authority,
+            &[sk_mint.key.as_ref(), b"ask_vault"],
+            program_id,
+        )?;
+        assert_associated_token_account(ask_vault_ata, ask_vault_authority.key, sk_mint.key)?;
+        assert_pda(
+            owner_state,
+            &[sk_mint.key.as_ref(), owner_account.key.as_ref()],
+            program_id,
+        )?;
+
+        let mut unpacked_sk_state_account =
+            UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_account.key, &unpacked_sk_state_account.user)?;
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+
+        let mut unpacked_order_book = OrderBook::unpack(&order_book_account.try_borrow_data()?)?;
+
+        match side {
+            0 => {
+                let index = unpacked_order_book
+                    .bids
+                    .iter()
+                    .position(|order| order.sequence == sequence)
+                    .ok_or(DecenseError::OrderNotFound)?;
+                let order = unpacked_order_book.bids.remove(index);
+
+                assert_keys_equal(owner_account.key, &order.owner)?;
+
+                let refund = Self::bid_escrow(order.price, order.quantity, order.fee_bps)?;
+
+                let transfer_sol =
+                    system_instruction::transfer(bid_vault_account.key, owner_account.key, refund);
+
+                invoke_signed(
+                    &transfer_sol,
+                    &[
+                        bid_vault_account.clone(),
+                        owner_account.clone(),
+                        system_program_account.clone(),
+                    ],
+                    &[&[sk_mint.key.as_ref(), b"bid_vault", &[bid_vault_bump_seeds]]],
+                )?;
+            }
+
+            1 => {
+                let index = unpacked_order_book
+                    .asks
+                    .iter()
+                    .position(|order| order.sequence == sequence)
+                    .ok_or(DecenseError::OrderNotFound)?;
+                let order = unpacked_order_book.asks.remove(index);
+
+                assert_keys_equal(owner_account.key, &order.owner)?;
+                assert_writable(owner_state)?;
+                assert_owned_by(owner_state, program_id)?;
+
+                let mut unpacked_owner_state = BuyerState::unpack(&owner_state.try_borrow_data()?)?;
+
+                unpacked_owner_state
+                    .add_to_position(order.quantity, order.cost_basis)
+                    .ok_or(DecenseError::MathError)?;
+
+                BuyerState::pack(
+                    unpacked_owner_state,
+                    &mut owner_state.try_borrow_mut_data()?,
+                )?;
+
+                let unpacked_owner_token_ata =
+                    spl_token::state::Account::unpack(&owner_token_ata.try_borrow_data()?)?;
+
+                let transfer_token_to_owner = spl_token::instruction::transfer_checked(
+                    &spl_token::id(),
+                    ask_vault_ata.key,
+                    sk_mint.key,
+                    owner_token_ata.key,
+                    ask_vault_authority.key,
+                    &[],
+                    order.quantity,
+                    4,
+                )?;
+
+                invoke_signed(
+                    &transfer_token_to_owner,
+                    &[
+                        ask_vault_ata.clone(),
+                        sk_mint.clone(),
+                        owner_token_ata.clone(),
+                        ask_vault_authority.clone(),
+                        token_program_account.clone(),
+                    ],
+                    &[&[sk_mint.key.as_ref(), b"ask_vault", &[ask_vault_bump_seeds]]],
+                )?;
+
+                if owner_account.key != sk_account.key && unpacked_owner_token_ata.amount == 0 {
+                    unpacked_sk_state_account.holders = unpacked_sk_state_account
+                        .holders
+                        .checked_add(1)
+                        .ok_or(DecenseError::MathError)?;
+
+                    UserState::pack(
+                        unpacked_sk_state_account,
+                        &mut sk_state_account.try_borrow_mut_data()?,
+                    )?;
+                }
+            }
+
+            _ => return Err(DecenseError::InvalidInstruction.into()),
+        }
+
+        OrderBook::pack(
+            unpacked_order_book,
+            &mut order_book_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_match_orders(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        limit: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let sk_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let order_book_account = next_account_info(account_info_iter)?;
+
+        let bid_vault_account = next_account_info(account_info_iter)?;
+
+        let ask_vault_authority = next_account_info(account_info_iter)?;
+
+        let ask_vault_ata = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        let fee_recipient_account = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        assert_writable(sk_state_account)?;
+        assert_writable(order_book_account)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_owned_by(order_book_account, program_id)?;
+        assert_platform_state(platform_state_account, program_id)?;
+        assert_token_program(token_program_account)?;
+        assert_system_program(system_program_account)?;
+
+        assert_pda(
+            order_book_account,
+            &[sk_mint.key.as_ref(), b"order_book"],
+            program_id,
+        )?;
+        let bid_vault_bump_seeds = assert_pda(
+            bid_vault_account,
+            &[sk_mint.key.as_ref(), b"bid_vault"],
+            program_id,
+        )?;
+        let ask_vault_bump_seeds = assert_pda(
+            ask_vault_authority,
+            &[sk_mint.key.as_ref(), b"ask_vault"],
+            program_id,
+        )?;
+        assert_associated_token_account(ask_vault_ata, ask_vault_authority.key, sk_mint.key)?;
+
+        let mut unpacked_sk_state_account =
+            UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_account.key, &unpacked_sk_state_account.user)?;
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+        assert_keys_equal(
+            platform_state_account.key,
+            &unpacked_sk_state_account.platform_state,
+        )?;
+
+        let unpacked_platform_state_account =
+            PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
+
+        assert_not_paused(&unpacked_platform_state_account)?;
+        assert_keys_equal(
+            fee_recipient_account.key,
+            &unpacked_platform_state_account.fee_recipient,
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        Self::assert_trading(&mut unpacked_sk_state_account, now)?;
+
+        let mut unpacked_order_book = OrderBook::unpack(&order_book_account.try_borrow_data()?)?;
+
+        let mut matches = 0;
+
+        // a match that trips the circuit breaker ends the crank
+        while matches < limit && unpacked_sk_state_account.halt_reason == 0 {
+            let (bid, ask) = match (
+                unpacked_order_book.bids.first(),
+                unpacked_order_book.asks.first(),
+            ) {
+                (Some(bid), Some(ask)) if bid.price >= ask.price => (*bid, *ask),
+                _ => break,
+            };
+
+            // the crank passes bidder wallet, ata and state, then asker wallet and state for
+            // every match
+            let bidder_account = next_account_info(account_info_iter)?;
+
+            let bidder_token_ata = next_account_info(account_info_iter)?;
+
+            let bidder_state = next_account_info(account_info_iter)?;
+
+            let asker_account = next_account_info(account_info_iter)?;
+
+            let asker_state = next_account_info(account_info_iter)?;
+
+            assert_keys_equal(bidder_account.key, &bid.owner)?;
+            assert_associated_token_account(bidder_token_ata, &bid.owner, sk_mint.key)?;
+            assert_keys_equal(asker_account.key, &ask.owner)?;
+            assert_owned_by(bidder_state, program_id)?;
+            assert_owned_by(asker_state, program_id)?;
+            assert_pda(
+                bidder_state,
+                &[sk_mint.key.as_ref(), bidder_account.key.as_ref()],
+                program_id,
+            )?;
+            assert_pda(
+                asker_state,
+                &[sk_mint.key.as_ref(), asker_account.key.as_ref()],
+                program_id,
+            )?;
+
+            let fill = bid.quantity.min(ask.quantity);
+
+            // the order resting on the book first sets the trade price
+            let price = if bid.sequence < ask.sequence {
+                bid.price
+            } else {
+                ask.price
+            };
+
+            let escrow_released = Self::bid_escrow(bid.price, bid.quantity, bid.fee_bps)?
+                .checked_sub(Self::bid_escrow(
+                    bid.price,
+                    bid.quantity - fill,
+                    bid.fee_bps,
+                )?)
+                .ok_or(DecenseError::MathError)?;
+
+            let proceeds = Self::order_cost(price, fill, false)?;
+
+            // the bidder pays the buy fee it escrowed, the asker the current sell fee
+            let buy_fee = Self::platform_fee(proceeds, bid.fee_bps)?;
+            let sell_fee =
+                Self::platform_fee(proceeds, unpacked_platform_state_account.sell_fee_bps)?;
+
+            let refund = escrow_released
+                .checked_sub(proceeds)
+                .and_then(|refund| refund.checked_sub(buy_fee))
+                .ok_or(DecenseError::MathError)?;
+
+            let unpacked_bidder_token_ata =
+                spl_token::state::Account::unpack(&bidder_token_ata.try_borrow_data()?)?;
+
+            let transfer_token_to_bidder = spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                ask_vault_ata.key,
+                sk_mint.key,
+                bidder_token_ata.key,
+                ask_vault_authority.key,
+                &[],
+                fill,
+                4,
+            )?;
+
+            invoke_signed(
+                &transfer_token_to_bidder,
+                &[
+                    ask_vault_ata.clone(),
+                    sk_mint.clone(),
+                    bidder_token_ata.clone(),
+                    ask_vault_authority.clone(),
+                    token_program_account.clone(),
+                ],
+                &[&[sk_mint.key.as_ref(), b"ask_vault", &[ask_vault_bump_seeds]]],
+            )?;
+
+            for (recipient, amount) in [
+                (asker_account, proceeds - sell_fee),
+                (bidder_account, refund),
+                (fee_recipient_account, buy_fee + sell_fee),
+            ] {
+                if amount == 0 {
+                    continue;
+                }
+
+                let transfer_sol =
+                    system_instruction::transfer(bid_vault_account.key, recipient.key, amount);
+
+                invoke_signed(
+                    &transfer_sol,
+                    &[
+                        bid_vault_account.clone(),
+                        recipient.clone(),
+                        system_program_account.clone(),
+                    ],
+                    &[&[sk_mint.key.as_ref(), b"bid_vault", &[bid_vault_bump_seeds]]],
+                )?;
+            }
+
+            // the asker's holder count dropped when the ask emptied its ata
+            if bidder_account.key != sk_account.key && unpacked_bidder_token_ata.amount == 0 {
+                unpacked_sk_state_account.holders = unpacked_sk_state_account
+                    .holders
+                    .checked_add(1)
+                    .ok_or(DecenseError::MathError)?;
+            }
+
+            let released_basis = unpacked_order_book.asks[0]
+                .fill(fill)
+                .ok_or(DecenseError::MathError)?;
+            if unpacked_order_book.asks[0].quantity == 0 {
+                unpacked_order_book.asks.remove(0);
+            }
+
+            unpacked_order_book.bids[0].quantity -= fill;
+            if unpacked_order_book.bids[0].quantity == 0 {
+                unpacked_order_book.bids.remove(0);
+            }
+
+            // both states may be the same account on a self-match, so each is packed in turn
+            let mut unpacked_asker_state = BuyerState::unpack(&asker_state.try_borrow_data()?)?;
+
+            assert_keys_equal(asker_account.key, &unpacked_asker_state.buyer)?;
+
+            unpacked_asker_state
+                .realize(proceeds - sell_fee, released_basis)
+                .ok_or(DecenseError::MathError)?;
+            BuyerState::pack(
+                unpacked_asker_state,
+                &mut asker_state.try_borrow_mut_data()?,
+            )?;
+
+            let mut unpacked_bidder_state = BuyerState::unpack(&bidder_state.try_borrow_data()?)?;
+
+            assert_keys_equal(bidder_account.key, &unpacked_bidder_state.buyer)?;
+
+            unpacked_bidder_state
+                .add_to_position(
+                    fill,
+                    proceeds
+                        .checked_add(buy_fee)
+                        .ok_or(DecenseError::MathError)?,
+                )
+                .ok_or(DecenseError::MathError)?;
+            BuyerState::pack(
+                unpacked_bidder_state,
+                &mut bidder_state.try_borrow_mut_data()?,
+            )?;
+
+            // a wash trade pays its fees but does not move the market price
+            if bidder_account.key != asker_account.key {
+                Self::record_trade_price(&mut unpacked_sk_state_account, price, now)?;
+            }
+
+            matches += 1;
+        }
+
+        UserState::pack(
+            unpacked_sk_state_account,
+            &mut sk_state_account.try_borrow_mut_data()?,
+        )?;
+
+        OrderBook::pack(
+            unpacked_order_book,
+            &mut order_book_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    // trading fee in lamports for fee_bps basis points of amount, rounded down
+    fn platform_fee(amount: u64, fee_bps: u16) -> Result<u64, ProgramError> {
+        let fee = (amount as u128)
+            .checked_mul(fee_bps as u128)
+            .ok_or(DecenseError::MathError)?
+            .checked_div(10000)
+            .ok_or(DecenseError::MathError)?;
+
+        Ok(fee as u64)
+    }
+
+    // a circuit breaker halt lifts itself once its cooldown is over
+    fn assert_trading(user_state: &mut UserState, now: i64) -> ProgramResult {
+        if user_state.halt_reason() == Some(HaltReason::CircuitBreaker)
+            && now >= user_state.halted_until
+        {
+            user_state.halt_reason = 0;
+        }
+
+        if user_state.halt_reason != 0 {
+            return Err(DecenseError::TradingHalted.into());
+        }
+
+        Ok(())
+    }
+
+    // balance of a token account that may not have been created yet
+    fn token_balance(token_account: &AccountInfo) -> Result<u64, ProgramError> {
+        if token_account.data_is_empty() {
+            return Ok(0);
+        }
+
+        Ok(spl_token::state::Account::unpack(&token_account.try_borrow_data()?)?.amount)
+    }
+
+    // the offering and the delisting halt trading themselves, such a halt can not be taken over
+    // or lifted by hand and the share count stays fixed meanwhile
+    fn assert_listing_active(user_state: &UserState) -> ProgramResult {
+        match user_state.halt_reason() {
+            Some(HaltReason::Delisted) => Err(DecenseError::ListingDelisted.into()),
+            Some(HaltReason::Offering) => Err(DecenseError::OfferingInProgress.into()),
+            _ => Ok(()),
+        }
+    }
+
+    // the mint can not tell old shares from converted ones, so a holder's tokens trade and redeem
+    // again only once their position is converted through every split of the listing
+    fn assert_split_claimed(
+        holder_state: &AccountInfo,
+        split_count: u64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        if split_count == 0 {
+            return Ok(());
+        }
+
+        if holder_state.data_is_empty() {
+            return Err(DecenseError::SplitNotClaimed.into());
+        }
+
+        assert_owned_by(holder_state, program_id)?;
+
+        let unpacked_holder_state = BuyerState::unpack(&holder_state.try_borrow_data()?)?;
+
+        if unpacked_holder_state.split_count != split_count {
+            return Err(DecenseError::SplitNotClaimed.into());
+        }
+
+        Ok(())
+    }
+
+    // sets cmp after a trade and halts trading when it moved too far within the breaker window
+    fn record_trade_price(user_state: &mut UserState, price: u64, now: i64) -> ProgramResult {
+        if user_state.breaker_max_move_bps > 0 {
+            let window_end = user_state
+                .breaker_window_start
+                .checked_add(user_state.breaker_window as i64)
+                .ok_or(DecenseError::MathError)?;
+
+            if user_state.breaker_reference_price == 0 || now >= window_end {
+                user_state.breaker_window_start = now;
+                user_state.breaker_reference_price = user_state.cmp;
+            }
+
+            let reference_price = user_state.breaker_reference_price as u128;
+            let price_move = reference_price.abs_diff(price as u128) * 10000;
+
+            if price_move > user_state.breaker_max_move_bps as u128 * reference_price {
+                user_state.halt_reason = HaltReason::CircuitBreaker as u8;
+                user_state.halted_until = now
+                    .checked_add(user_state.breaker_cooldown as i64)
+                    .ok_or(DecenseError::MathError)?;
+                // trading resumes with a fresh window
+                user_state.breaker_reference_price = 0;
+            }
+        }
+
+        user_state.cmp = price;
+
+        Ok(())
+    }
+
+    // supply counts whole tokens, so burned base units only show once they add up to one
+    fn update_supply(user_state: &mut UserState, mint: &AccountInfo) -> ProgramResult {
+        let unpacked_mint = spl_token::state::Mint::unpack(&mint.try_borrow_data()?)?;
+
+        user_state.supply = unpacked_mint.supply / 10000;
+
+        Ok(())
+    }
+
+    // the platform admin or the issuer of the listing, checked against the listing's platform
+    fn listing_authority(
+        program_id: &Pubkey,
+        authority: &AccountInfo,
+        platform_state_account: &AccountInfo,
+        user_state: &UserState,
+    ) -> Result<HaltReason, ProgramError> {
+        assert_signer(authority)?;
+        assert_platform_state(platform_state_account, program_id)?;
+        assert_keys_equal(platform_state_account.key, &user_state.platform_state)?;
+
+        let unpacked_platform_state_account =
+            PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
+
+        if *authority.key == unpacked_platform_state_account.admin {
+            Ok(HaltReason::Admin)
+        } else if *authority.key == user_state.user {
+            Ok(HaltReason::Issuer)
+        } else {
+            Err(DecenseError::Unauthorized.into())
+        }
+    }
+
+    // moves amount of an spl token, signed by a pda when seeds are given
+    fn transfer_tokens<'a>(
+        source: &AccountInfo<'a>,
+        mint: &AccountInfo<'a>,
+        destination: &AccountInfo<'a>,
+        authority: &AccountInfo<'a>,
+        token_program: &AccountInfo<'a>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let decimals = spl_token::state::Mint::unpack(&mint.try_borrow_data()?)?.decimals;
+
+        let transfer_ix = spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            source.key,
+            mint.key,
+            destination.key,
+            authority.key,
+            &[],
+            amount,
+            decimals,
+        )?;
+
+        invoke_signed(
+            &transfer_ix,
+            &[
+                source.clone(),
+                mint.clone(),
+                destination.clone(),
+                authority.clone(),
+                token_program.clone(),
+            ],
+            signer_seeds,
+        )
+    }
+
+    fn mint_tokens<'a>(
+        mint: &AccountInfo<'a>,
+        destination: &AccountInfo<'a>,
+        authority: &AccountInfo<'a>,
+        token_program: &AccountInfo<'a>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let decimals = spl_token::state::Mint::unpack(&mint.try_borrow_data()?)?.decimals;
+
+        let mint_ix = spl_token::instruction::mint_to_checked(
+            &spl_token::id(),
+            mint.key,
+            destination.key,
+            authority.key,
+            &[],
+            amount,
+            decimals,
+        )?;
+
+        invoke_signed(
+            &mint_ix,
+            &[
+                mint.clone(),
+                destination.clone(),
+                authority.clone(),
+                token_program.clone(),
+            ],
+            signer_seeds,
+        )
+    }
+
+    fn burn_tokens<'a>(
+        source: &AccountInfo<'a>,
+        mint: &AccountInfo<'a>,
+        authority: &AccountInfo<'a>,
+        token_program: &AccountInfo<'a>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let decimals = spl_token::state::Mint::unpack(&mint.try_borrow_data()?)?.decimals;
+
+        let burn_ix = spl_token::instruction::burn_checked(
+            &spl_token::id(),
+            source.key,
+            mint.key,
+            authority.key,
+            &[],
+            amount,
+            decimals,
+        )?;
+
+        invoke_signed(
+            &burn_ix,
+            &[
+                source.clone(),
+                mint.clone(),
+                authority.clone(),
+                token_program.clone(),
+            ],
+            signer_seeds,
+        )
+    }
+
+    fn process_update_platform_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        listing_fee: u64,
+        buy_fee_bps: u64,
+        sell_fee_bps: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let admin_account = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        let fee_recipient_account = next_account_info(account_info_iter)?;
+
+        assert_writable(platform_state_account)?;
+        assert_platform_state(platform_state_account, program_id)?;
+
+        let mut unpacked_platform_state_account =
+            PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
+
+        assert_platform_admin(admin_account, &unpacked_platform_state_account)?;
+
+        if buy_fee_bps > 10000 || sell_fee_bps > 10000 {
+            return Err(DecenseError::InvalidFee.into());
+        }
+
+        unpacked_platform_state_account.listing_fee = listing_fee;
+        unpacked_platform_state_account.buy_fee_bps = buy_fee_bps as u16;
+        unpacked_platform_state_account.sell_fee_bps = sell_fee_bps as u16;
+        unpacked_platform_state_account.fee_recipient = *fee_recipient_account.key;
+
+        PlatformState::pack(
+            unpacked_platform_state_account,
+            &mut platform_state_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    // first step of the admin handover, the new admin still has to accept
+    fn process_transfer_admin(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let admin_account = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        let new_admin_account = next_account_info(account_info_iter)?;
+
+        assert_writable(platform_state_account)?;
+        assert_platform_state(platform_state_account, program_id)?;
+
+        let mut unpacked_platform_state_account =
+            PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
+
+        assert_platform_admin(admin_account, &unpacked_platform_state_account)?;
+
+        unpacked_platform_state_account.pending_admin = *new_admin_account.key;
+
+        PlatformState::pack(
+            unpacked_platform_state_account,
+            &mut platform_state_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_accept_admin(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let new_admin_account = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        assert_signer(new_admin_account)?;
+        assert_writable(platform_state_account)?;
+        assert_platform_state(platform_state_account, program_id)?;
+
+        let mut unpacked_platform_state_account =
+            PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
+
+        if unpacked_platform_state_account.pending_admin == Pubkey::default()
+            || unpacked_platform_state_account.pending_admin != *new_admin_account.key
+        {
+            return Err(DecenseError::Unauthorized.into());
+        }
+
+        unpacked_platform_state_account.admin = *new_admin_account.key;
+        unpacked_platform_state_account.pending_admin = Pubkey::default();
+
+        PlatformState::pack(
+            unpacked_platform_state_account,
+            &mut platform_state_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_set_paused(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        paused: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let admin_account = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        assert_writable(platform_state_account)?;
+        assert_platform_state(platform_state_account, program_id)?;
+
+        let mut unpacked_platform_state_account =
+            PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
+
+        assert_platform_admin(admin_account, &unpacked_platform_state_account)?;
+
+        unpacked_platform_state_account.paused = paused;
+
+        PlatformState::pack(
+            unpacked_platform_state_account,
+            &mut platform_state_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_migrate_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let payer_account = next_account_info(account_info_iter)?;
+
+        let migrated_account = next_account_info(account_info_iter)?;
+
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(payer_account)?;
+        assert_writable(migrated_account)?;
+        assert_owned_by(migrated_account, program_id)?;
+        assert_system_program(system_program_account)?;
+
+        // layouts from before the account type are told apart by their size
+        let migrated_data = {
+            let data = migrated_account.try_borrow_data()?;
+
+            match data.len() {
+                PlatformState::BASELINE_LEN | PlatformState::LEGACY_LEN | PlatformState::LEN => {
+                    PlatformState::migrate(&data, program_id)?
+                }
+                UserState::BASELINE_LEN | UserState::LEGACY_LEN | UserState::LEN => {
+                    UserState::migrate(&data, program_id)?
+                }
+                BuyerState::LEGACY_LEN | BuyerState::LEN => BuyerState::migrate(&data, program_id)?,
+                _ => return Err(DecenseError::UnknownAccountLayout.into()),
+            }
+        };
+
+        // every layout from before accounts were derived from the program and the listed mint
+        // sits at an address derived from the wallet that created it, so migrated accounts move
+        match migrated_data.len() {
+            PlatformState::LEN => {
+                let platform_state_account = next_account_info(account_info_iter)?;
+
+                Self::move_platform_state(
+                    program_id,
+                    payer_account,
+                    migrated_account,
+                    platform_state_account,
+                    system_program_account,
+                    PlatformState::unpack(&migrated_data)?,
+                )
+            }
+            UserState::LEN => {
+                // listings created after the baseline keep their vault, vesting, order book and
+                // dividends under pdas derived from the issuer's wallet, only the baseline ones
+                // hold nothing but the pool and can move
+                if migrated_account.data_len() != UserState::BASELINE_LEN {
+                    return Err(DecenseError::UnmigratableListing.into());
+                }
+
+                Self::move_listing(
+                    program_id,
+                    payer_account,
+                    migrated_account,
+                    system_program_account,
+                    account_info_iter.as_slice(),
+                    UserState::unpack(&migrated_data)?,
+                )
+            }
+            _ => Self::move_position(
+                program_id,
+                payer_account,
+                migrated_account,
+                system_program_account,
+                account_info_iter.as_slice(),
+                BuyerState::unpack(&migrated_data)?,
+            ),
+        }
+    }
+
+    // platform states used to live at an address derived from their creator's wallet, they move
+    // to the program's platform pda and the old account is closed
+    fn move_platform_state<'a>(
+        program_id: &Pubkey,
+        payer_account: &AccountInfo<'a>,
+        legacy_platform_state_account: &AccountInfo<'a>,
+        platform_state_account: &AccountInfo<'a>,
+        system_program_account: &AccountInfo<'a>,
+        mut unpacked_platform_state_account: PlatformState,
+    ) -> ProgramResult {
+        assert_writable(platform_state_account)?;
+
+        let platform_state_bump_seeds =
+            assert_pda(platform_state_account, &[b"platform"], program_id)?;
+
+        if !platform_state_account.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        if unpacked_platform_state_account.admin == Pubkey::default() {
+            // the baseline had no admin, the wallet that created the platform state becomes it
+            let creator_platform_state =
+                Pubkey::create_with_seed(payer_account.key, "DECENSE PLATFORM", program_id)?;
+
+            assert_keys_equal(legacy_platform_state_account.key, &creator_platform_state)?;
+
+            unpacked_platform_state_account.admin = *payer_account.key;
+        } else {
+            assert_platform_admin(payer_account, &unpacked_platform_state_account)?;
+        }
+
+        let create_platform_state_account_ix = system_instruction::create_account(
+            payer_account.key,
+            platform_state_account.key,
+            Rent::default().minimum_balance(PlatformState::LEN),
+            PlatformState::LEN as u64,
+            program_id,
+        );
+
+        invoke_signed(
+            &create_platform_state_account_ix,
+            &[
+                payer_account.clone(),
+                platform_state_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[b"platform", &[platform_state_bump_seeds]]],
+        )?;
+
+        PlatformState::pack(
+            unpacked_platform_state_account,
+            &mut platform_state_account.try_borrow_mut_data()?,
+        )?;
+
+        **payer_account.try_borrow_mut_lamports()? = payer_account
+            .lamports()
+            .checked_add(legacy_platform_state_account.lamports())
+            .ok_or(DecenseError::MathError)?;
+        **legacy_platform_state_account.try_borrow_mut_lamports()? = 0;
+        legacy_platform_state_account.try_borrow_mut_data()?.fill(0);
+
+        Ok(())
+    }
+
+    // baseline listings kept their state at an address derived from the issuer's wallet, their
+    // pool in a token account of the pda derived from it and the mint and freeze authority with
+    // the issuer. the issuer moves all of it to the listing's pdas, hands the mint over to the
+    // listing pda and drops the freeze authority.
+    fn move_listing<'a>(
+        program_id: &Pubkey,
+        user_account: &AccountInfo<'a>,
+        legacy_user_state_account: &AccountInfo<'a>,
+        system_program_account: &AccountInfo<'a>,
+        accounts: &[AccountInfo<'a>],
+        mut unpacked_user_state_account: UserState,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let user_state_account = next_account_info(account_info_iter)?;
+
+        let user_mint = next_account_info(account_info_iter)?;
+
+        let legacy_pda_account = next_account_info(account_info_iter)?;
+
+        let legacy_pda_ata = next_account_info(account_info_iter)?;
+
+        let pda_account = next_account_info(account_info_iter)?;
+
+        let pda_ata = next_account_info(account_info_iter)?;
+
+        let vault_account = next_account_info(account_info_iter)?;
+
+        let user_ata = next_account_info(account_info_iter)?;
+
+        let user_buyer_state = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        let associated_token_account_program_account = next_account_info(account_info_iter)?;
+
+        assert_keys_equal(user_account.key, &unpacked_user_state_account.user)?;
+        assert_keys_equal(
+            legacy_user_state_account.key,
+            &Pubkey::create_with_seed(user_account.key, "DECENSE USER", program_id)?,
+        )?;
+        assert_keys_equal(user_mint.key, &unpacked_user_state_account.user_token_mint)?;
+        assert_keys_equal(legacy_pda_ata.key, &unpacked_user_state_account.pda_ata)?;
+        assert_keys_equal(user_ata.key, &unpacked_user_state_account.user_ata)?;
+        assert_writable(user_state_account)?;
+        assert_writable(user_mint)?;
+        assert_writable(legacy_pda_ata)?;
+        assert_writable(pda_ata)?;
+        assert_writable(vault_account)?;
+        assert_writable(user_buyer_state)?;
+        assert_owned_by(user_mint, &spl_token::id())?;
+        assert_mint_authority(user_mint, user_account.key)?;
+        assert_token_program(token_program_account)?;
+        assert_associated_token_program(associated_token_account_program_account)?;
+
+        let user_state_bump_seeds = assert_pda(
+            user_state_account,
+            &[user_mint.key.as_ref(), b"user_state"],
+            program_id,
+        )?;
+        let legacy_bump_seeds =
+            assert_pda(legacy_pda_account, &[user_account.key.as_ref()], program_id)?;
+        assert_pda(pda_account, &[user_mint.key.as_ref()], program_id)?;
+        assert_associated_token_account(pda_ata, pda_account.key, user_mint.key)?;
+        assert_pda(
+            vault_account,
+            &[user_mint.key.as_ref(), b"vault"],
+            program_id,
+        )?;
+        let user_buyer_state_bump_seeds = assert_pda(
+            user_buyer_state,
+            &[user_mint.key.as_ref(), user_account.key.as_ref()],
+            program_id,
+        )?;
+
+        if !user_state_account.data_is_empty() || !user_buyer_state.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        // create user state account
+        let create_user_state_account_ix = system_instruction::create_account(
+            user_account.key,
+            user_state_account.key,
+            Rent::default().minimum_balance(UserState::LEN),
+            UserState::LEN as u64,
+            program_id,
+        );
+
+        invoke_signed(
+            &create_user_state_account_ix,
+            &[
+                user_account.clone(),
+                user_state_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[
+                user_mint.key.as_ref(),
+                b"user_state",
+                &[user_state_bump_seeds],
+            ]],
+        )?;
+
+        // create mint ata for pda, anybody could have created it already
+        let create_pda_ata_ix = create_associated_token_account_idempotent(
+            user_account.key,
+            pda_account.key,
+            user_mint.key,
+            &spl_token::id(),
+        );
+
+        invoke(
+            &create_pda_ata_ix,
+            &[
+                user_account.clone(),
+                pda_ata.clone(),
+                pda_account.clone(),
+                user_mint.clone(),
+                system_program_account.clone(),
+                token_program_account.clone(),
+                associated_token_account_program_account.clone(),
+            ],
+        )?;
+
+        // move the pool to the listing pda and close the old pool account
+        let legacy_pda_seeds: &[&[u8]] = &[user_account.key.as_ref(), &[legacy_bump_seeds]];
+
+        Self::transfer_tokens(
+            legacy_pda_ata,
+            user_mint,
+            pda_ata,
+            legacy_pda_account,
+            token_program_account,
+            Self::token_balance(legacy_pda_ata)?,
+            &[legacy_pda_seeds],
+        )?;
+
+        let close_legacy_pda_ata_ix = spl_token::instruction::close_account(
+            &spl_token::id(),
+            legacy_pda_ata.key,
+            user_account.key,
+            legacy_pda_account.key,
+            &[],
+        )?;
+
+        invoke_signed(
+            &close_legacy_pda_ata_ix,
+            &[
+                legacy_pda_ata.clone(),
+                user_account.clone(),
+                legacy_pda_account.clone(),
+                token_program_account.clone(),
+            ],
+            &[legacy_pda_seeds],
+        )?;
+
+        // only the listing pda can mint more and nobody can freeze holders
+        let set_mint_authority_ix = spl_token::instruction::set_authority(
+            &spl_token::id(),
+            user_mint.key,
+            Some(pda_account.key),
+            spl_token::instruction::AuthorityType::MintTokens,
+            user_account.key,
+            &[],
+        )?;
+
+        invoke(
+            &set_mint_authority_ix,
+            &[
+                user_mint.clone(),
+                user_account.clone(),
+                token_program_account.clone(),
+            ],
+        )?;
+
+        let unset_freeze_authority_ix = spl_token::instruction::set_authority(
+            &spl_token::id(),
+            user_mint.key,
+            None,
+            spl_token::instruction::AuthorityType::FreezeAccount,
+            user_account.key,
+            &[],
+        )?;
+
+        invoke(
+            &unset_freeze_authority_ix,
+            &[
+                user_mint.clone(),
+                user_account.clone(),
+                token_program_account.clone(),
+            ],
+        )?;
+
+        // the baseline paid every buy to the issuer, the vault starts out holding only its rent
+        // and pays sells out of what buys deposit from here on
+        let vault_rent = Rent::default()
+            .minimum_balance(0)
+            .saturating_sub(vault_account.lamports());
+
+        if vault_rent > 0 {
+            let fund_vault_ix =
+                system_instruction::transfer(user_account.key, vault_account.key, vault_rent);
+
+            invoke(
+                &fund_vault_ix,
+                &[
+                    user_account.clone(),
+                    vault_account.clone(),
+                    system_program_account.clone(),
+                ],
+            )?;
+        }
+
+        // the issuer's position holds what it kept of the supply at no cost
+        let create_user_buyer_state_ix = system_instruction::create_account(
+            user_account.key,
+            user_buyer_state.key,
+            Rent::default().minimum_balance(BuyerState::LEN),
+            BuyerState::LEN as u64,
+            program_id,
+        );
+
+        invoke_signed(
+            &create_user_buyer_state_ix,
+            &[
+                user_account.clone(),
+                user_buyer_state.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[
+                user_mint.key.as_ref(),
+                user_account.key.as_ref(),
+                &[user_buyer_state_bump_seeds],
+            ]],
+        )?;
+
+        let mut unpacked_user_buyer_state =
+            BuyerState::unpack_unchecked(&user_buyer_state.try_borrow_data()?)?;
+
+        unpacked_user_buyer_state.is_initialized = true;
+        unpacked_user_buyer_state.buyer = *user_account.key;
+        unpacked_user_buyer_state.mint = *user_mint.key;
+        unpacked_user_buyer_state.current_holding_in_tokens = Self::token_balance(user_ata)?;
+
+        BuyerState::pack(
+            unpacked_user_buyer_state,
+            &mut user_buyer_state.try_borrow_mut_data()?,
+        )?;
+
+        unpacked_user_state_account.pda_ata = *pda_ata.key;
+
+        UserState::pack(
+            unpacked_user_state_account,
+            &mut user_state_account.try_borrow_mut_data()?,
+        )?;
+
+        **user_account.try_borrow_mut_lamports()? = user_account
+            .lamports()
+            .checked_add(legacy_user_state_account.lamports())
+            .ok_or(DecenseError::MathError)?;
+        **legacy_user_state_account.try_borrow_mut_lamports()? = 0;
+        legacy_user_state_account.try_borrow_mut_data()?.fill(0);
+
+        Ok(())
+    }
+
+    // positions used to live at an address derived from the holder's wallet and cover every
+    // listing, the holder moves theirs to the position of one listing. it can not hold more than
+    // the holder's balance of that listing's tokens and its cost is unknown.
+    fn move_position<'a>(
+        program_id: &Pubkey,
+        buyer_account: &AccountInfo<'a>,
+        legacy_buyer_state: &AccountInfo<'a>,
+        system_program_account: &AccountInfo<'a>,
+        accounts: &[AccountInfo<'a>],
+        mut unpacked_buyer_state: BuyerState,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let buyer_state = next_account_info(account_info_iter)?;
+
+        let buyer_token_ata = next_account_info(account_info_iter)?;
+
+        assert_keys_equal(buyer_account.key, &unpacked_buyer_state.buyer)?;
+        assert_keys_equal(
+            legacy_buyer_state.key,
+            &Pubkey::create_with_seed(buyer_account.key, "DECENSE BUYER", program_id)?,
+        )?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_writable(buyer_state)?;
+
+        let unpacked_sk_state_account = UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+        let sk_mint = unpacked_sk_state_account.user_token_mint;
+
+        assert_pda(
+            sk_state_account,
+            &[sk_mint.as_ref(), b"user_state"],
+            program_id,
+        )?;
+        let buyer_state_bump_seeds = assert_pda(
+            buyer_state,
+            &[sk_mint.as_ref(), buyer_account.key.as_ref()],
+            program_id,
+        )?;
+        assert_associated_token_account(buyer_token_ata, buyer_account.key, &sk_mint)?;
+
+        if !buyer_state.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        // create buyer state account
+        let create_buyer_state_account_ix = system_instruction::create_account(
+            buyer_account.key,
+            buyer_state.key,
+            Rent::default().minimum_balance(BuyerState::LEN),
+            BuyerState::LEN as u64,
+            program_id,
+        );
+
+        invoke_signed(
+            &create_buyer_state_account_ix,
+            &[
+                buyer_account.clone(),
+                buyer_state.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[
+                sk_mint.as_ref(),
+                buyer_account.key.as_ref(),
+                &[buyer_state_bump_seeds],
+            ]],
+        )?;
+
+        unpacked_buyer_state.mint = sk_mint;
+        unpacked_buyer_state.current_holding_in_tokens = unpacked_buyer_state
+            .current_holding_in_tokens
+            .min(Self::token_balance(buyer_token_ata)?);
+        unpacked_buyer_state.cost_basis = 0;
+        // the holder's tokens are counted in the listing's current units
+        unpacked_buyer_state.split_count = unpacked_sk_state_account.split_count;
+
+        BuyerState::pack(
+            unpacked_buyer_state,
+            &mut buyer_state.try_borrow_mut_data()?,
+        )?;
+
+        **buyer_account.try_borrow_mut_lamports()? = buyer_account
+            .lamports()
+            .checked_add(legacy_buyer_state.lamports())
+            .ok_or(DecenseError::MathError)?;
+        **legacy_buyer_state.try_borrow_mut_lamports()? = 0;
+        legacy_buyer_state.try_borrow_mut_data()?.fill(0);
+
+        Ok(())
+    }
+
+    fn process_declare_dividend(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount_per_token: u64,
+        eligible_supply: u64,
+        claim_period: u64,
+        snapshot_root: [u8; 32],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let sk_account = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let pda_token_ata = next_account_info(account_info_iter)?;
+
+        let vesting_account = next_account_info(account_info_iter)?;
+
+        let vesting_ata = next_account_info(account_info_iter)?;
+
+        let dividend_account = next_account_info(account_info_iter)?;
+
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(sk_account)?;
+        assert_writable(sk_state_account)?;
+        assert_writable(dividend_account)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_owned_by(sk_mint, &spl_token::id())?;
+        assert_system_program(system_program_account)?;
+
+        let mut unpacked_sk_state_account =
+            UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_account.key, &unpacked_sk_state_account.user)?;
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+        assert_keys_equal(pda_token_ata.key, &unpacked_sk_state_account.pda_ata)?;
+        assert_pda(
+            vesting_account,
+            &[sk_mint.key.as_ref(), b"vesting"],
+            program_id,
+        )?;
+        assert_associated_token_account(vesting_ata, vesting_account.key, sk_mint.key)?;
+
+        let index = unpacked_sk_state_account.dividend_count;
+        let dividend_bump_seeds = assert_pda(
+            dividend_account,
+            &[sk_mint.key.as_ref(), b"dividend", &index.to_le_bytes()],
+            program_id,
+        )?;
+
+        let unpacked_sk_mint = spl_token::state::Mint::unpack(&sk_mint.try_borrow_data()?)?;
+
+        // the snapshot is built off-chain, so at least it can not count the pool or the unvested
+        // share, which belong to no holder
+        let outstanding_supply = unpacked_sk_mint
+            .supply
+            .checked_sub(Self::token_balance(pda_token_ata)?)
+            .ok_or(DecenseError::MathError)?
+            .checked_sub(Self::token_balance(vesting_ata)?)
+            .ok_or(DecenseError::MathError)?;
+
+        if amount_per_token == 0
+            || eligible_supply == 0
+            || eligible_supply > outstanding_supply
+            || claim_period == 0
+        {
+            return Err(DecenseError::InvalidDividend.into());
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let claim_deadline = now
+            .checked_add(i64::try_from(claim_period).map_err(|_| DecenseError::MathError)?)
+            .ok_or(DecenseError::MathError)?;
+
+        let total_amount = Self::order_cost(amount_per_token, eligible_supply, true)?;
+
+        let quote_mint = unpacked_sk_state_account.quote_mint;
+        let sol_deposit = if quote_mint == Pubkey::default() {
+            total_amount
+        } else {
+            0
+        };
+
+        // create dividend account, it holds the deposit itself for listings priced in lamports
+        let create_dividend_account_ix = system_instruction::create_account(
+            sk_account.key,
+            dividend_account.key,
+            Rent::default()
+                .minimum_balance(Dividend::LEN)
+                .checked_add(sol_deposit)
+                .ok_or(DecenseError::MathError)?,
+            Dividend::LEN as u64,
+            program_id,
+        );
+
+        invoke_signed(
+            &create_dividend_account_ix,
+            &[
+                sk_account.clone(),
+                dividend_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[
+                sk_mint.key.as_ref(),
+                b"dividend",
+                &index.to_le_bytes(),
+                &[dividend_bump_seeds],
+            ]],
+        )?;
+
+        if quote_mint != Pubkey::default() {
+            // the deposit of spl quoted listings goes to the dividend's quote token account
+            let quote_mint_account = next_account_info(account_info_iter)?;
+
+            let sk_quote_account = next_account_info(account_info_iter)?;
+
+            let dividend_quote_vault = next_account_info(account_info_iter)?;
+
+            let token_program_account = next_account_info(account_info_iter)?;
+
+            let rent_sysvar_account = next_account_info(account_info_iter)?;
+
+            let associated_token_account_program_account = next_account_info(account_info_iter)?;
+
+            assert_keys_equal(quote_mint_account.key, &quote_mint)?;
+            assert_associated_token_account(
+                sk_quote_account,
+                sk_account.key,
+                quote_mint_account.key,
+            )?;
+            assert_associated_token_account(
+                dividend_quote_vault,
+                dividend_account.key,
+                quote_mint_account.key,
+            )?;
+            assert_token_program(token_program_account)?;
+            assert_rent_sysvar(rent_sysvar_account)?;
+            assert_associated_token_program(associated_token_account_program_account)?;
+
+            let create_dividend_quote_vault_ix = create_associated_token_account(
+                sk_account.key,
+                dividend_account.key,
+                quote_mint_account.key,
+                &spl_token::id(),
+            );
+
+            invoke(
+                &create_dividend_quote_vault_ix,
+                &[
+                    sk_account.clone(),
+                    dividend_quote_vault.clone(),
+                    dividend_account.clone(),
+                    quote_mint_account.clone(),
+                    system_program_account.clone(),
+                    token_program_account.clone(),
+                    rent_sysvar_account.clone(),
+                    associated_token_account_program_account.clone(),
+                ],
+            )?;
+
+            Self::transfer_tokens(
+                sk_quote_account,
+                quote_mint_account,
+                dividend_quote_vault,
+                sk_account,
+                token_program_account,
+                total_amount,
+                &[],
+            )?;
+        }
+
+        let mut unpacked_dividend =
+            Dividend::unpack_unchecked(&dividend_account.try_borrow_data()?)?;
+
+        unpacked_dividend.is_initialized = true;
+        unpacked_dividend.user_token_mint = *sk_mint.key;
+        unpacked_dividend.quote_mint = quote_mint;
+        unpacked_dividend.index = index;
+        unpacked_dividend.amount_per_token = amount_per_token;
+        unpacked_dividend.snapshot_root = snapshot_root;
+        unpacked_dividend.snapshot_slot = Clock::get()?.slot;
+        unpacked_dividend.total_amount = total_amount;
+        unpacked_dividend.claim_deadline = claim_deadline;
+
+        Dividend::pack(
+            unpacked_dividend,
+            &mut dividend_account.try_borrow_mut_data()?,
+        )?;
+
+        unpacked_sk_state_account.dividend_count =
+            index.checked_add(1).ok_or(DecenseError::MathError)?;
+        UserState::pack(
+            unpacked_sk_state_account,
+            &mut sk_state_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_claim_dividend(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        proof: &[[u8; 32]],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let holder_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let dividend_account = next_account_info(account_info_iter)?;
+
+        let claim_account = next_account_info(account_info_iter)?;
+
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(holder_account)?;
+        assert_writable(dividend_account)?;
+        assert_writable(claim_account)?;
+        assert_owned_by(dividend_account, program_id)?;
+        assert_system_program(system_program_account)?;
+
+        let mut unpacked_dividend = Dividend::unpack(&dividend_account.try_borrow_data()?)?;
+
+        let dividend_bump_seeds = assert_pda(
+            dividend_account,
+            &[
+                sk_mint.key.as_ref(),
+                b"dividend",
+                &unpacked_dividend.index.to_le_bytes(),
+            ],
+            program_id,
+        )?;
+        let claim_bump_seeds = assert_pda(
+            claim_account,
+            &[dividend_account.key.as_ref(), holder_account.key.as_ref()],
+            program_id,
+        )?;
+
+        if !claim_account.data_is_empty() {
+            return Err(DecenseError::DividendAlreadyClaimed.into());
+        }
+
+        if Clock::get()?.unix_timestamp >= unpacked_dividend.claim_deadline {
+            return Err(DecenseError::DividendExpired.into());
+        }
+
+        if !snapshot::verify(
+            &unpacked_dividend.snapshot_root,
+            snapshot::leaf(holder_account.key, amount),
+            proof,
+        ) {
+            return Err(DecenseError::InvalidSnapshotProof.into());
+        }
+
+        let payout = Self::order_cost(unpacked_dividend.amount_per_token, amount, false)?;
+
+        unpacked_dividend.claimed_amount = unpacked_dividend
+            .claimed_amount
+            .checked_add(payout)
+            .ok_or(DecenseError::MathError)?;
+
+        if unpacked_dividend.claimed_amount > unpacked_dividend.total_amount {
+            return Err(DecenseError::InsufficientVaultBalance.into());
+        }
+
+        // create claim record
+        let create_claim_account_ix = system_instruction::create_account(
+            holder_account.key,
+            claim_account.key,
+            Rent::default().minimum_balance(DividendClaim::LEN),
+            DividendClaim::LEN as u64,
+            program_id,
+        );
+
+        invoke_signed(
+            &create_claim_account_ix,
+            &[
+                holder_account.clone(),
+                claim_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[
+                dividend_account.key.as_ref(),
+                holder_account.key.as_ref(),
+                &[claim_bump_seeds],
+            ]],
+        )?;
+
+        DividendClaim::pack(
+            DividendClaim {
+                is_initialized: true,
+                dividend: *dividend_account.key,
+                holder: *holder_account.key,
+                amount: payout,
+            },
+            &mut claim_account.try_borrow_mut_data()?,
+        )?;
+
+        if unpacked_dividend.quote_mint == Pubkey::default() {
+            // the dividend account is owned by the program, so lamports move directly
+            **dividend_account.try_borrow_mut_lamports()? = dividend_account
+                .lamports()
+                .checked_sub(payout)
+                .ok_or(DecenseError::InsufficientVaultBalance)?;
+            **holder_account.try_borrow_mut_lamports()? = holder_account
+                .lamports()
+                .checked_add(payout)
+                .ok_or(DecenseError::MathError)?;
+        } else {
+            let quote_mint_account = next_account_info(account_info_iter)?;
+
+            let holder_quote_account = next_account_info(account_info_iter)?;
+
+            let dividend_quote_vault = next_account_info(account_info_iter)?;
+
+            let token_program_account = next_account_info(account_info_iter)?;
+
+            assert_keys_equal(quote_mint_account.key, &unpacked_dividend.quote_mint)?;
+            assert_associated_token_account(
+                holder_quote_account,
+                holder_account.key,
+                quote_mint_account.key,
+            )?;
+            assert_associated_token_account(
+                dividend_quote_vault,
+                dividend_account.key,
+                quote_mint_account.key,
+            )?;
+            assert_token_program(token_program_account)?;
+
+            Self::transfer_tokens(
+                dividend_quote_vault,
+                quote_mint_account,
+                holder_quote_account,
+                dividend_account,
+                token_program_account,
+                payout,
+                &[&[
+                    sk_mint.key.as_ref(),
+                    b"dividend",
+                    &unpacked_dividend.index.to_le_bytes(),
+                    &[dividend_bump_seeds],
+                ]],
+            )?;
+        }
+
+        Dividend::pack(
+            unpacked_dividend,
+            &mut dividend_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_reclaim_dividend(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let sk_account = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let dividend_account = next_account_info(account_info_iter)?;
+
+        assert_signer(sk_account)?;
+        assert_writable(dividend_account)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_owned_by(dividend_account, program_id)?;
+
+        let unpacked_sk_state_account = UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_account.key, &unpacked_sk_state_account.user)?;
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+
+        let unpacked_dividend = Dividend::unpack(&dividend_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_mint.key, &unpacked_dividend.user_token_mint)?;
+
+        let dividend_bump_seeds = assert_pda(
+            dividend_account,
+            &[
+                sk_mint.key.as_ref(),
+                b"dividend",
+                &unpacked_dividend.index.to_le_bytes(),
+            ],
+            program_id,
+        )?;
+
+        if Clock::get()?.unix_timestamp < unpacked_dividend.claim_deadline {
+            return Err(DecenseError::DividendNotExpired.into());
+        }
+
+        if unpacked_dividend.quote_mint != Pubkey::default() {
+            let quote_mint_account = next_account_info(account_info_iter)?;
+
+            let sk_quote_account = next_account_info(account_info_iter)?;
+
+            let dividend_quote_vault = next_account_info(account_info_iter)?;
+
+            let token_program_account = next_account_info(account_info_iter)?;
+
+            assert_keys_equal(quote_mint_account.key, &unpacked_dividend.quote_mint)?;
+            assert_associated_token_account(
+                sk_quote_account,
+                sk_account.key,
+                quote_mint_account.key,
+            )?;
+            assert_associated_token_account(
+                dividend_quote_vault,
+                dividend_account.key,
+                quote_mint_account.key,
+            )?;
+            assert_token_program(token_program_account)?;
+
+            let dividend_seeds: &[&[u8]] = &[
+                sk_mint.key.as_ref(),
+                b"dividend",
+                &unpacked_dividend.index.to_le_bytes(),
+                &[dividend_bump_seeds],
+            ];
+
+            let unclaimed = Self::token_balance(dividend_quote_vault)?;
+
+            if unclaimed > 0 {
+                Self::transfer_tokens(
+                    dividend_quote_vault,
+                    quote_mint_account,
+                    sk_quote_account,
+                    dividend_account,
+                    token_program_account,
+                    unclaimed,
+                    &[dividend_seeds],
+                )?;
+            }
+
+            let close_dividend_quote_vault_ix = spl_token::instruction::close_account(
+                &spl_token::id(),
+                dividend_quote_vault.key,
+                sk_account.key,
+                dividend_account.key,
+                &[],
+            )?;
+
+            invoke_signed(
+                &close_dividend_quote_vault_ix,
+                &[
+                    dividend_quote_vault.clone(),
+                    sk_account.clone(),
+                    dividend_account.clone(),
+                    token_program_account.clone(),
+                ],
+                &[dividend_seeds],
+            )?;
+        }
+
+        // unclaimed lamports and the rent go back to the issuer, later claims find no dividend
+        **sk_account.try_borrow_mut_lamports()? = sk_account
+            .lamports()
+            .checked_add(dividend_account.lamports())
+            .ok_or(DecenseError::MathError)?;
+        **dividend_account.try_borrow_mut_lamports()? = 0;
+        dividend_account.try_borrow_mut_data()?.fill(0);
+
+        Ok(())
+    }
+
+    fn process_create_proposal(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        voting_period: u64,
+        quorum: u64,
+        threshold_bps: u64,
+        description_hash: [u8; 32],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let proposer_account = next_account_info(account_info_iter)?;
+
+        let proposer_token_ata = next_account_info(account_info_iter)?;
+
+        let sk_account = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let proposal_account = next_account_info(account_info_iter)?;
+
+        let escrow_ata = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        let rent_sysvar_account = next_account_info(account_info_iter)?;
+
+        let associated_token_account_program_account = next_account_info(account_info_iter)?;
+
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(proposer_account)?;
+        assert_writable(sk_state_account)?;
+        assert_writable(proposal_account)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_owned_by(sk_mint, &spl_token::id())?;
+        assert_owned_by(proposer_token_ata, &spl_token::id())?;
+        assert_associated_token_account(proposer_token_ata, proposer_account.key, sk_mint.key)?;
+        assert_token_program(token_program_account)?;
+        assert_rent_sysvar(rent_sysvar_account)?;
+        assert_associated_token_program(associated_token_account_program_account)?;
+        assert_system_program(system_program_account)?;
+
+        let mut unpacked_sk_state_account =
+            UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_account.key, &unpacked_sk_state_account.user)?;
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+
+        let index = unpacked_sk_state_account.proposal_count;
+        let proposal_bump_seeds = assert_pda(
+            proposal_account,
+            &[sk_mint.key.as_ref(), b"proposal", &index.to_le_bytes()],
+            program_id,
+        )?;
+        assert_associated_token_account(escrow_ata, proposal_account.key, sk_mint.key)?;
+
+        // only holders can put a proposal to the vote
+        let unpacked_proposer_token_ata =
+            spl_token::state::Account::unpack(&proposer_token_ata.try_borrow_data()?)?;
+
+        if unpacked_proposer_token_ata.amount == 0 {
+            return Err(DecenseError::InsufficientTokenBalance.into());
+        }
+
+        let unpacked_sk_mint = spl_token::state::Mint::unpack(&sk_mint.try_borrow_data()?)?;
+
+        let voting_period =
+            i64::try_from(voting_period).map_err(|_| DecenseError::InvalidProposal)?;
+
+        if voting_period == 0
+            || threshold_bps == 0
+            || threshold_bps > 10000
+            || quorum > unpacked_sk_mint.supply
+        {
+            return Err(DecenseError::InvalidProposal.into());
+        }
+
+        let voting_ends_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(voting_period)
+            .ok_or(DecenseError::MathError)?;
+
+        // create proposal account
+        let create_proposal_account_ix = system_instruction::create_account(
+            proposer_account.key,
+            proposal_account.key,
+            Rent::default().minimum_balance(Proposal::LEN),
+            Proposal::LEN as u64,
+            program_id,
+        );
+
+        invoke_signed(
+            &create_proposal_account_ix,
+            &[
+                proposer_account.clone(),
+                proposal_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[
+                sk_mint.key.as_ref(),
+                b"proposal",
+                &index.to_le_bytes(),
+                &[proposal_bump_seeds],
+            ]],
+        )?;
+
+        // votes are escrowed in the proposal's token account until voting ends
+        let create_escrow_ata_ix = create_associated_token_account(
+            proposer_account.key,
+            proposal_account.key,
+            sk_mint.key,
+            &spl_token::id(),
+        );
+
+        invoke(
+            &create_escrow_ata_ix,
+            &[
+                proposer_account.clone(),
+                escrow_ata.clone(),
+                proposal_account.clone(),
+                sk_mint.clone(),
+                system_program_account.clone(),
+                token_program_account.clone(),
+                rent_sysvar_account.clone(),
+                associated_token_account_program_account.clone(),
+            ],
+        )?;
+
+        Proposal::pack(
+            Proposal {
+                is_initialized: true,
+                user_token_mint: *sk_mint.key,
+                proposer: *proposer_account.key,
+                index,
+                description_hash,
+                voting_ends_at,
+                quorum,
+                threshold_bps: threshold_bps as u16,
+                yes_votes: 0,
+                no_votes: 0,
+                status: ProposalStatus::Voting,
+            },
+            &mut proposal_account.try_borrow_mut_data()?,
+        )?;
+
+        unpacked_sk_state_account.proposal_count =
+            index.checked_add(1).ok_or(DecenseError::MathError)?;
+        UserState::pack(
+            unpacked_sk_state_account,
+            &mut sk_state_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_cast_vote(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        support: u64,
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let voter_account = next_account_info(account_info_iter)?;
+
+        let voter_token_ata = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let proposal_account = next_account_info(account_info_iter)?;
+
+        let escrow_ata = next_account_info(account_info_iter)?;
+
+        let vote_record_account = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(voter_account)?;
+        assert_writable(proposal_account)?;
+        assert_writable(vote_record_account)?;
+        assert_owned_by(proposal_account, program_id)?;
+        assert_associated_token_account(voter_token_ata, voter_account.key, sk_mint.key)?;
+        assert_token_program(token_program_account)?;
+        assert_system_program(system_program_account)?;
+
+        let mut unpacked_proposal = Proposal::unpack(&proposal_account.try_borrow_data()?)?;
+
+        assert_pda(
+            proposal_account,
+            &[
+                sk_mint.key.as_ref(),
+                b"proposal",
+                &unpacked_proposal.index.to_le_bytes(),
+            ],
+            program_id,
+        )?;
+        assert_keys_equal(sk_mint.key, &unpacked_proposal.user_token_mint)?;
+        assert_associated_token_account(escrow_ata, proposal_account.key, sk_mint.key)?;
+
+        let vote_record_bump_seeds = assert_pda(
+            vote_record_account,
+            &[proposal_account.key.as_ref(), voter_account.key.as_ref()],
+            program_id,
+        )?;
+
+        if unpacked_proposal.status != ProposalStatus::Voting
+            || Clock::get()?.unix_timestamp >= unpacked_proposal.voting_ends_at
+        {
+            return Err(DecenseError::VotingClosed.into());
+        }
+
+        if support > 1 || amount == 0 {
+            return Err(DecenseError::InvalidNumber.into());
+        }
+
+        if !vote_record_account.data_is_empty() {
+            return Err(DecenseError::VoteAlreadyCast.into());
+        }
+
+        // create vote record
+        let create_vote_record_account_ix = system_instruction::create_account(
+            voter_account.key,
+            vote_record_account.key,
+            Rent::default().minimum_balance(VoteRecord::LEN),
+            VoteRecord::LEN as u64,
+            program_id,
+        );
+
+        invoke_signed(
+            &create_vote_record_account_ix,
+            &[
+                voter_account.clone(),
+                vote_record_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[
+                proposal_account.key.as_ref(),
+                voter_account.key.as_ref(),
+                &[vote_record_bump_seeds],
+            ]],
+        )?;
+
+        VoteRecord::pack(
+            VoteRecord {
+                is_initialized: true,
+                proposal: *proposal_account.key,
+                voter: *voter_account.key,
+                amount,
+                in_favor: support == 1,
+            },
+            &mut vote_record_account.try_borrow_mut_data()?,
+        )?;
+
+        // the voting weight is locked in the escrow for the rest of the vote
+        Self::transfer_tokens(
+            voter_token_ata,
+            sk_mint,
+            escrow_ata,
+            voter_account,
+            token_program_account,
+            amount,
+            &[],
+        )?;
+
+        if support == 1 {
+            unpacked_proposal.yes_votes = unpacked_proposal
+                .yes_votes
+                .checked_add(amount)
+                .ok_or(DecenseError::MathError)?;
+        } else {
+            unpacked_proposal.no_votes = unpacked_proposal
+                .no_votes
+                .checked_add(amount)
+                .ok_or(DecenseError::MathError)?;
+        }
+
+        Proposal::pack(
+            unpacked_proposal,
+            &mut proposal_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_finalize_proposal(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let proposal_account = next_account_info(account_info_iter)?;
+
+        assert_writable(proposal_account)?;
+        assert_owned_by(proposal_account, program_id)?;
+
+        let mut unpacked_proposal = Proposal::unpack(&proposal_account.try_borrow_data()?)?;
+
+        if unpacked_proposal.status != ProposalStatus::Voting {
+            return Err(DecenseError::VotingClosed.into());
+        }
+
+        if Clock::get()?.unix_timestamp < unpacked_proposal.voting_ends_at {
+            return Err(DecenseError::VotingInProgress.into());
+        }
+
+        let total_votes = (unpacked_proposal.yes_votes as u128)
+            .checked_add(unpacked_proposal.no_votes as u128)
+            .ok_or(DecenseError::MathError)?;
+
+        // passes when quorum is met and the yes share reaches the threshold
+        let passed = total_votes > 0
+            && total_votes >= unpacked_proposal.quorum as u128
+            && unpacked_proposal.yes_votes as u128 * 10000
+                >= unpacked_proposal.threshold_bps as u128 * total_votes;
+
+        unpacked_proposal.status = if passed {
+            ProposalStatus::Passed
+        } else {
+            ProposalStatus::Rejected
+        };
+
+        Proposal::pack(
+            unpacked_proposal,
+            &mut proposal_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_withdraw_vote(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let voter_account = next_account_info(account_info_iter)?;
+
+        let voter_token_ata = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let proposal_account = next_account_info(account_info_iter)?;
+
+        let escrow_ata = next_account_info(account_info_iter)?;
+
+        let vote_record_account = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(voter_account)?;
+        assert_writable(voter_account)?;
+        assert_writable(vote_record_account)?;
+        assert_owned_by(proposal_account, program_id)?;
+        assert_owned_by(vote_record_account, program_id)?;
+        assert_associated_token_account(voter_token_ata, voter_account.key, sk_mint.key)?;
+        assert_token_program(token_program_account)?;
+
+        let unpacked_proposal = Proposal::unpack(&proposal_account.try_borrow_data()?)?;
+
+        let proposal_bump_seeds = assert_pda(
+            proposal_account,
+            &[
+                sk_mint.key.as_ref(),
+                b"proposal",
+                &unpacked_proposal.index.to_le_bytes(),
+            ],
+            program_id,
+        )?;
+        assert_keys_equal(sk_mint.key, &unpacked_proposal.user_token_mint)?;
+        assert_associated_token_account(escrow_ata, proposal_account.key, sk_mint.key)?;
+
+        let unpacked_vote_record = VoteRecord::unpack(&vote_record_account.try_borrow_data()?)?;
+
+        assert_keys_equal(proposal_account.key, &unpacked_vote_record.proposal)?;
+        assert_keys_equal(voter_account.key, &unpacked_vote_record.voter)?;
+
+        if Clock::get()?.unix_timestamp < unpacked_proposal.voting_ends_at {
+            return Err(DecenseError::VotingInProgress.into());
+        }
+
+        Self::transfer_tokens(
+            escrow_ata,
+            sk_mint,
+            voter_token_ata,
+            proposal_account,
+            token_program_account,
+            unpacked_vote_record.amount,
+            &[&[
+                sk_mint.key.as_ref(),
+                b"proposal",
+                &unpacked_proposal.index.to_le_bytes(),
+                &[proposal_bump_seeds],
+            ]],
+        )?;
+
+        // close the vote record, the tally on the proposal keeps the result
+        **voter_account.try_borrow_mut_lamports()? = voter_account
+            .lamports()
+            .checked_add(vote_record_account.lamports())
+            .ok_or(DecenseError::MathError)?;
+        **vote_record_account.try_borrow_mut_lamports()? = 0;
+        vote_record_account.try_borrow_mut_data()?.fill(0);
+
+        Ok(())
+    }
+
+    fn process_release_vested(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let sk_account = next_account_info(account_info_iter)?;
+
+        let sk_ata = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let vesting_account = next_account_info(account_info_iter)?;
+
+        let vesting_ata = next_account_info(account_info_iter)?;
+
+        let sk_state = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(sk_account)?;
+        assert_writable(vesting_account)?;
+        assert_writable(sk_state)?;
+        assert_owned_by(vesting_account, program_id)?;
+        assert_owned_by(sk_state, program_id)?;
+        assert_associated_token_account(sk_ata, sk_account.key, sk_mint.key)?;
+        assert_token_program(token_program_account)?;
+        assert_pda(
+            sk_state,
+            &[sk_mint.key.as_ref(), sk_account.key.as_ref()],
+            program_id,
+        )?;
+
+        let vesting_bump_seeds = assert_pda(
+            vesting_account,
+            &[sk_mint.key.as_ref(), b"vesting"],
+            program_id,
+        )?;
+        assert_associated_token_account(vesting_ata, vesting_account.key, sk_mint.key)?;
+
+        let mut unpacked_vesting = Vesting::unpack(&vesting_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_account.key, &unpacked_vesting.beneficiary)?;
+        assert_keys_equal(sk_mint.key, &unpacked_vesting.user_token_mint)?;
+
+        let releasable = unpacked_vesting
+            .vested_amount(Clock::get()?.unix_timestamp)
+            .ok_or(DecenseError::MathError)?
+            .checked_sub(unpacked_vesting.released_amount)
+            .ok_or(DecenseError::MathError)?;
+
+        if releasable == 0 {
+            return Err(DecenseError::NothingToRelease.into());
+        }
+
+        Self::transfer_tokens(
+            vesting_ata,
+            sk_mint,
+            sk_ata,
+            vesting_account,
+            token_program_account,
+            releasable,
+            &[&[sk_mint.key.as_ref(), b"vesting", &[vesting_bump_seeds]]],
+        )?;
+
+        unpacked_vesting.released_amount = unpacked_vesting
+            .released_amount
+            .checked_add(releasable)
+            .ok_or(DecenseError::MathError)?;
+
+        Vesting::pack(
+            unpacked_vesting,
+            &mut vesting_account.try_borrow_mut_data()?,
+        )?;
+
+        // released tokens join the issuer's position at no cost
+        let mut unpacked_sk_state = BuyerState::unpack(&sk_state.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_account.key, &unpacked_sk_state.buyer)?;
+
+        unpacked_sk_state
+            .add_to_position(releasable, 0)
+            .ok_or(DecenseError::MathError)?;
+        BuyerState::pack(unpacked_sk_state, &mut sk_state.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    fn process_configure_circuit_breaker(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        max_move_bps: u64,
+        window: u64,
+        cooldown: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let admin_account = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        assert_writable(sk_state_account)?;
+        assert_platform_state(platform_state_account, program_id)?;
+        assert_owned_by(sk_state_account, program_id)?;
+
+        let mut unpacked_sk_state_account =
+            UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(
+            platform_state_account.key,
+            &unpacked_sk_state_account.platform_state,
+        )?;
+
+        let unpacked_platform_state_account =
+            PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
+
+        assert_platform_admin(admin_account, &unpacked_platform_state_account)?;
+
+        let max_move_bps =
+            u16::try_from(max_move_bps).map_err(|_| DecenseError::InvalidCircuitBreaker)?;
+
+        if max_move_bps > 0
+            && (window == 0 || window > i64::MAX as u64 || cooldown > i64::MAX as u64)
+        {
+            return Err(DecenseError::InvalidCircuitBreaker.into());
+        }
+
+        unpacked_sk_state_account.breaker_max_move_bps = max_move_bps;
+        unpacked_sk_state_account.breaker_window = window;
+        unpacked_sk_state_account.breaker_cooldown = cooldown;
+        unpacked_sk_state_account.breaker_reference_price = 0;
+
+        UserState::pack(
+            unpacked_sk_state_account,
+            &mut sk_state_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_halt_trading(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let authority_account = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        assert_writable(sk_state_account)?;
+        assert_owned_by(sk_state_account, program_id)?;
+
+        let mut unpacked_sk_state_account =
+            UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        let reason = Self::listing_authority(
+            program_id,
+            authority_account,
+            platform_state_account,
+            &unpacked_sk_state_account,
+        )?;
+        Self::assert_listing_active(&unpacked_sk_state_account)?;
+
+        // the issuer can not take over a halt made by the admin
+        if reason == HaltReason::Issuer
+            && unpacked_sk_state_account.halt_reason() == Some(HaltReason::Admin)
+        {
+            return Err(DecenseError::Unauthorized.into());
+        }
+
+        unpacked_sk_state_account.halt_reason = reason as u8;
+
+        UserState::pack(
+            unpacked_sk_state_account,
+            &mut sk_state_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_resume_trading(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let authority_account = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        assert_writable(sk_state_account)?;
+        assert_owned_by(sk_state_account, program_id)?;
+
+        let mut unpacked_sk_state_account =
+            UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        let authority = Self::listing_authority(
+            program_id,
+            authority_account,
+            platform_state_account,
+            &unpacked_sk_state_account,
+        )?;
+        Self::assert_listing_active(&unpacked_sk_state_account)?;
+
+        // the admin lifts any halt, the issuer only its own
+        let halt_reason = unpacked_sk_state_account.halt_reason();
+        if authority == HaltReason::Issuer
+            && halt_reason.is_some()
+            && halt_reason != Some(HaltReason::Issuer)
+        {
+            return Err(DecenseError::Unauthorized.into());
+        }
+
+        unpacked_sk_state_account.halt_reason = 0;
+        unpacked_sk_state_account.breaker_reference_price = 0;
+
+        UserState::pack(
+            unpacked_sk_state_account,
+            &mut sk_state_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_issue_new_shares(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let sk_account = next_account_info(account_info_iter)?;
+
+        let admin_account = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let pda_account = next_account_info(account_info_iter)?;
+
+        let pda_token_ata = next_account_info(account_info_iter)?;
+
+        let proposal_account = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(sk_account)?;
+        assert_writable(sk_state_account)?;
+        assert_writable(pda_token_ata)?;
+        assert_writable(proposal_account)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_owned_by(proposal_account, program_id)?;
+        assert_platform_state(platform_state_account, program_id)?;
+        assert_token_program(token_program_account)?;
+
+        let bump_seeds = assert_pda(pda_account, &[sk_mint.key.as_ref()], program_id)?;
+
+        let mut unpacked_sk_state_account =
+            UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_account.key, &unpacked_sk_state_account.user)?;
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+        assert_keys_equal(pda_token_ata.key, &unpacked_sk_state_account.pda_ata)?;
+        assert_keys_equal(
+            platform_state_account.key,
+            &unpacked_sk_state_account.platform_state,
+        )?;
+
+        let unpacked_platform_state_account =
+            PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
+
+        assert_not_paused(&unpacked_platform_state_account)?;
+        // holders may be outvoted by the issuer's own stake, so the platform co-approves
+        assert_platform_admin(admin_account, &unpacked_platform_state_account)?;
+        Self::assert_listing_active(&unpacked_sk_state_account)?;
+
+        let mut unpacked_proposal = Proposal::unpack(&proposal_account.try_borrow_data()?)?;
+
+        assert_pda(
+            proposal_account,
+            &[
+                sk_mint.key.as_ref(),
+                b"proposal",
+                &unpacked_proposal.index.to_le_bytes(),
+            ],
+            program_id,
+        )?;
+        assert_keys_equal(sk_mint.key, &unpacked_proposal.user_token_mint)?;
+
+        // holders approve an issue by passing a proposal that names its size, once
+        if unpacked_proposal.status != ProposalStatus::Passed
+            || unpacked_proposal.description_hash != Proposal::issue_new_shares_hash(amount)
+        {
+            return Err(DecenseError::IssueNotApproved.into());
+        }
+
+        // shares issued earlier count towards the limit of all issues together
+        let issued_supply = unpacked_sk_state_account
+            .issued_supply
+            .checked_add(amount)
+            .ok_or(DecenseError::MathError)?;
+        let original_supply = unpacked_sk_state_account
+            .supply
+            .saturating_sub(unpacked_sk_state_account.issued_supply);
+
+        if amount as u128 * 10000 > unpacked_sk_state_account.supply as u128 * MAX_ISSUE_BPS as u128
+            || issued_supply as u128 * 10000 > original_supply as u128 * MAX_TOTAL_ISSUE_BPS as u128
+        {
+            return Err(DecenseError::IssueLimitExceeded.into());
+        }
+
+        // listings created before the handover still have the issuer as mint authority
+        assert_mint_authority(sk_mint, pda_account.key)?;
+
+        // new shares go to the pool, so the issuer only raises what buyers pay for them
+        let issued_amount = amount.checked_mul(10000).ok_or(DecenseError::MathError)?;
+        let new_pool = spl_token::state::Account::unpack(&pda_token_ata.try_borrow_data()?)?
+            .amount
+            .checked_add(issued_amount)
+            .ok_or(DecenseError::MathError)?;
+
+        Self::mint_tokens(
+            sk_mint,
+            pda_token_ata,
+            pda_account,
+            token_program_account,
+            issued_amount,
+            &[&[sk_mint.key.as_ref(), &[bump_seeds]]],
+        )?;
+
+        // new shares are valued at the current price per share
+        let supply = unpacked_sk_state_account
+            .supply
+            .checked_add(amount)
+            .ok_or(DecenseError::MathError)?;
+        let market_valuation = (unpacked_sk_state_account.market_valuation as u128)
+            .checked_mul(supply as u128)
+            .and_then(|value| value.checked_div(unpacked_sk_state_account.supply as u128))
+            .and_then(|value| u64::try_from(value).ok())
+            .ok_or(DecenseError::MathError)?;
+
+        unpacked_sk_state_account.supply = supply;
+        unpacked_sk_state_account.market_valuation = market_valuation;
+        unpacked_sk_state_account.issued_supply = issued_supply;
+        // the larger pool quotes a lower price
+        unpacked_sk_state_account.cmp = unpacked_sk_state_account
+            .pricing_curve()
+            .ok_or(DecenseError::InvalidPricingCurve)?
+            .spot_price(new_pool)
+            .ok_or(DecenseError::MathError)?;
+
+        UserState::pack(
+            unpacked_sk_state_account,
+            &mut sk_state_account.try_borrow_mut_data()?,
+        )?;
+
+        unpacked_proposal.status = ProposalStatus::Executed;
+
+        Proposal::pack(
+            unpacked_proposal,
+            &mut proposal_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_split(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        numerator: u64,
+        denominator: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let sk_account = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let pda_account = next_account_info(account_info_iter)?;
+
+        let pda_token_ata = next_account_info(account_info_iter)?;
+
+        let split_account = next_account_info(account_info_iter)?;
+
+        let split_vault = next_account_info(account_info_iter)?;
+
+        let order_book_account = next_account_info(account_info_iter)?;
+
+        let vesting_account = next_account_info(account_info_iter)?;
+
+        let vesting_ata = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        let rent_sysvar_account = next_account_info(account_info_iter)?;
+
+        let associated_token_account_program_account = next_account_info(account_info_iter)?;
+
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(sk_account)?;
+        assert_writable(sk_state_account)?;
+        assert_writable(split_account)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_platform_state(platform_state_account, program_id)?;
+        assert_token_program(token_program_account)?;
+        assert_rent_sysvar(rent_sysvar_account)?;
+        assert_associated_token_program(associated_token_account_program_account)?;
+        assert_system_program(system_program_account)?;
+
+        let bump_seeds = assert_pda(pda_account, &[sk_mint.key.as_ref()], program_id)?;
+        assert_pda(
+            order_book_account,
+            &[sk_mint.key.as_ref(), b"order_book"],
+            program_id,
+        )?;
+        let vesting_bump_seeds = assert_pda(
+            vesting_account,
+            &[sk_mint.key.as_ref(), b"vesting"],
+            program_id,
+        )?;
+        assert_associated_token_account(vesting_ata, vesting_account.key, sk_mint.key)?;
+
+        let mut unpacked_sk_state_account =
+            UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_account.key, &unpacked_sk_state_account.user)?;
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+        assert_keys_equal(pda_token_ata.key, &unpacked_sk_state_account.pda_ata)?;
+        assert_keys_equal(
+            platform_state_account.key,
+            &unpacked_sk_state_account.platform_state,
+        )?;
+
+        let unpacked_platform_state_account =
+            PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
+
+        assert_not_paused(&unpacked_platform_state_account)?;
+        Self::assert_listing_active(&unpacked_sk_state_account)?;
+
+        let index = unpacked_sk_state_account.split_count;
+        let split_bump_seeds = assert_pda(
+            split_account,
+            &[sk_mint.key.as_ref(), b"split", &index.to_le_bytes()],
+            program_id,
+        )?;
+        assert_associated_token_account(split_vault, split_account.key, sk_mint.key)?;
+
+        assert_mint_authority(sk_mint, pda_account.key)?;
+
+        if numerator == 0 || denominator == 0 || numerator == denominator {
+            return Err(DecenseError::InvalidSplit.into());
+        }
+
+        // resting orders are priced for the old share count
+        if !order_book_account.data_is_empty() {
+            assert_owned_by(order_book_account, program_id)?;
+
+            let unpacked_order_book = OrderBook::unpack(&order_book_account.try_borrow_data()?)?;
+
+            if !unpacked_order_book.bids.is_empty() || !unpacked_order_book.asks.is_empty() {
+                return Err(DecenseError::OrderBookNotEmpty.into());
+            }
+        }
+
+        let is_vested = !vesting_account.data_is_empty();
+
+        let vesting_balance = if is_vested {
+            assert_owned_by(vesting_account, program_id)?;
+
+            spl_token::state::Account::unpack(&vesting_ata.try_borrow_data()?)?.amount
+        } else {
+            0
+        };
+
+        let pool = spl_token::state::Account::unpack(&pda_token_ata.try_borrow_data()?)?.amount;
+
+        let unpacked_sk_mint = spl_token::state::Mint::unpack(&sk_mint.try_borrow_data()?)?;
+
+        // the pool and the vesting account are converted here, positions claim the rest
+        let eligible_supply = unpacked_sk_mint
+            .supply
+            .checked_sub(pool)
+            .and_then(|supply| supply.checked_sub(vesting_balance))
+            .ok_or(DecenseError::MathError)?;
+
+        // create split account
+        let create_split_account_ix = system_instruction::create_account(
+            sk_account.key,
+            split_account.key,
+            Rent::default().minimum_balance(Split::LEN),
+            Split::LEN as u64,
+            program_id,
+        );
+
+        invoke_signed(
+            &create_split_account_ix,
+            &[
+                sk_account.clone(),
+                split_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[
+                sk_mint.key.as_ref(),
+                b"split",
+                &index.to_le_bytes(),
+                &[split_bump_seeds],
+            ]],
+        )?;
+
+        let unpacked_split = Split {
+            is_initialized: true,
+            user_token_mint: *sk_mint.key,
+            index,
+            numerator,
+            denominator,
+            eligible_supply,
+        };
+
+        Split::pack(unpacked_split, &mut split_account.try_borrow_mut_data()?)?;
+
+        let pda_seeds: &[&[u8]] = &[sk_mint.key.as_ref(), &[bump_seeds]];
+
+        if numerator > denominator {
+            // holders claim the new tokens of a forward split from the split's conversion vault
+            let create_split_vault_ix = create_associated_token_account(
+                sk_account.key,
+                split_account.key,
+                sk_mint.key,
+                &spl_token::id(),
+            );
+
+            invoke(
+                &create_split_vault_ix,
+                &[
+                    sk_account.clone(),
+                    split_vault.clone(),
+                    split_account.clone(),
+                    sk_mint.clone(),
+                    system_program_account.clone(),
+                    token_program_account.clone(),
+                    rent_sysvar_account.clone(),
+                    associated_token_account_program_account.clone(),
+                ],
+            )?;
+
+            // positions never hold more than the eligible supply and every claim rounds down
+            let vault_amount = unpacked_split
+                .convert(eligible_supply)
+                .ok_or(DecenseError::MathError)?
+                - eligible_supply;
+
+            if vault_amount > 0 {
+                Self::mint_tokens(
+                    sk_mint,
+                    split_vault,
+                    pda_account,
+                    token_program_account,
+                    vault_amount,
+                    &[pda_seeds],
+                )?;
+            }
+        }
+
+        let new_pool = unpacked_split
+            .convert(pool)
+            .ok_or(DecenseError::MathError)?;
+
+        if new_pool > pool {
+            Self::mint_tokens(
+                sk_mint,
+                pda_token_ata,
+                pda_account,
+                token_program_account,
+                new_pool - pool,
+                &[pda_seeds],
+            )?;
+        } else if new_pool < pool {
+            Self::burn_tokens(
+                pda_token_ata,
+                sk_mint,
+                pda_account,
+                token_program_account,
+                pool - new_pool,
+                &[pda_seeds],
+            )?;
+        }
+
+        if is_vested {
+            let mut unpacked_vesting = Vesting::unpack(&vesting_account.try_borrow_data()?)?;
+
+            let new_vesting_balance = unpacked_split
+                .convert(vesting_balance)
+                .ok_or(DecenseError::MathError)?;
+
+            if new_vesting_balance > vesting_balance {
+                Self::mint_tokens(
+                    sk_mint,
+                    vesting_ata,
+                    pda_account,
+                    token_program_account,
+                    new_vesting_balance - vesting_balance,
+                    &[pda_seeds],
+                )?;
+            } else if new_vesting_balance < vesting_balance {
+                Self::burn_tokens(
+                    vesting_ata,
+                    sk_mint,
+                    vesting_account,
+                    token_program_account,
+                    vesting_balance - new_vesting_balance,
+                    &[&[sk_mint.key.as_ref(), b"vesting", &[vesting_bump_seeds]]],
+                )?;
+            }
+
+            // what is left to release has to match the converted balance exactly
+            unpacked_vesting.released_amount = unpacked_split
+                .convert(unpacked_vesting.released_amount)
+                .ok_or(DecenseError::MathError)?;
+            unpacked_vesting.total_amount = unpacked_vesting
+                .released_amount
+                .checked_add(new_vesting_balance)
+                .ok_or(DecenseError::MathError)?;
+
+            Vesting::pack(
+                unpacked_vesting,
+                &mut vesting_account.try_borrow_mut_data()?,
+            )?;
+        }
+
+        let pricing_curve = unpacked_sk_state_account
+            .pricing_curve()
+            .ok_or(DecenseError::InvalidPricingCurve)?
+            .split(numerator, denominator)
+            .ok_or(DecenseError::InvalidSplit)?;
+
+        // the market valuation stays, the company is only cut into more or fewer shares
+        unpacked_sk_state_account.supply = unpacked_split
+            .convert(unpacked_sk_state_account.supply)
+            .ok_or(DecenseError::MathError)?;
+        unpacked_sk_state_account.cmp = unpacked_split
+            .convert_price(unpacked_sk_state_account.cmp)
+            .ok_or(DecenseError::MathError)?;
+        unpacked_sk_state_account.breaker_reference_price = unpacked_split
+            .convert_price(unpacked_sk_state_account.breaker_reference_price)
+            .ok_or(DecenseError::MathError)?;
+        unpacked_sk_state_account.curve_parameter = pricing_curve.parameter;
+        unpacked_sk_state_account.base_price = pricing_curve.base_price;
+        unpacked_sk_state_account.initial_pool = pricing_curve.initial_pool;
+        unpacked_sk_state_account.issued_supply = unpacked_split
+            .convert(unpacked_sk_state_account.issued_supply)
+            .ok_or(DecenseError::MathError)?;
+        unpacked_sk_state_account.split_count =
+            index.checked_add(1).ok_or(DecenseError::MathError)?;
+
+        UserState::pack(
+            unpacked_sk_state_account,
+            &mut sk_state_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_claim_split(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let holder_account = next_account_info(account_info_iter)?;
+
+        let holder_token_ata = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let split_account = next_account_info(account_info_iter)?;
+
+        let split_vault = next_account_info(account_info_iter)?;
+
+        let holder_state = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(holder_account)?;
+        assert_writable(holder_state)?;
+        assert_owned_by(split_account, program_id)?;
+        assert_owned_by(holder_state, program_id)?;
+        assert_associated_token_account(holder_token_ata, holder_account.key, sk_mint.key)?;
+        assert_token_program(token_program_account)?;
+
+        let unpacked_split = Split::unpack(&split_account.try_borrow_data()?)?;
+
+        let split_bump_seeds = assert_pda(
+            split_account,
+            &[
+                sk_mint.key.as_ref(),
+                b"split",
+                &unpacked_split.index.to_le_bytes(),
+            ],
+            program_id,
+        )?;
+        assert_associated_token_account(split_vault, split_account.key, sk_mint.key)?;
+        assert_pda(
+            holder_state,
+            &[sk_mint.key.as_ref(), holder_account.key.as_ref()],
+            program_id,
+        )?;
+
+        let mut unpacked_holder_state = BuyerState::unpack(&holder_state.try_borrow_data()?)?;
+
+        // positions opened after the split only hold converted tokens and are already past it
+        if unpacked_split.index < unpacked_holder_state.split_count {
+            return Err(DecenseError::SplitAlreadyClaimed.into());
+        }
+
+        // splits are converted in order, a later one would skip the burn of an earlier one
+        if unpacked_split.index > unpacked_holder_state.split_count {
+            return Err(DecenseError::SplitNotClaimed.into());
+        }
+
+        // the program only converts what it saw the position acquire, not the wallet's balance
+        let holding = unpacked_holder_state.current_holding_in_tokens;
+        let converted = unpacked_split
+            .convert(holding)
+            .ok_or(DecenseError::MathError)?;
+
+        if converted > holding {
+            Self::transfer_tokens(
+                split_vault,
+                sk_mint,
+                holder_token_ata,
+                split_account,
+                token_program_account,
+                converted - holding,
+                &[&[
+                    sk_mint.key.as_ref(),
+                    b"split",
+                    &unpacked_split.index.to_le_bytes(),
+                    &[split_bump_seeds],
+                ]],
+            )?;
+        } else if converted < holding {
+            // a reverse split burns the difference out of the holder's own account
+            Self::burn_tokens(
+                holder_token_ata,
+                sk_mint,
+                holder_account,
+                token_program_account,
+                holding - converted,
+                &[],
+            )?;
+        }
+
+        // the position keeps its cost basis, only the share count changes
+        unpacked_holder_state.current_holding_in_tokens = converted;
+        unpacked_holder_state.split_count = unpacked_split
+            .index
+            .checked_add(1)
+            .ok_or(DecenseError::MathError)?;
+
+        BuyerState::pack(
+            unpacked_holder_state,
+            &mut holder_state.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_buyback(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        budget: u64,
+        max_price: u64,
+        burn: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let sk_account = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let pda_token_ata = next_account_info(account_info_iter)?;
+
+        let buyback_account = next_account_info(account_info_iter)?;
+
+        let order_book_account = next_account_info(account_info_iter)?;
+
+        let ask_vault_authority = next_account_info(account_info_iter)?;
+
+        let ask_vault_ata = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(sk_account)?;
+        assert_writable(sk_state_account)?;
+        assert_writable(buyback_account)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_platform_state(platform_state_account, program_id)?;
+        assert_token_program(token_program_account)?;
+        assert_system_program(system_program_account)?;
+
+        let buyback_bump_seeds = assert_pda(
+            buyback_account,
+            &[sk_mint.key.as_ref(), b"buyback"],
+            program_id,
+        )?;
+        assert_pda(
+            order_book_account,
+            &[sk_mint.key.as_ref(), b"order_book"],
+            program_id,
+        )?;
+        let ask_vault_bump_seeds = assert_pda(
+            ask_vault_authority,
+            &[sk_mint.key.as_ref(), b"ask_vault"],
+            program_id,
+        )?;
+        assert_associated_token_account(ask_vault_ata, ask_vault_authority.key, sk_mint.key)?;
+
+        let mut unpacked_sk_state_account =
+            UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_account.key, &unpacked_sk_state_account.user)?;
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+        assert_keys_equal(pda_token_ata.key, &unpacked_sk_state_account.pda_ata)?;
+        assert_keys_equal(
+            platform_state_account.key,
+            &unpacked_sk_state_account.platform_state,
+        )?;
+
+        let unpacked_platform_state_account =
+            PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
+
+        assert_not_paused(&unpacked_platform_state_account)?;
+
+        // the budget is held in lamports, like the bids it buys against
+        if unpacked_sk_state_account.quote_mint != Pubkey::default() {
+            return Err(DecenseError::InvalidQuoteMint.into());
+        }
+
+        if max_price == 0 || burn > 1 {
+            return Err(DecenseError::InvalidBuyback.into());
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        Self::assert_trading(&mut unpacked_sk_state_account, now)?;
+
+        let mut unpacked_buyback = if buyback_account.data_is_empty() {
+            // create buyback account, it holds the budget itself
+            let create_buyback_account_ix = system_instruction::create_account(
+                sk_account.key,
+                buyback_account.key,
+                Rent::default()
+                    .minimum_balance(Buyback::LEN)
+                    .checked_add(budget)
+                    .ok_or(DecenseError::MathError)?,
+                Buyback::LEN as u64,
+                program_id,
+            );
+
+            invoke_signed(
+                &create_buyback_account_ix,
+                &[
+                    sk_account.clone(),
+                    buyback_account.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[sk_mint.key.as_ref(), b"buyback", &[buyback_bump_seeds]]],
+            )?;
+
+            Buyback {
+                is_initialized: true,
+                user_token_mint: *sk_mint.key,
+                max_price,
+                burn: burn == 1,
+                budget,
+                bought: 0,
+            }
+        } else {
+            assert_owned_by(buyback_account, program_id)?;
+
+            // a running buyback is topped up and takes the new terms
+            let mut unpacked_buyback = Buyback::unpack(&buyback_account.try_borrow_data()?)?;
+
+            if budget > 0 {
+                let transfer_sol =
+                    system_instruction::transfer(sk_account.key, buyback_account.key, budget);
+
+                invoke(
+                    &transfer_sol,
+                    &[
+                        sk_account.clone(),
+                        buyback_account.clone(),
+                        system_program_account.clone(),
+                    ],
+                )?;
+            }
+
+            unpacked_buyback.max_price = max_price;
+            unpacked_buyback.burn = burn == 1;
+            unpacked_buyback.budget = unpacked_buyback
+                .budget
+                .checked_add(budget)
+                .ok_or(DecenseError::MathError)?;
+
+            unpacked_buyback
+        };
+
+        if !order_book_account.data_is_empty() {
+            assert_owned_by(order_book_account, program_id)?;
+            assert_writable(order_book_account)?;
+
+            let mut unpacked_order_book =
+                OrderBook::unpack(&order_book_account.try_borrow_data()?)?;
+
+            // the caller passes the asker wallet and position of every ask it expects to fill
+            while unpacked_sk_state_account.halt_reason == 0 {
+                let ask = match unpacked_order_book.asks.first() {
+                    Some(ask) if ask.price <= unpacked_buyback.max_price => *ask,
+                    _ => break,
+                };
+
+                let affordable = (unpacked_buyback.budget as u128)
+                    .checked_mul(10000)
+                    .ok_or(DecenseError::MathError)?
+                    / ask.price as u128;
+
+                let fill = ask.quantity.min(affordable.min(u64::MAX as u128) as u64);
+
+                if fill == 0 {
+                    break;
+                }
+
+                let asker_account = match next_account_info(account_info_iter) {
+                    Ok(asker_account) => asker_account,
+                    Err(_) => break,
+                };
+
+                let asker_state = next_account_info(account_info_iter)?;
+
+                assert_keys_equal(asker_account.key, &ask.owner)?;
+                assert_owned_by(asker_state, program_id)?;
+                assert_pda(
+                    asker_state,
+                    &[sk_mint.key.as_ref(), asker_account.key.as_ref()],
+                    program_id,
+                )?;
+
+                let cost = Self::order_cost(ask.price, fill, false)?;
+
+                let ask_vault_seeds: &[&[u8]] =
+                    &[sk_mint.key.as_ref(), b"ask_vault", &[ask_vault_bump_seeds]];
+
+                if unpacked_buyback.burn {
+                    Self::burn_tokens(
+                        ask_vault_ata,
+                        sk_mint,
+                        ask_vault_authority,
+                        token_program_account,
+                        fill,
+                        &[ask_vault_seeds],
+                    )?;
+                } else {
+                    Self::transfer_tokens(
+                        ask_vault_ata,
+                        sk_mint,
+                        pda_token_ata,
+                        ask_vault_authority,
+                        token_program_account,
+                        fill,
+                        &[ask_vault_seeds],
+                    )?;
+                }
+
+                // the buyback account is owned by the program, so lamports move directly
+                **buyback_account.try_borrow_mut_lamports()? = buyback_account
+                    .lamports()
+                    .checked_sub(cost)
+                    .ok_or(DecenseError::InsufficientVaultBalance)?;
+                **asker_account.try_borrow_mut_lamports()? = asker_account
+                    .lamports()
+                    .checked_add(cost)
+                    .ok_or(DecenseError::MathError)?;
+
+                unpacked_buyback.budget -= cost;
+                unpacked_buyback.bought = unpacked_buyback
+                    .bought
+                    .checked_add(fill)
+                    .ok_or(DecenseError::MathError)?;
+
+                let released_basis = unpacked_order_book.asks[0]
+                    .fill(fill)
+                    .ok_or(DecenseError::MathError)?;
+                if unpacked_order_book.asks[0].quantity == 0 {
+                    unpacked_order_book.asks.remove(0);
+                }
+
+                // the ask took its tokens out of the position when it was placed
+                let mut unpacked_asker_state = BuyerState::unpack(&asker_state.try_borrow_data()?)?;
+
+                assert_keys_equal(asker_account.key, &unpacked_asker_state.buyer)?;
+
+                unpacked_asker_state
+                    .realize(cost, released_basis)
+                    .ok_or(DecenseError::MathError)?;
+                BuyerState::pack(
+                    unpacked_asker_state,
+                    &mut asker_state.try_borrow_mut_data()?,
+                )?;
+
+                Self::record_trade_price(&mut unpacked_sk_state_account, ask.price, now)?;
+            }
+
+            OrderBook::pack(
+                unpacked_order_book,
+                &mut order_book_account.try_borrow_mut_data()?,
+            )?;
+        }
+
+        if unpacked_buyback.burn {
+            Self::update_supply(&mut unpacked_sk_state_account, sk_mint)?;
+        }
+
+        UserState::pack(
+            unpacked_sk_state_account,
+            &mut sk_state_account.try_borrow_mut_data()?,
+        )?;
+
+        Buyback::pack(
+            unpacked_buyback,
+            &mut buyback_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_sell_to_buyback(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        quantity: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let seller_account = next_account_info(account_info_iter)?;
+
+        let seller_state = next_account_info(account_info_iter)?;
+
+        let seller_token_ata = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let pda_token_ata = next_account_info(account_info_iter)?;
+
+        let buyback_account = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(seller_account)?;
+        assert_writable(seller_state)?;
+        assert_writable(sk_state_account)?;
+        assert_writable(buyback_account)?;
+        assert_owned_by(seller_state, program_id)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_owned_by(buyback_account, program_id)?;
+        assert_platform_state(platform_state_account, program_id)?;
+        assert_associated_token_account(seller_token_ata, seller_account.key, sk_mint.key)?;
+        assert_token_program(token_program_account)?;
+
+        assert_pda(
+            seller_state,
+            &[sk_mint.key.as_ref(), seller_account.key.as_ref()],
+            program_id,
+        )?;
+        assert_pda(
+            buyback_account,
+            &[sk_mint.key.as_ref(), b"buyback"],
+            program_id,
+        )?;
+
+        let mut unpacked_sk_state_account =
+            UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+        assert_keys_equal(pda_token_ata.key, &unpacked_sk_state_account.pda_ata)?;
+        assert_keys_equal(
+            platform_state_account.key,
+            &unpacked_sk_state_account.platform_state,
+        )?;
+
+        let unpacked_platform_state_account =
+            PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
+
+        assert_not_paused(&unpacked_platform_state_account)?;
+
+        // the issuer would only pay itself
+        if *seller_account.key == unpacked_sk_state_account.user {
+            return Err(DecenseError::InvalidBuyback.into());
+        }
+
+        let mut unpacked_buyback = Buyback::unpack(&buyback_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_mint.key, &unpacked_buyback.user_token_mint)?;
+
+        Self::assert_split_claimed(
+            seller_state,
+            unpacked_sk_state_account.split_count,
+            program_id,
+        )?;
+
+        let mut unpacked_seller_state = BuyerState::unpack(&seller_state.try_borrow_data()?)?;
+
+        assert_keys_equal(seller_account.key, &unpacked_seller_state.buyer)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        Self::assert_trading(&mut unpacked_sk_state_account, now)?;
+
+        let unpacked_seller_token_ata =
+            spl_token::state::Account::unpack(&seller_token_ata.try_borrow_data()?)?;
+
+        if quantity == 0 || quantity > unpacked_seller_token_ata.amount {
+            return Err(DecenseError::InsufficientTokenBalance.into());
+        }
+
+        // holders sell at the buyback's limit price
+        let proceeds = Self::order_cost(unpacked_buyback.max_price, quantity, false)?;
+
+        if proceeds > unpacked_buyback.budget {
+            return Err(DecenseError::InsufficientVaultBalance.into());
+        }
+
+        unpacked_seller_state.mint = *sk_mint.key;
+        Self::remove_from_position(&mut unpacked_seller_state, quantity, proceeds)?;
+
+        if unpacked_buyback.burn {
+            Self::burn_tokens(
+                seller_token_ata,
+                sk_mint,
+                seller_account,
+                token_program_account,
+                quantity,
+                &[],
+            )?;
+        } else {
+            Self::transfer_tokens(
+                seller_token_ata,
+                sk_mint,
+                pda_token_ata,
+                seller_account,
+                token_program_account,
+                quantity,
+                &[],
+            )?;
+        }
+
+        **buyback_account.try_borrow_mut_lamports()? = buyback_account
+            .lamports()
+            .checked_sub(proceeds)
+            .ok_or(DecenseError::InsufficientVaultBalance)?;
+        **seller_account.try_borrow_mut_lamports()? = seller_account
+            .lamports()
+            .checked_add(proceeds)
+            .ok_or(DecenseError::MathError)?;
+
+        unpacked_buyback.budget -= proceeds;
+        unpacked_buyback.bought = unpacked_buyback
+            .bought
+            .checked_add(quantity)
+            .ok_or(DecenseError::MathError)?;
+
+        if unpacked_seller_token_ata.amount == quantity {
+            unpacked_sk_state_account.holders = unpacked_sk_state_account
+                .holders
+                .checked_sub(1)
+                .ok_or(DecenseError::MathError)?;
+        }
+
+        // the price per whole token the seller was actually paid, after rounding
+        let executed_price = (proceeds as u128)
+            .checked_mul(10000)
+            .ok_or(DecenseError::MathError)?
+            / quantity as u128;
+
+        Self::record_trade_price(
+            &mut unpacked_sk_state_account,
+            u64::try_from(executed_price).map_err(|_| DecenseError::MathError)?,
+            now,
+        )?;
+
+        if unpacked_buyback.burn {
+            Self::update_supply(&mut unpacked_sk_state_account, sk_mint)?;
+        }
+
+        UserState::pack(
+            unpacked_sk_state_account,
+            &mut sk_state_account.try_borrow_mut_data()?,
+        )?;
+
+        Buyback::pack(
+            unpacked_buyback,
+            &mut buyback_account.try_borrow_mut_data()?,
+        )?;
+
+        BuyerState::pack(
+            unpacked_seller_state,
+            &mut seller_state.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_close_buyback(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let sk_account = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let buyback_account = next_account_info(account_info_iter)?;
+
+        assert_signer(sk_account)?;
+        assert_writable(sk_account)?;
+        assert_writable(buyback_account)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_owned_by(buyback_account, program_id)?;
+
+        assert_pda(
+            buyback_account,
+            &[sk_mint.key.as_ref(), b"buyback"],
+            program_id,
+        )?;
+
+        let unpacked_sk_state_account = UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_account.key, &unpacked_sk_state_account.user)?;
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+
+        Buyback::unpack(&buyback_account.try_borrow_data()?)?;
+
+        // the unspent budget and the rent go back to the issuer
+        **sk_account.try_borrow_mut_lamports()? = sk_account
+            .lamports()
+            .checked_add(buyback_account.lamports())
+            .ok_or(DecenseError::MathError)?;
+        **buyback_account.try_borrow_mut_lamports()? = 0;
+        buyback_account.try_borrow_mut_data()?.fill(0);
+
+        Ok(())
+    }
+
+    fn process_delist(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        redemption_window: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let authority_account = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let settlement_account = next_account_info(account_info_iter)?;
+
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        assert_writable(sk_state_account)?;
+        assert_writable(settlement_account)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_system_program(system_program_account)?;
+
+        let settlement_bump_seeds = assert_pda(
+            settlement_account,
+            &[sk_mint.key.as_ref(), b"settlement"],
+            program_id,
+        )?;
+
+        let mut unpacked_sk_state_account =
+            UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+
+        Self::listing_authority(
+            program_id,
+            authority_account,
+            platform_state_account,
+            &unpacked_sk_state_account,
+        )?;
+        Self::assert_listing_active(&unpacked_sk_state_account)?;
+
+        // settlements pay out lamports, the quote vault of these listings would be stranded
+        if unpacked_sk_state_account.quote_mint != Pubkey::default() {
+            return Err(DecenseError::InvalidQuoteMint.into());
+        }
+
+        if redemption_window == 0 || redemption_window > i64::MAX as u64 {
+            return Err(DecenseError::InvalidSettlement.into());
+        }
+
+        // create settlement account, the issuer funds it later
+        let create_settlement_account_ix = system_instruction::create_account(
+            authority_account.key,
+            settlement_account.key,
+            Rent::default().minimum_balance(Settlement::LEN),
+            Settlement::LEN as u64,
+            program_id,
+        );
+
+        invoke_signed(
+            &create_settlement_account_ix,
+            &[
+                authority_account.clone(),
+                settlement_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[
+                sk_mint.key.as_ref(),
+                b"settlement",
+                &[settlement_bump_seeds],
+            ]],
+        )?;
+
+        Settlement::pack(
+            Settlement {
+                is_initialized: true,
+                user_token_mint: *sk_mint.key,
+                redemption_window,
+                redeem_ends_at: 0,
+                total_amount: 0,
+                eligible_supply: 0,
+                redeemed_supply: 0,
+                paid_amount: 0,
+                split_count: unpacked_sk_state_account.split_count,
+            },
+            &mut settlement_account.try_borrow_mut_data()?,
+        )?;
+
+        unpacked_sk_state_account.halt_reason = HaltReason::Delisted as u8;
+
+        UserState::pack(
+            unpacked_sk_state_account,
+            &mut sk_state_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_deposit_settlement(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let sk_account = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let pda_token_ata = next_account_info(account_info_iter)?;
+
+        let vault_account = next_account_info(account_info_iter)?;
+
+        let settlement_account = next_account_info(account_info_iter)?;
+
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(sk_account)?;
+        assert_writable(vault_account)?;
+        assert_writable(settlement_account)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_owned_by(settlement_account, program_id)?;
+        assert_system_program(system_program_account)?;
+
+        assert_pda(
+            settlement_account,
+            &[sk_mint.key.as_ref(), b"settlement"],
+            program_id,
+        )?;
+        let vault_bump_seeds =
+            assert_pda(vault_account, &[sk_mint.key.as_ref(), b"vault"], program_id)?;
+
+        let unpacked_sk_state_account = UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_account.key, &unpacked_sk_state_account.user)?;
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+        assert_keys_equal(pda_token_ata.key, &unpacked_sk_state_account.pda_ata)?;
+
+        let mut unpacked_settlement = Settlement::unpack(&settlement_account.try_borrow_data()?)?;
+
+        if unpacked_settlement.redeem_ends_at != 0 {
+            return Err(DecenseError::SettlementAlreadyFunded.into());
+        }
+
+        // the pool is retired when the listing closes, every other token can still reach a holder's
+        // position, vested releases, cancelled asks, offering claims and split conversions included
+        let unpacked_sk_mint = spl_token::state::Mint::unpack(&sk_mint.try_borrow_data()?)?;
+
+        let eligible_supply = unpacked_sk_mint
+            .supply
+            .checked_sub(Self::token_balance(pda_token_ata)?)
+            .ok_or(DecenseError::MathError)?;
+
+        if eligible_supply == 0 {
+            return Err(DecenseError::InvalidSettlement.into());
+        }
+
+        // the liquidity left for sells back to the pool belongs to the holders as well
+        let liquidity = vault_account.lamports();
+
+        let total_amount = amount
+            .checked_add(liquidity)
+            .ok_or(DecenseError::MathError)?;
+
+        if total_amount == 0 {
+            return Err(DecenseError::InvalidSettlement.into());
+        }
+
+        if amount > 0 {
+            let transfer_sol =
+                system_instruction::transfer(sk_account.key, settlement_account.key, amount);
+
+            invoke(
+                &transfer_sol,
+                &[
+                    sk_account.clone(),
+                    settlement_account.clone(),
+                    system_program_account.clone(),
+                ],
+            )?;
+        }
+
+        if liquidity > 0 {
+            let transfer_liquidity =
+                system_instruction::transfer(vault_account.key, settlement_account.key, liquidity);
+
+            invoke_signed(
+                &transfer_liquidity,
+                &[
+                    vault_account.clone(),
+                    settlement_account.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[sk_mint.key.as_ref(), b"vault", &[vault_bump_seeds]]],
+            )?;
+        }
+
+        unpacked_settlement.total_amount = total_amount;
+        unpacked_settlement.eligible_supply = eligible_supply;
+        unpacked_settlement.redeem_ends_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(unpacked_settlement.redemption_window as i64)
+            .ok_or(DecenseError::MathError)?;
+
+        Settlement::pack(
+            unpacked_settlement,
+            &mut settlement_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_redeem_settlement(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        quantity: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let holder_account = next_account_info(account_info_iter)?;
+
+        let holder_token_ata = next_account_info(account_info_iter)?;
+
+        let holder_state = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let settlement_account = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(holder_account)?;
+        assert_writable(holder_account)?;
+        assert_writable(settlement_account)?;
+        assert_owned_by(settlement_account, program_id)?;
+        assert_associated_token_account(holder_token_ata, holder_account.key, sk_mint.key)?;
+        assert_token_program(token_program_account)?;
+
+        assert_pda(
+            settlement_account,
+            &[sk_mint.key.as_ref(), b"settlement"],
+            program_id,
+        )?;
+        assert_pda(
+            holder_state,
+            &[sk_mint.key.as_ref(), holder_account.key.as_ref()],
+            program_id,
+        )?;
+
+        let mut unpacked_settlement = Settlement::unpack(&settlement_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_mint.key, &unpacked_settlement.user_token_mint)?;
+
+        let now = Clock::get()?.unix_timestamp;
+
+        if unpacked_settlement.redeem_ends_at == 0 || now >= unpacked_settlement.redeem_ends_at {
+            return Err(DecenseError::RedemptionNotOpen.into());
+        }
+
+        Self::assert_split_claimed(holder_state, unpacked_settlement.split_count, program_id)?;
+
+        let unpacked_holder_token_ata =
+            spl_token::state::Account::unpack(&holder_token_ata.try_borrow_data()?)?;
+
+        if quantity == 0 || quantity > unpacked_holder_token_ata.amount {
+            return Err(DecenseError::InsufficientTokenBalance.into());
+        }
+
+        unpacked_settlement.redeemed_supply = unpacked_settlement
+            .redeemed_supply
+            .checked_add(quantity)
+            .filter(|redeemed| *redeemed <= unpacked_settlement.eligible_supply)
+            .ok_or(DecenseError::InvalidSettlement)?;
+
+        let payout = unpacked_settlement
+            .payout(quantity)
+            .ok_or(DecenseError::MathError)?;
+
+        Self::burn_tokens(
+            holder_token_ata,
+            sk_mint,
+            holder_account,
+            token_program_account,
+            quantity,
+            &[],
+        )?;
+
+        // the settlement account is owned by the program, so lamports move directly
+        **settlement_account.try_borrow_mut_lamports()? = settlement_account
+            .lamports()
+            .checked_sub(payout)
+            .ok_or(DecenseError::InsufficientVaultBalance)?;
+        **holder_account.try_borrow_mut_lamports()? = holder_account
+            .lamports()
+            .checked_add(payout)
+            .ok_or(DecenseError::MathError)?;
+
+        unpacked_settlement.paid_amount = unpacked_settlement
+            .paid_amount
+            .checked_add(payout)
+            .ok_or(DecenseError::MathError)?;
+
+        Settlement::pack(
+            unpacked_settlement,
+            &mut settlement_account.try_borrow_mut_data()?,
+        )?;
+
+        // only tokens the holder's position accounts for are redeemed
+        if holder_state.data_is_empty() {
+            return Err(DecenseError::PositionExceeded.into());
+        }
+
+        assert_owned_by(holder_state, program_id)?;
+        assert_writable(holder_state)?;
+
+        let mut unpacked_holder_state = BuyerState::unpack(&holder_state.try_borrow_data()?)?;
+
+        unpacked_holder_state.mint = *sk_mint.key;
+        Self::remove_from_position(&mut unpacked_holder_state, quantity, payout)?;
+        BuyerState::pack(
+            unpacked_holder_state,
+            &mut holder_state.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_close_listing(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let authority_account = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let pda_account = next_account_info(account_info_iter)?;
+
+        let pda_token_ata = next_account_info(account_info_iter)?;
+
+        let settlement_account = next_account_info(account_info_iter)?;
+
+        let sk_account = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        assert_writable(sk_state_account)?;
+        assert_writable(settlement_account)?;
+        assert_writable(sk_account)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_owned_by(settlement_account, program_id)?;
+        assert_token_program(token_program_account)?;
+
+        let bump_seeds = assert_pda(pda_account, &[sk_mint.key.as_ref()], program_id)?;
+        assert_pda(
+            settlement_account,
+            &[sk_mint.key.as_ref(), b"settlement"],
+            program_id,
+        )?;
+
+        let unpacked_sk_state_account = UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_account.key, &unpacked_sk_state_account.user)?;
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+        assert_keys_equal(pda_token_ata.key, &unpacked_sk_state_account.pda_ata)?;
+
+        Self::listing_authority(
+            program_id,
+            authority_account,
+            platform_state_account,
+            &unpacked_sk_state_account,
+        )?;
+
+        if unpacked_sk_state_account.halt_reason() != Some(HaltReason::Delisted) {
+            return Err(DecenseError::ListingNotDelisted.into());
+        }
+
+        let unpacked_settlement = Settlement::unpack(&settlement_account.try_borrow_data()?)?;
+
+        if unpacked_settlement.redeem_ends_at == 0 {
+            return Err(DecenseError::RedemptionNotOpen.into());
+        }
+
+        if Clock::get()?.unix_timestamp < unpacked_settlement.redeem_ends_at {
+            return Err(DecenseError::RedemptionInProgress.into());
+        }
+
+        let pda_seeds: &[&[u8]] = &[sk_mint.key.as_ref(), &[bump_seeds]];
+
+        let pool = spl_token::state::Account::unpack(&pda_token_ata.try_borrow_data()?)?.amount;
+
+        if pool > 0 {
+            Self::burn_tokens(
+                pda_token_ata,
+                sk_mint,
+                pda_account,
+                token_program_account,
+                pool,
+                &[pda_seeds],
+            )?;
+        }
+
+        let close_pda_ata_ix = spl_token::instruction::close_account(
+            &spl_token::id(),
+            pda_token_ata.key,
+            sk_account.key,
+            pda_account.key,
+            &[],
+        )?;
+
+        invoke_signed(
+            &close_pda_ata_ix,
+            &[
+                pda_token_ata.clone(),
+                sk_account.clone(),
+                pda_account.clone(),
+                token_program_account.clone(),
+            ],
+            &[pda_seeds],
+        )?;
+
+        // unredeemed settlement lamports and the rent of both state accounts go back to the issuer
+        for closed_account in [settlement_account, sk_state_account] {
+            **sk_account.try_borrow_mut_lamports()? = sk_account
+                .lamports()
+                .checked_add(closed_account.lamports())
+                .ok_or(DecenseError::MathError)?;
+            **closed_account.try_borrow_mut_lamports()? = 0;
+            closed_account.try_borrow_mut_data()?.fill(0);
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn process_create_offering(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        price: u64,
+        start_time: u64,
+        end_time: u64,
+        per_wallet_max: u64,
+        soft_cap: u64,
+        hard_cap: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let sk_account = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let pda_token_ata = next_account_info(account_info_iter)?;
+
+        let offering_account = next_account_info(account_info_iter)?;
+
+        let offering_vault = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        let rent_sysvar_account = next_account_info(account_info_iter)?;
+
+        let associated_token_account_program_account = next_account_info(account_info_iter)?;
+
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(sk_account)?;
+        assert_writable(sk_state_account)?;
+        assert_writable(offering_account)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_platform_state(platform_state_account, program_id)?;
+        assert_token_program(token_program_account)?;
+        assert_rent_sysvar(rent_sysvar_account)?;
+        assert_associated_token_program(associated_token_account_program_account)?;
+        assert_system_program(system_program_account)?;
+
+        let offering_bump_seeds = assert_pda(
+            offering_account,
+            &[sk_mint.key.as_ref(), b"offering"],
+            program_id,
+        )?;
+        assert_associated_token_account(offering_vault, offering_account.key, sk_mint.key)?;
+
+        let mut unpacked_sk_state_account =
+            UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_account.key, &unpacked_sk_state_account.user)?;
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+        assert_keys_equal(pda_token_ata.key, &unpacked_sk_state_account.pda_ata)?;
+        assert_keys_equal(
+            platform_state_account.key,
+            &unpacked_sk_state_account.platform_state,
+        )?;
+
+        let unpacked_platform_state_account =
+            PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
+
+        assert_not_paused(&unpacked_platform_state_account)?;
+
+        // subscriptions are paid in lamports
+        if unpacked_sk_state_account.quote_mint != Pubkey::default() {
+            return Err(DecenseError::InvalidQuoteMint.into());
+        }
+
+        let pool = spl_token::state::Account::unpack(&pda_token_ata.try_borrow_data()?)?.amount;
+
+        // one offering per listing, before anything has traded
+        if !offering_account.data_is_empty()
+            || unpacked_sk_state_account.halt_reason != 0
+            || pool != unpacked_sk_state_account.initial_pool
+        {
+            return Err(DecenseError::InvalidOffering.into());
+        }
+
+        let (start_time, end_time) = match (i64::try_from(start_time), i64::try_from(end_time)) {
+            (Ok(start_time), Ok(end_time)) => (start_time, end_time),
+            _ => return Err(DecenseError::InvalidOffering.into()),
+        };
+
+        if price == 0
+            || start_time >= end_time
+            || per_wallet_max == 0
+            || hard_cap == 0
+            || soft_cap > hard_cap
+        {
+            return Err(DecenseError::InvalidOffering.into());
+        }
+
+        // the pool has to cover the tokens sold at the hard cap
+        let tokens_at_hard_cap = (hard_cap as u128)
+            .checked_mul(10000)
+            .ok_or(DecenseError::MathError)?
+            / price as u128;
+
+        if tokens_at_hard_cap > pool as u128 || end_time <= Clock::get()?.unix_timestamp {
+            return Err(DecenseError::InvalidOffering.into());
+        }
+
+        // create offering account, it holds the raised lamports until the offering is finalized
+        let create_offering_account_ix = system_instruction::create_account(
+            sk_account.key,
+            offering_account.key,
+            Rent::default().minimum_balance(Offering::LEN),
+            Offering::LEN as u64,
+            program_id,
+        );
+
+        invoke_signed(
+            &create_offering_account_ix,
+            &[
+                sk_account.clone(),
+                offering_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[sk_mint.key.as_ref(), b"offering", &[offering_bump_seeds]]],
+        )?;
+
+        Offering::pack(
+            Offering {
+                is_initialized: true,
+                user_token_mint: *sk_mint.key,
+                price,
+                start_time,
+                end_time,
+                per_wallet_max,
+                soft_cap,
+                hard_cap,
+                raised: 0,
+                sold: 0,
+                status: OfferingStatus::Open,
+            },
+            &mut offering_account.try_borrow_mut_data()?,
+        )?;
+
+        // sold tokens wait here for their subscribers once the offering succeeds
+        let create_offering_vault_ix = create_associated_token_account(
+            sk_account.key,
+            offering_account.key,
+            sk_mint.key,
+            &spl_token::id(),
+        );
+
+        invoke(
+            &create_offering_vault_ix,
+            &[
+                sk_account.clone(),
+                offering_vault.clone(),
+                offering_account.clone(),
+                sk_mint.clone(),
+                system_program_account.clone(),
+                token_program_account.clone(),
+                rent_sysvar_account.clone(),
+                associated_token_account_program_account.clone(),
+            ],
+        )?;
+
+        unpacked_sk_state_account.halt_reason = HaltReason::Offering as u8;
+
+        UserState::pack(
+            unpacked_sk_state_account,
+            &mut sk_state_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_subscribe(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        quantity: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let subscriber_account = next_account_info(account_info_iter)?;
+
+        let subscription_account = next_account_info(account_info_iter)?;
+
+        let offering_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let platform_state_account = next_account_info(account_info_iter)?;
+
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(subscriber_account)?;
+        assert_writable(subscription_account)?;
+        assert_writable(offering_account)?;
+        assert_owned_by(offering_account, program_id)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_platform_state(platform_state_account, program_id)?;
+        assert_system_program(system_program_account)?;
+
+        assert_pda(
+            offering_account,
+            &[sk_mint.key.as_ref(), b"offering"],
+            program_id,
+        )?;
+        let subscription_bump_seeds = assert_pda(
+            subscription_account,
+            &[
+                offering_account.key.as_ref(),
+                subscriber_account.key.as_ref(),
+            ],
+            program_id,
+        )?;
+
+        let mut unpacked_offering = Offering::unpack(&offering_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_mint.key, &unpacked_offering.user_token_mint)?;
+
+        let unpacked_sk_state_account = UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+        assert_keys_equal(
+            platform_state_account.key,
+            &unpacked_sk_state_account.platform_state,
+        )?;
+
+        let unpacked_platform_state_account =
+            PlatformState::unpack(&platform_state_account.try_borrow_data()?)?;
+
+        assert_not_paused(&unpacked_platform_state_account)?;
+
+        let now = Clock::get()?.unix_timestamp;
+
+        if unpacked_offering.status != OfferingStatus::Open
+            || now < unpacked_offering.start_time
+            || now >= unpacked_offering.end_time
+        {
+            return Err(DecenseError::OfferingNotOpen.into());
+        }
+
+        if quantity == 0 {
+            return Err(DecenseError::InvalidOffering.into());
+        }
+
+        let cost = Self::order_cost(unpacked_offering.price, quantity, true)?;
+
+        unpacked_offering.raised = unpacked_offering
+            .raised
+            .checked_add(cost)
+            .filter(|raised| *raised <= unpacked_offering.hard_cap)
+            .ok_or(DecenseError::HardCapExceeded)?;
+        unpacked_offering.sold = unpacked_offering
+            .sold
+            .checked_add(quantity)
+            .ok_or(DecenseError::MathError)?;
+
+        let mut unpacked_subscription = if subscription_account.data_is_empty() {
+            Subscription {
+                is_initialized: true,
+                offering: *offering_account.key,
+                subscriber: *subscriber_account.key,
+                quantity: 0,
+                paid: 0,
+            }
+        } else {
+            assert_owned_by(subscription_account, program_id)?;
+
+            Subscription::unpack(&subscription_account.try_borrow_data()?)?
+        };
+
+        unpacked_subscription.quantity = unpacked_subscription
+            .quantity
+            .checked_add(quantity)
+            .ok_or(DecenseError::MathError)?;
+        unpacked_subscription.paid = unpacked_subscription
+            .paid
+            .checked_add(cost)
+            .filter(|paid| *paid <= unpacked_offering.per_wallet_max)
+            .ok_or(DecenseError::WalletLimitExceeded)?;
+
+        if subscription_account.data_is_empty() {
+            // create subscription account
+            let create_subscription_account_ix = system_instruction::create_account(
+                subscriber_account.key,
+                subscription_account.key,
+                Rent::default().minimum_balance(Subscription::LEN),
+                Subscription::LEN as u64,
+                program_id,
+            );
+
+            invoke_signed(
+                &create_subscription_account_ix,
+                &[
+                    subscriber_account.clone(),
+                    subscription_account.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[
+                    offering_account.key.as_ref(),
+                    subscriber_account.key.as_ref(),
+                    &[subscription_bump_seeds],
+                ]],
+            )?;
+        }
+
+        let transfer_sol =
+            system_instruction::transfer(subscriber_account.key, offering_account.key, cost);
+
+        invoke(
+            &transfer_sol,
+            &[
+                subscriber_account.clone(),
+                offering_account.clone(),
+                system_program_account.clone(),
+            ],
+        )?;
+
+        Subscription::pack(
+            unpacked_subscription,
+            &mut subscription_account.try_borrow_mut_data()?,
+        )?;
+
+        Offering::pack(
+            unpacked_offering,
+            &mut offering_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_finalize_offering(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let pda_account = next_account_info(account_info_iter)?;
+
+        let pda_token_ata = next_account_info(account_info_iter)?;
+
+        let offering_account = next_account_info(account_info_iter)?;
+
+        let offering_vault = next_account_info(account_info_iter)?;
+
+        let sk_account = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        assert_writable(sk_state_account)?;
+        assert_writable(offering_account)?;
+        assert_writable(sk_account)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_owned_by(offering_account, program_id)?;
+        assert_token_program(token_program_account)?;
+
+        let bump_seeds = assert_pda(pda_account, &[sk_mint.key.as_ref()], program_id)?;
+        assert_pda(
+            offering_account,
+            &[sk_mint.key.as_ref(), b"offering"],
+            program_id,
+        )?;
+        assert_associated_token_account(offering_vault, offering_account.key, sk_mint.key)?;
+
+        let mut unpacked_sk_state_account =
+            UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_account.key, &unpacked_sk_state_account.user)?;
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+        assert_keys_equal(pda_token_ata.key, &unpacked_sk_state_account.pda_ata)?;
+
+        let mut unpacked_offering = Offering::unpack(&offering_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_mint.key, &unpacked_offering.user_token_mint)?;
+
+        if unpacked_offering.status != OfferingStatus::Open {
+            return Err(DecenseError::OfferingNotOpen.into());
+        }
+
+        // a sold out offering can finish early
+        if Clock::get()?.unix_timestamp < unpacked_offering.end_time
+            && unpacked_offering.raised < unpacked_offering.hard_cap
+        {
+            return Err(DecenseError::OfferingInProgress.into());
+        }
+
+        if unpacked_offering.raised >= unpacked_offering.soft_cap {
+            let pool = spl_token::state::Account::unpack(&pda_token_ata.try_borrow_data()?)?.amount;
+
+            let new_pool = pool
+                .checked_sub(unpacked_offering.sold)
+                .ok_or(DecenseError::MathError)?;
+
+            if unpacked_offering.sold > 0 {
+                Self::transfer_tokens(
+                    pda_token_ata,
+                    sk_mint,
+                    offering_vault,
+                    pda_account,
+                    token_program_account,
+                    unpacked_offering.sold,
+                    &[&[sk_mint.key.as_ref(), &[bump_seeds]]],
+                )?;
+            }
+
+            // the offering account is owned by the program, so lamports move directly
+            **offering_account.try_borrow_mut_lamports()? = offering_account
+                .lamports()
+                .checked_sub(unpacked_offering.raised)
+                .ok_or(DecenseError::InsufficientVaultBalance)?;
+            **sk_account.try_borrow_mut_lamports()? = sk_account
+                .lamports()
+                .checked_add(unpacked_offering.raised)
+                .ok_or(DecenseError::MathError)?;
+
+            // secondary trading opens at the pool's price after the offering
+            unpacked_sk_state_account.cmp = unpacked_sk_state_account
+                .pricing_curve()
+                .ok_or(DecenseError::InvalidPricingCurve)?
+                .spot_price(new_pool)
+                .ok_or(DecenseError::MathError)?;
+            unpacked_sk_state_account.halt_reason = 0;
+            unpacked_sk_state_account.breaker_reference_price = 0;
+
+            unpacked_offering.status = OfferingStatus::Succeeded;
+        } else {
+            // nothing was sold, the pool trades on where it stood before the offering
+            unpacked_sk_state_account.halt_reason = 0;
+
+            unpacked_offering.status = OfferingStatus::Failed;
+        }
+
+        UserState::pack(
+            unpacked_sk_state_account,
+            &mut sk_state_account.try_borrow_mut_data()?,
+        )?;
+
+        Offering::pack(
+            unpacked_offering,
+            &mut offering_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_claim_offering(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let subscriber_account = next_account_info(account_info_iter)?;
+
+        let subscriber_state = next_account_info(account_info_iter)?;
+
+        let subscriber_token_ata = next_account_info(account_info_iter)?;
+
+        let sk_state_account = next_account_info(account_info_iter)?;
+
+        let sk_mint = next_account_info(account_info_iter)?;
+
+        let subscription_account = next_account_info(account_info_iter)?;
+
+        let offering_account = next_account_info(account_info_iter)?;
+
+        let offering_vault = next_account_info(account_info_iter)?;
+
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        let rent_sysvar_account = next_account_info(account_info_iter)?;
+
+        let associated_token_account_program_account = next_account_info(account_info_iter)?;
+
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        assert_signer(subscriber_account)?;
+        assert_writable(subscriber_account)?;
+        assert_writable(subscription_account)?;
+        assert_writable(offering_account)?;
+        assert_owned_by(sk_state_account, program_id)?;
+        assert_owned_by(subscription_account, program_id)?;
+        assert_owned_by(offering_account, program_id)?;
+        assert_associated_token_account(subscriber_token_ata, subscriber_account.key, sk_mint.key)?;
+        assert_token_program(token_program_account)?;
+        assert_rent_sysvar(rent_sysvar_account)?;
+        assert_associated_token_program(associated_token_account_program_account)?;
+        assert_system_program(system_program_account)?;
+
+        let offering_bump_seeds = assert_pda(
+            offering_account,
+            &[sk_mint.key.as_ref(), b"offering"],
+            program_id,
+        )?;
+        assert_pda(
+            subscription_account,
+            &[
+                offering_account.key.as_ref(),
+                subscriber_account.key.as_ref(),
+            ],
+            program_id,
+        )?;
+        assert_associated_token_account(offering_vault, offering_account.key, sk_mint.key)?;
+        let subscriber_state_bump_seeds = assert_pda(
+            subscriber_state,
+            &[sk_mint.key.as_ref(), subscriber_account.key.as_ref()],
+            program_id,
+        )?;
+
+        let mut unpacked_sk_state_account =
+            UserState::unpack(&sk_state_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_mint.key, &unpacked_sk_state_account.user_token_mint)?;
+
+        let unpacked_offering = Offering::unpack(&offering_account.try_borrow_data()?)?;
+
+        assert_keys_equal(sk_mint.key, &unpacked_offering.user_token_mint)?;
+
+        let unpacked_subscription = Subscription::unpack(&subscription_account.try_borrow_data()?)?;
+
+        assert_keys_equal(offering_account.key, &unpacked_subscription.offering)?;
+        assert_keys_equal(subscriber_account.key, &unpacked_subscription.subscriber)?;
+
+        match unpacked_offering.status {
+            OfferingStatus::Open => return Err(DecenseError::OfferingNotOpen.into()),
+
+            OfferingStatus::Succeeded => {
+                assert_writable(sk_state_account)?;
+                assert_writable(subscriber_state)?;
+
+                let create_subscriber_ata_ix = create_associated_token_account_idempotent(
+                    subscriber_account.key,
+                    subscriber_account.key,
+                    sk_mint.key,
+                    &spl_token::id(),
+                );
+
+                invoke(
+                    &create_subscriber_ata_ix,
+                    &[
+                        subscriber_account.clone(),
+                        subscriber_token_ata.clone(),
+                        subscriber_account.clone(),
+                        sk_mint.clone(),
+                        system_program_account.clone(),
+                        token_program_account.clone(),
+                        rent_sysvar_account.clone(),
+                        associated_token_account_program_account.clone(),
+                    ],
+                )?;
+
+                let unpacked_subscriber_token_ata =
+                    spl_token::state::Account::unpack(&subscriber_token_ata.try_borrow_data()?)?;
+
+                Self::transfer_tokens(
+                    offering_vault,
+                    sk_mint,
+                    subscriber_token_ata,
+                    offering_account,
+                    token_program_account,
+                    unpacked_subscription.quantity,
+                    &[&[sk_mint.key.as_ref(), b"offering", &[offering_bump_seeds]]],
+                )?;
+
+                if *subscriber_account.key != unpacked_sk_state_account.user
+                    && unpacked_subscriber_token_ata.amount == 0
+                {
+                    unpacked_sk_state_account.holders = unpacked_sk_state_account
+                        .holders
+                        .checked_add(1)
+                        .ok_or(DecenseError::MathError)?;
+
+                    UserState::pack(
+                        unpacked_sk_state_account,
+                        &mut sk_state_account.try_borrow_mut_data()?,
+                    )?;
+                }
+
+                if subscriber_state.data_is_empty() {
+                    // create buyer state account
+                    let create_subscriber_state_account_ix = system_instruction::create_account(
+                        subscriber_account.key,
+                        subscriber_state.key,
+                        Rent::default().minimum_balance(BuyerState::LEN),
+                        BuyerState::LEN as u64,
+                        program_id,
+                    );
+
+                    invoke_signed(
+                        &create_subscriber_state_account_ix,
+                        &[
+                            subscriber_account.clone(),
+                            subscriber_state.clone(),
+                            system_program_account.clone(),
+                        ],
+                        &[&[
+                            sk_mint.key.as_ref(),
+                            subscriber_account.key.as_ref(),
+                            &[subscriber_state_bump_seeds],
+                        ]],
+                    )?;
+
+                    let mut unpacked_subscriber_state =
+                        BuyerState::unpack_unchecked(&subscriber_state.try_borrow_data()?)?;
+
+                    unpacked_subscriber_state.is_initialized = true;
+                    unpacked_subscriber_state.buyer = *subscriber_account.key;
+                    // a new position holds nothing from before the listing's splits
+                    unpacked_subscriber_state.split_count =
+                        UserState::unpack(&sk_state_account.try_borrow_data()?)?.split_count;
+
+                    BuyerState::pack(
+                        unpacked_subscriber_state,
+                        &mut subscriber_state.try_borrow_mut_data()?,
+                    )?;
+                }
+
+                assert_owned_by(subscriber_state, program_id)?;
+
+                let mut unpacked_subscriber_state =
+                    BuyerState::unpack(&subscriber_state.try_borrow_data()?)?;
+
+                unpacked_subscriber_state.mint = *sk_mint.key;
+                unpacked_subscriber_state
+                    .add_to_position(unpacked_subscription.quantity, unpacked_subscription.paid)
+                    .ok_or(DecenseError::MathError)?;
+                BuyerState::pack(
+                    unpacked_subscriber_state,
+                    &mut subscriber_state.try_borrow_mut_data()?,
+                )?;
+            }
+
+            OfferingStatus::Failed => {
+                **offering_account.try_borrow_mut_lamports()? = offering_account
+                    .lamports()
+                    .checked_sub(unpacked_subscription.paid)
+                    .ok_or(DecenseError::InsufficientVaultBalance)?;
+                **subscriber_account.try_borrow_mut_lamports()? = subscriber_account
+                    .lamports()
+                    .checked_add(unpacked_subscription.paid)
+                    .ok_or(DecenseError::MathError)?;
+            }
+        }
+
+        // close the subscription, it can only be claimed once
+        **subscriber_account.try_borrow_mut_lamports()? = subscriber_account
+            .lamports()
+            .checked_add(subscription_account.lamports())
+            .ok_or(DecenseError::MathError)?;
+        **subscription_account.try_borrow_mut_lamports()? = 0;
+        subscription_account.try_borrow_mut_data()?.fill(0);
+
+        Ok(())
+    }
 }
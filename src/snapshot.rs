@@ -0,0 +1,66 @@
+// merkle snapshots of token balances. issuers build the tree off-chain from the holders'
+// balances at a slot and record its root, holders prove their balance with the sibling hashes on
+// the path to the root. leaves and inner nodes are domain separated and pairs are hashed in
+// sorted order, so a proof is just the list of siblings
+
+use solana_program::{hash::hashv, pubkey::Pubkey};
+
+pub fn leaf(holder: &Pubkey, balance: u64) -> [u8; 32] {
+    hashv(&[&[0], holder.as_ref(), &balance.to_le_bytes()]).to_bytes()
+}
+
+pub fn node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let (low, high) = if left <= right {
+        (left, right)
+    } else {
+        (right, left)
+    };
+
+    hashv(&[&[1], low, high]).to_bytes()
+}
+
+pub fn verify(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    proof
+        .iter()
+        .fold(leaf, |hash, sibling| node(&hash, sibling))
+        == *root
+}
+
+// an odd node out is carried up unchanged
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+pub fn root(leaves: &[[u8; 32]]) -> Option<[u8; 32]> {
+    let mut level = leaves.to_vec();
+
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+
+    level.first().copied()
+}
+
+// sibling hashes from leaves[index] up to the root
+pub fn proof(leaves: &[[u8; 32]], mut index: usize) -> Vec<[u8; 32]> {
+    let mut level = leaves.to_vec();
+    let mut proof = vec![];
+
+    while level.len() > 1 {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+
+        level = next_level(&level);
+        index /= 2;
+    }
+
+    proof
+}
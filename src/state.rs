@@ -1,14 +1,90 @@
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use solana_program::{
+    hash::hashv,
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack, Sealed},
     pubkey::Pubkey,
 };
 
+use crate::{
+    error::DecenseError,
+    instruction::get_platform_state_address,
+    pricing::{CurveKind, PricingCurve, TOKEN_UNIT},
+};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum AccountType {
+    PlatformState = 1,
+    UserState = 2,
+    BuyerState = 3,
+}
+
+// accounts whose layout starts with is_initialized, a version byte and the account type and ends
+// in reserved space, so new fields can be carved out of the reserved bytes without resizing
+pub trait Versioned: Pack + IsInitialized {
+    const VERSION: u8;
+    const ACCOUNT_TYPE: AccountType;
+    // size of the unversioned layout the account was created with before versioning
+    const LEGACY_LEN: usize;
+    // size of the layout the first deployment created, a prefix of the legacy layout
+    const BASELINE_LEN: usize;
+
+    // sets what a layout of src_len bytes did not store and can not start out zeroed
+    fn upgrade(&mut self, _src_len: usize, _program_id: &Pubkey) {}
+
+    // rewrites the data of an account left by an older program in the current layout
+    fn migrate(src: &[u8], program_id: &Pubkey) -> Result<Vec<u8>, ProgramError> {
+        let mut dst = vec![0; Self::LEN];
+        dst[0] = src[0];
+        dst[1] = Self::VERSION;
+        dst[2] = Self::ACCOUNT_TYPE as u8;
+
+        if src.len() == Self::LEGACY_LEN || src.len() == Self::BASELINE_LEN {
+            // version 0 had neither the version byte nor the account type nor reserved space
+            dst[3..src.len() + 2].copy_from_slice(&src[1..]);
+        } else if src.len() == Self::LEN && src[1] == 1 {
+            // version 1 had no account type, which took the first reserved byte
+            dst[3..].copy_from_slice(&src[2..Self::LEN - 1]);
+        } else {
+            return Err(DecenseError::UnknownAccountLayout.into());
+        }
+
+        // refuse anything that is not an initialized account of this type
+        let mut state = Self::unpack(&dst)?;
+
+        state.upgrade(src.len(), program_id);
+        Self::pack(state, &mut dst)?;
+
+        Ok(dst)
+    }
+}
+
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub struct PlatformState {
     pub is_initialized: bool,
     pub platform_treasury_sol_wallet: Pubkey,
+    pub listing_fee: u64,
+    pub buy_fee_bps: u16,
+    pub sell_fee_bps: u16,
+    pub fee_recipient: Pubkey,
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub paused: bool,
+}
+
+impl Versioned for PlatformState {
+    const VERSION: u8 = 2;
+    const ACCOUNT_TYPE: AccountType = AccountType::PlatformState;
+    const LEGACY_LEN: usize = 142;
+    const BASELINE_LEN: usize = 33;
+
+    fn upgrade(&mut self, src_len: usize, _program_id: &Pubkey) {
+        // the baseline charged a fixed 1 SOL listing fee into the treasury and no trading fee
+        if src_len == Self::BASELINE_LEN {
+            self.listing_fee = 1_000_000_000;
+            self.fee_recipient = self.platform_treasury_sol_wallet;
+        }
+    }
 }
 
 impl Sealed for PlatformState {}
@@ -19,12 +95,25 @@ impl IsInitialized for PlatformState {
 }
 
 impl Pack for PlatformState {
-    const LEN: usize = 33;
+    const LEN: usize = 207;
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
         let src = array_ref![src, 0, PlatformState::LEN];
 
-        let (is_initialized, platform_treasury_sol_wallet) = array_refs![src, 1, 32];
+        let (
+            is_initialized,
+            version,
+            account_type,
+            platform_treasury_sol_wallet,
+            listing_fee,
+            buy_fee_bps,
+            sell_fee_bps,
+            fee_recipient,
+            admin,
+            pending_admin,
+            paused,
+            _reserved,
+        ) = array_refs![src, 1, 1, 1, 32, 8, 2, 2, 32, 32, 32, 1, 63];
 
         let is_initialized = match is_initialized {
             [0] => false,
@@ -32,24 +121,68 @@ impl Pack for PlatformState {
             _ => return Err(ProgramError::InvalidAccountData),
         };
 
+        if is_initialized && version[0] != Self::VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if is_initialized && account_type[0] != Self::ACCOUNT_TYPE as u8 {
+            return Err(DecenseError::AccountTypeMismatch.into());
+        }
+
         Ok(PlatformState {
             is_initialized,
             platform_treasury_sol_wallet: Pubkey::new_from_array(*platform_treasury_sol_wallet),
+            listing_fee: u64::from_le_bytes(*listing_fee),
+            buy_fee_bps: u16::from_le_bytes(*buy_fee_bps),
+            sell_fee_bps: u16::from_le_bytes(*sell_fee_bps),
+            fee_recipient: Pubkey::new_from_array(*fee_recipient),
+            admin: Pubkey::new_from_array(*admin),
+            pending_admin: Pubkey::new_from_array(*pending_admin),
+            paused: paused[0] != 0,
         })
     }
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
         let dst = array_mut_ref![dst, 0, PlatformState::LEN];
 
-        let (is_initialized_dst, platform_treasury_sol_wallet_dst) = mut_array_refs![dst, 1, 32];
+        let (
+            is_initialized_dst,
+            version_dst,
+            account_type_dst,
+            platform_treasury_sol_wallet_dst,
+            listing_fee_dst,
+            buy_fee_bps_dst,
+            sell_fee_bps_dst,
+            fee_recipient_dst,
+            admin_dst,
+            pending_admin_dst,
+            paused_dst,
+            _,
+        ) = mut_array_refs![dst, 1, 1, 1, 32, 8, 2, 2, 32, 32, 32, 1, 63];
 
         let PlatformState {
             is_initialized,
             platform_treasury_sol_wallet,
+            listing_fee,
+            buy_fee_bps,
+            sell_fee_bps,
+            fee_recipient,
+            admin,
+            pending_admin,
+            paused,
         } = self;
 
         is_initialized_dst[0] = *is_initialized as u8;
+        version_dst[0] = Self::VERSION;
+        account_type_dst[0] = Self::ACCOUNT_TYPE as u8;
         platform_treasury_sol_wallet_dst.copy_from_slice(platform_treasury_sol_wallet.as_ref());
+        *listing_fee_dst = listing_fee.to_le_bytes();
+        *buy_fee_bps_dst = buy_fee_bps.to_le_bytes();
+        *sell_fee_bps_dst = sell_fee_bps.to_le_bytes();
+        fee_recipient_dst.copy_from_slice(fee_recipient.as_ref());
+        admin_dst.copy_from_slice(admin.as_ref());
+        pending_admin_dst.copy_from_slice(pending_admin.as_ref());
+        paused_dst[0] = *paused as u8;
     }
 }
 
@@ -66,6 +199,93 @@ pub struct UserState {
     pub pda_ata: Pubkey,
     pub cmp: u64,
     pub holders: u64,
+    pub pricing_curve: u8,
+    pub curve_parameter: u64,
+    pub base_price: u64,
+    pub initial_pool: u64,
+    pub quote_mint: Pubkey,
+    pub dividend_count: u64,
+    pub proposal_count: u64,
+    pub platform_state: Pubkey,
+    // 0 while trading, see HaltReason
+    pub halt_reason: u8,
+    // end of a circuit breaker halt
+    pub halted_until: i64,
+    // largest move of cmp in basis points within breaker_window seconds, 0 turns it off
+    pub breaker_max_move_bps: u16,
+    pub breaker_window: u64,
+    pub breaker_cooldown: u64,
+    pub breaker_window_start: i64,
+    // cmp when the current window opened
+    pub breaker_reference_price: u64,
+    pub split_count: u64,
+    // whole tokens added by IssueNewShares over the listing's life, rescaled by splits
+    pub issued_supply: u64,
+}
+
+impl UserState {
+    pub fn pricing_curve(&self) -> Option<PricingCurve> {
+        Some(PricingCurve {
+            kind: CurveKind::from_u8(self.pricing_curve)?,
+            parameter: self.curve_parameter,
+            base_price: self.base_price,
+            initial_pool: self.initial_pool,
+        })
+    }
+
+    pub fn halt_reason(&self) -> Option<HaltReason> {
+        HaltReason::from_u8(self.halt_reason)
+    }
+}
+
+// most new shares a single issue can add, in basis points of the supply
+pub const MAX_ISSUE_BPS: u64 = 2000;
+
+// most new shares all issues together can add, in basis points of the shares not issued
+pub const MAX_TOTAL_ISSUE_BPS: u64 = 3000;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum HaltReason {
+    Admin = 1,
+    Issuer = 2,
+    CircuitBreaker = 3,
+    Delisted = 4,
+    Offering = 5,
+}
+
+impl HaltReason {
+    pub fn from_u8(reason: u8) -> Option<Self> {
+        match reason {
+            1 => Some(HaltReason::Admin),
+            2 => Some(HaltReason::Issuer),
+            3 => Some(HaltReason::CircuitBreaker),
+            4 => Some(HaltReason::Delisted),
+            5 => Some(HaltReason::Offering),
+            _ => None,
+        }
+    }
+}
+
+impl Versioned for UserState {
+    const VERSION: u8 = 2;
+    const ACCOUNT_TYPE: AccountType = AccountType::UserState;
+    const LEGACY_LEN: usize = 311;
+    const BASELINE_LEN: usize = 163;
+
+    fn upgrade(&mut self, src_len: usize, program_id: &Pubkey) {
+        // the baseline moved cmp by the price each buyer asked for and had no curve, so its
+        // listings keep trading at their last price on a flat one. half the supply went to the
+        // pool.
+        if src_len == Self::BASELINE_LEN {
+            self.pricing_curve = CurveKind::Linear as u8;
+            self.curve_parameter = 0;
+            self.base_price = self.cmp;
+            self.initial_pool = (self.supply / 2).saturating_mul(TOKEN_UNIT);
+        }
+
+        // listings follow the platform state to the program's single platform address
+        self.platform_state = get_platform_state_address(program_id);
+    }
 }
 
 impl Sealed for UserState {}
@@ -76,13 +296,15 @@ impl IsInitialized for UserState {
 }
 
 impl Pack for UserState {
-    const LEN: usize = 163;
+    const LEN: usize = 440;
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
         let src = array_ref![src, 0, UserState::LEN];
 
         let (
             is_initialized,
+            version,
+            account_type,
             user,
             market_valuation,
             supply,
@@ -93,7 +315,28 @@ impl Pack for UserState {
             pda_ata,
             cmp,
             holders,
-        ) = array_refs![src, 1, 32, 8, 8, 32, 32, 1, 1, 32, 8, 8];
+            pricing_curve,
+            curve_parameter,
+            base_price,
+            initial_pool,
+            quote_mint,
+            dividend_count,
+            proposal_count,
+            platform_state,
+            halt_reason,
+            halted_until,
+            breaker_max_move_bps,
+            breaker_window,
+            breaker_cooldown,
+            breaker_window_start,
+            breaker_reference_price,
+            split_count,
+            issued_supply,
+            _reserved,
+        ) = array_refs![
+            src, 1, 1, 1, 32, 8, 8, 32, 32, 1, 1, 32, 8, 8, 1, 8, 8, 8, 32, 8, 8, 32, 1, 8, 2, 8,
+            8, 8, 8, 8, 8, 111
+        ];
 
         let is_initialized = match is_initialized {
             [0] => false,
@@ -101,6 +344,14 @@ impl Pack for UserState {
             _ => return Err(ProgramError::InvalidAccountData),
         };
 
+        if is_initialized && version[0] != Self::VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if is_initialized && account_type[0] != Self::ACCOUNT_TYPE as u8 {
+            return Err(DecenseError::AccountTypeMismatch.into());
+        }
+
         Ok(UserState {
             is_initialized,
             user: Pubkey::new_from_array(*user),
@@ -113,6 +364,23 @@ impl Pack for UserState {
             pda_ata: Pubkey::new_from_array(*pda_ata),
             cmp: u64::from_le_bytes(*cmp),
             holders: u64::from_le_bytes(*holders),
+            pricing_curve: pricing_curve[0],
+            curve_parameter: u64::from_le_bytes(*curve_parameter),
+            base_price: u64::from_le_bytes(*base_price),
+            initial_pool: u64::from_le_bytes(*initial_pool),
+            quote_mint: Pubkey::new_from_array(*quote_mint),
+            dividend_count: u64::from_le_bytes(*dividend_count),
+            proposal_count: u64::from_le_bytes(*proposal_count),
+            platform_state: Pubkey::new_from_array(*platform_state),
+            halt_reason: halt_reason[0],
+            halted_until: i64::from_le_bytes(*halted_until),
+            breaker_max_move_bps: u16::from_le_bytes(*breaker_max_move_bps),
+            breaker_window: u64::from_le_bytes(*breaker_window),
+            breaker_cooldown: u64::from_le_bytes(*breaker_cooldown),
+            breaker_window_start: i64::from_le_bytes(*breaker_window_start),
+            breaker_reference_price: u64::from_le_bytes(*breaker_reference_price),
+            split_count: u64::from_le_bytes(*split_count),
+            issued_supply: u64::from_le_bytes(*issued_supply),
         })
     }
 
@@ -121,6 +389,8 @@ impl Pack for UserState {
 
         let (
             is_initialized_dst,
+            version_dst,
+            account_type_dst,
             user_dst,
             market_valuation_dst,
             supply_dst,
@@ -131,7 +401,28 @@ impl Pack for UserState {
             pda_ata_dst,
             cmp_dst,
             holders_dst,
-        ) = mut_array_refs![dst, 1, 32, 8, 8, 32, 32, 1, 1, 32, 8, 8];
+            pricing_curve_dst,
+            curve_parameter_dst,
+            base_price_dst,
+            initial_pool_dst,
+            quote_mint_dst,
+            dividend_count_dst,
+            proposal_count_dst,
+            platform_state_dst,
+            halt_reason_dst,
+            halted_until_dst,
+            breaker_max_move_bps_dst,
+            breaker_window_dst,
+            breaker_cooldown_dst,
+            breaker_window_start_dst,
+            breaker_reference_price_dst,
+            split_count_dst,
+            issued_supply_dst,
+            _,
+        ) = mut_array_refs![
+            dst, 1, 1, 1, 32, 8, 8, 32, 32, 1, 1, 32, 8, 8, 1, 8, 8, 8, 32, 8, 8, 32, 1, 8, 2, 8,
+            8, 8, 8, 8, 8, 111
+        ];
 
         let UserState {
             is_initialized,
@@ -145,9 +436,28 @@ impl Pack for UserState {
             pda_ata,
             cmp,
             holders,
+            pricing_curve,
+            curve_parameter,
+            base_price,
+            initial_pool,
+            quote_mint,
+            dividend_count,
+            proposal_count,
+            platform_state,
+            halt_reason,
+            halted_until,
+            breaker_max_move_bps,
+            breaker_window,
+            breaker_cooldown,
+            breaker_window_start,
+            breaker_reference_price,
+            split_count,
+            issued_supply,
         } = self;
 
         is_initialized_dst[0] = *is_initialized as u8;
+        version_dst[0] = Self::VERSION;
+        account_type_dst[0] = Self::ACCOUNT_TYPE as u8;
         user_dst.copy_from_slice(user.as_ref());
         *market_valuation_dst = market_valuation.to_le_bytes();
         *supply_dst = supply.to_le_bytes();
@@ -158,6 +468,23 @@ impl Pack for UserState {
         pda_ata_dst.copy_from_slice(pda_ata.as_ref());
         *cmp_dst = cmp.to_le_bytes();
         *holders_dst = holders.to_le_bytes();
+        pricing_curve_dst[0] = *pricing_curve;
+        *curve_parameter_dst = curve_parameter.to_le_bytes();
+        *base_price_dst = base_price.to_le_bytes();
+        *initial_pool_dst = initial_pool.to_le_bytes();
+        quote_mint_dst.copy_from_slice(quote_mint.as_ref());
+        *dividend_count_dst = dividend_count.to_le_bytes();
+        *proposal_count_dst = proposal_count.to_le_bytes();
+        platform_state_dst.copy_from_slice(platform_state.as_ref());
+        halt_reason_dst[0] = *halt_reason;
+        *halted_until_dst = halted_until.to_le_bytes();
+        *breaker_max_move_bps_dst = breaker_max_move_bps.to_le_bytes();
+        *breaker_window_dst = breaker_window.to_le_bytes();
+        *breaker_cooldown_dst = breaker_cooldown.to_le_bytes();
+        *breaker_window_start_dst = breaker_window_start.to_le_bytes();
+        *breaker_reference_price_dst = breaker_reference_price.to_le_bytes();
+        *split_count_dst = split_count.to_le_bytes();
+        *issued_supply_dst = issued_supply.to_le_bytes();
     }
 }
 
@@ -166,6 +493,69 @@ pub struct BuyerState {
     pub is_initialized: bool,
     pub buyer: Pubkey,
     pub current_holding_in_tokens: u64,
+    pub mint: Pubkey,
+    // quote paid, fees included, for current_holding_in_tokens
+    pub cost_basis: u64,
+    // proceeds of everything that left the position less its share of the cost basis
+    pub realized_pnl: i64,
+    // splits the holder's balance has been converted through by ClaimSplit
+    pub split_count: u64,
+}
+
+impl BuyerState {
+    // adds quantity bought for cost to the position
+    pub fn add_to_position(&mut self, quantity: u64, cost: u64) -> Option<()> {
+        self.current_holding_in_tokens = self.current_holding_in_tokens.checked_add(quantity)?;
+        self.cost_basis = self.cost_basis.checked_add(cost)?;
+
+        Some(())
+    }
+
+    // takes quantity out of the position and returns its share of the cost basis
+    pub fn release_basis(&mut self, quantity: u64) -> Option<u64> {
+        if quantity > self.current_holding_in_tokens {
+            return None;
+        }
+
+        let released_basis = if quantity == self.current_holding_in_tokens {
+            self.cost_basis
+        } else {
+            ((self.cost_basis as u128)
+                .checked_mul(quantity as u128)?
+                .checked_div(self.current_holding_in_tokens as u128)?) as u64
+        };
+
+        self.current_holding_in_tokens -= quantity;
+        self.cost_basis -= released_basis;
+
+        Some(released_basis)
+    }
+
+    // books proceeds against cost basis already released from the position
+    pub fn realize(&mut self, proceeds: u64, released_basis: u64) -> Option<()> {
+        let pnl = i64::try_from(proceeds as i128 - released_basis as i128).ok()?;
+
+        self.realized_pnl = self.realized_pnl.checked_add(pnl)?;
+
+        Some(())
+    }
+
+    // quote per whole token paid on average for the current holding
+    pub fn average_entry_price(&self) -> Option<u64> {
+        let price = (self.cost_basis as u128)
+            .checked_mul(TOKEN_UNIT as u128)?
+            .checked_div(self.current_holding_in_tokens as u128)?;
+
+        u64::try_from(price).ok()
+    }
+}
+
+impl Versioned for BuyerState {
+    const VERSION: u8 = 2;
+    const ACCOUNT_TYPE: AccountType = AccountType::BuyerState;
+    const LEGACY_LEN: usize = 41;
+    // positions kept their baseline layout until versioning
+    const BASELINE_LEN: usize = 41;
 }
 
 impl Sealed for BuyerState {}
@@ -176,12 +566,23 @@ impl IsInitialized for BuyerState {
 }
 
 impl Pack for BuyerState {
-    const LEN: usize = 41;
+    const LEN: usize = 106;
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
         let src = array_ref![src, 0, BuyerState::LEN];
 
-        let (is_initialized, buyer, current_holding_in_tokens) = array_refs![src, 1, 32, 8];
+        let (
+            is_initialized,
+            version,
+            account_type,
+            buyer,
+            current_holding_in_tokens,
+            mint,
+            cost_basis,
+            realized_pnl,
+            split_count,
+            _reserved,
+        ) = array_refs![src, 1, 1, 1, 32, 8, 32, 8, 8, 8, 7];
 
         let is_initialized = match is_initialized {
             [0] => false,
@@ -189,27 +590,1231 @@ impl Pack for BuyerState {
             _ => return Err(ProgramError::InvalidAccountData),
         };
 
+        if is_initialized && version[0] != Self::VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if is_initialized && account_type[0] != Self::ACCOUNT_TYPE as u8 {
+            return Err(DecenseError::AccountTypeMismatch.into());
+        }
+
         Ok(BuyerState {
             is_initialized,
             buyer: Pubkey::new_from_array(*buyer),
             current_holding_in_tokens: u64::from_le_bytes(*current_holding_in_tokens),
+            mint: Pubkey::new_from_array(*mint),
+            cost_basis: u64::from_le_bytes(*cost_basis),
+            realized_pnl: i64::from_le_bytes(*realized_pnl),
+            split_count: u64::from_le_bytes(*split_count),
         })
     }
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
         let dst = array_mut_ref![dst, 0, BuyerState::LEN];
 
-        let (is_initialized_dst, buyer_dst, current_holding_in_tokens_dst) =
-            mut_array_refs![dst, 1, 32, 8];
+        let (
+            is_initialized_dst,
+            version_dst,
+            account_type_dst,
+            buyer_dst,
+            current_holding_in_tokens_dst,
+            mint_dst,
+            cost_basis_dst,
+            realized_pnl_dst,
+            split_count_dst,
+            _,
+        ) = mut_array_refs![dst, 1, 1, 1, 32, 8, 32, 8, 8, 8, 7];
 
         let BuyerState {
             is_initialized,
             buyer,
             current_holding_in_tokens,
+            mint,
+            cost_basis,
+            realized_pnl,
+            split_count,
         } = self;
 
         is_initialized_dst[0] = *is_initialized as u8;
+        version_dst[0] = Self::VERSION;
+        account_type_dst[0] = Self::ACCOUNT_TYPE as u8;
         buyer_dst.copy_from_slice(buyer.as_ref());
         *current_holding_in_tokens_dst = current_holding_in_tokens.to_le_bytes();
+        mint_dst.copy_from_slice(mint.as_ref());
+        *cost_basis_dst = cost_basis.to_le_bytes();
+        *realized_pnl_dst = realized_pnl.to_le_bytes();
+        *split_count_dst = split_count.to_le_bytes();
+    }
+}
+
+pub const MAX_ORDERS_PER_SIDE: usize = 32;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Order {
+    pub owner: Pubkey,
+    pub price: u64,
+    pub quantity: u64,
+    pub sequence: u64,
+    // asks carry the cost basis of the tokens they escrow out of the owner's position
+    pub cost_basis: u64,
+    // bids escrow the buy fee at the rate they were placed with
+    pub fee_bps: u16,
+}
+
+impl Order {
+    pub const LEN: usize = 66;
+
+    // takes quantity off the order and returns its share of the cost basis
+    pub fn fill(&mut self, quantity: u64) -> Option<u64> {
+        let released_basis = if quantity == self.quantity {
+            self.cost_basis
+        } else {
+            ((self.cost_basis as u128)
+                .checked_mul(quantity as u128)?
+                .checked_div(self.quantity as u128)?) as u64
+        };
+
+        self.quantity = self.quantity.checked_sub(quantity)?;
+        self.cost_basis -= released_basis;
+
+        Some(released_basis)
+    }
+
+    fn unpack_from_array(src: &[u8; Order::LEN]) -> Self {
+        let (owner, price, quantity, sequence, cost_basis, fee_bps) =
+            array_refs![src, 32, 8, 8, 8, 8, 2];
+
+        Order {
+            owner: Pubkey::new_from_array(*owner),
+            price: u64::from_le_bytes(*price),
+            quantity: u64::from_le_bytes(*quantity),
+            sequence: u64::from_le_bytes(*sequence),
+            cost_basis: u64::from_le_bytes(*cost_basis),
+            fee_bps: u16::from_le_bytes(*fee_bps),
+        }
+    }
+
+    fn pack_into_array(&self, dst: &mut [u8; Order::LEN]) {
+        let (owner_dst, price_dst, quantity_dst, sequence_dst, cost_basis_dst, fee_bps_dst) =
+            mut_array_refs![dst, 32, 8, 8, 8, 8, 2];
+
+        owner_dst.copy_from_slice(self.owner.as_ref());
+        *price_dst = self.price.to_le_bytes();
+        *quantity_dst = self.quantity.to_le_bytes();
+        *sequence_dst = self.sequence.to_le_bytes();
+        *cost_basis_dst = self.cost_basis.to_le_bytes();
+        *fee_bps_dst = self.fee_bps.to_le_bytes();
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct OrderBook {
+    pub is_initialized: bool,
+    pub user_token_mint: Pubkey,
+    pub next_sequence: u64,
+    // best price first, ties broken by sequence
+    pub bids: Vec<Order>,
+    pub asks: Vec<Order>,
+}
+
+impl OrderBook {
+    pub fn insert_bid(&mut self, order: Order) -> bool {
+        if self.bids.len() >= MAX_ORDERS_PER_SIDE {
+            return false;
+        }
+
+        let index = self
+            .bids
+            .iter()
+            .position(|resting| resting.price < order.price)
+            .unwrap_or(self.bids.len());
+        self.bids.insert(index, order);
+
+        true
+    }
+
+    pub fn insert_ask(&mut self, order: Order) -> bool {
+        if self.asks.len() >= MAX_ORDERS_PER_SIDE {
+            return false;
+        }
+
+        let index = self
+            .asks
+            .iter()
+            .position(|resting| resting.price > order.price)
+            .unwrap_or(self.asks.len());
+        self.asks.insert(index, order);
+
+        true
+    }
+}
+
+impl Sealed for OrderBook {}
+impl IsInitialized for OrderBook {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for OrderBook {
+    const LEN: usize = 43 + 2 * MAX_ORDERS_PER_SIDE * Order::LEN;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, OrderBook::LEN];
+
+        let (is_initialized, user_token_mint, next_sequence, bid_count, ask_count, bids, asks) = array_refs![
+            src,
+            1,
+            32,
+            8,
+            1,
+            1,
+            MAX_ORDERS_PER_SIDE * Order::LEN,
+            MAX_ORDERS_PER_SIDE * Order::LEN
+        ];
+
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let bid_count = bid_count[0] as usize;
+        let ask_count = ask_count[0] as usize;
+
+        if bid_count > MAX_ORDERS_PER_SIDE || ask_count > MAX_ORDERS_PER_SIDE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let unpack_orders = |src: &[u8], count: usize| {
+            src.chunks_exact(Order::LEN)
+                .take(count)
+                .map(|chunk| Order::unpack_from_array(array_ref![chunk, 0, Order::LEN]))
+                .collect()
+        };
+
+        Ok(OrderBook {
+            is_initialized,
+            user_token_mint: Pubkey::new_from_array(*user_token_mint),
+            next_sequence: u64::from_le_bytes(*next_sequence),
+            bids: unpack_orders(bids, bid_count),
+            asks: unpack_orders(asks, ask_count),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, OrderBook::LEN];
+
+        let (
+            is_initialized_dst,
+            user_token_mint_dst,
+            next_sequence_dst,
+            bid_count_dst,
+            ask_count_dst,
+            bids_dst,
+            asks_dst,
+        ) = mut_array_refs![
+            dst,
+            1,
+            32,
+            8,
+            1,
+            1,
+            MAX_ORDERS_PER_SIDE * Order::LEN,
+            MAX_ORDERS_PER_SIDE * Order::LEN
+        ];
+
+        let OrderBook {
+            is_initialized,
+            user_token_mint,
+            next_sequence,
+            bids,
+            asks,
+        } = self;
+
+        is_initialized_dst[0] = *is_initialized as u8;
+        user_token_mint_dst.copy_from_slice(user_token_mint.as_ref());
+        *next_sequence_dst = next_sequence.to_le_bytes();
+        bid_count_dst[0] = bids.len() as u8;
+        ask_count_dst[0] = asks.len() as u8;
+
+        let pack_orders = |dst: &mut [u8], orders: &[Order]| {
+            dst.fill(0);
+            for (chunk, order) in dst.chunks_exact_mut(Order::LEN).zip(orders) {
+                order.pack_into_array(array_mut_ref![chunk, 0, Order::LEN]);
+            }
+        };
+
+        pack_orders(bids_dst, bids);
+        pack_orders(asks_dst, asks);
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Dividend {
+    pub is_initialized: bool,
+    pub user_token_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub index: u64,
+    // quote base units paid per whole token held at the snapshot
+    pub amount_per_token: u64,
+    // merkle root of (holder, balance) pairs, see snapshot.rs
+    pub snapshot_root: [u8; 32],
+    pub snapshot_slot: u64,
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    // unix timestamp after which claims close and the issuer can take back what is left
+    pub claim_deadline: i64,
+}
+
+impl Sealed for Dividend {}
+impl IsInitialized for Dividend {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Dividend {
+    const LEN: usize = 145;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Dividend::LEN];
+
+        let (
+            is_initialized,
+            user_token_mint,
+            quote_mint,
+            index,
+            amount_per_token,
+            snapshot_root,
+            snapshot_slot,
+            total_amount,
+            claimed_amount,
+            claim_deadline,
+        ) = array_refs![src, 1, 32, 32, 8, 8, 32, 8, 8, 8, 8];
+
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(Dividend {
+            is_initialized,
+            user_token_mint: Pubkey::new_from_array(*user_token_mint),
+            quote_mint: Pubkey::new_from_array(*quote_mint),
+            index: u64::from_le_bytes(*index),
+            amount_per_token: u64::from_le_bytes(*amount_per_token),
+            snapshot_root: *snapshot_root,
+            snapshot_slot: u64::from_le_bytes(*snapshot_slot),
+            total_amount: u64::from_le_bytes(*total_amount),
+            claimed_amount: u64::from_le_bytes(*claimed_amount),
+            claim_deadline: i64::from_le_bytes(*claim_deadline),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Dividend::LEN];
+
+        let (
+            is_initialized_dst,
+            user_token_mint_dst,
+            quote_mint_dst,
+            index_dst,
+            amount_per_token_dst,
+            snapshot_root_dst,
+            snapshot_slot_dst,
+            total_amount_dst,
+            claimed_amount_dst,
+            claim_deadline_dst,
+        ) = mut_array_refs![dst, 1, 32, 32, 8, 8, 32, 8, 8, 8, 8];
+
+        let Dividend {
+            is_initialized,
+            user_token_mint,
+            quote_mint,
+            index,
+            amount_per_token,
+            snapshot_root,
+            snapshot_slot,
+            total_amount,
+            claimed_amount,
+            claim_deadline,
+        } = self;
+
+        is_initialized_dst[0] = *is_initialized as u8;
+        user_token_mint_dst.copy_from_slice(user_token_mint.as_ref());
+        quote_mint_dst.copy_from_slice(quote_mint.as_ref());
+        *index_dst = index.to_le_bytes();
+        *amount_per_token_dst = amount_per_token.to_le_bytes();
+        *snapshot_root_dst = *snapshot_root;
+        *snapshot_slot_dst = snapshot_slot.to_le_bytes();
+        *total_amount_dst = total_amount.to_le_bytes();
+        *claimed_amount_dst = claimed_amount.to_le_bytes();
+        *claim_deadline_dst = claim_deadline.to_le_bytes();
+    }
+}
+
+// created on a holder's first claim of a dividend, its existence blocks a second claim
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct DividendClaim {
+    pub is_initialized: bool,
+    pub dividend: Pubkey,
+    pub holder: Pubkey,
+    pub amount: u64,
+}
+
+impl Sealed for DividendClaim {}
+impl IsInitialized for DividendClaim {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for DividendClaim {
+    const LEN: usize = 73;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, DividendClaim::LEN];
+
+        let (is_initialized, dividend, holder, amount) = array_refs![src, 1, 32, 32, 8];
+
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(DividendClaim {
+            is_initialized,
+            dividend: Pubkey::new_from_array(*dividend),
+            holder: Pubkey::new_from_array(*holder),
+            amount: u64::from_le_bytes(*amount),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, DividendClaim::LEN];
+
+        let (is_initialized_dst, dividend_dst, holder_dst, amount_dst) =
+            mut_array_refs![dst, 1, 32, 32, 8];
+
+        let DividendClaim {
+            is_initialized,
+            dividend,
+            holder,
+            amount,
+        } = self;
+
+        is_initialized_dst[0] = *is_initialized as u8;
+        dividend_dst.copy_from_slice(dividend.as_ref());
+        holder_dst.copy_from_slice(holder.as_ref());
+        *amount_dst = amount.to_le_bytes();
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ProposalStatus {
+    Voting,
+    Passed,
+    Rejected,
+    // a passed proposal whose on chain action has been carried out
+    Executed,
+}
+
+impl ProposalStatus {
+    pub fn from_u8(status: u8) -> Option<Self> {
+        match status {
+            0 => Some(ProposalStatus::Voting),
+            1 => Some(ProposalStatus::Passed),
+            2 => Some(ProposalStatus::Rejected),
+            3 => Some(ProposalStatus::Executed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Proposal {
+    pub is_initialized: bool,
+    pub user_token_mint: Pubkey,
+    pub proposer: Pubkey,
+    pub index: u64,
+    // hash of the proposal text, which is kept off chain
+    pub description_hash: [u8; 32],
+    pub voting_ends_at: i64,
+    // token base units that have to be cast for the result to count
+    pub quorum: u64,
+    // share of the cast votes in favour needed to pass
+    pub threshold_bps: u16,
+    pub yes_votes: u64,
+    pub no_votes: u64,
+    pub status: ProposalStatus,
+}
+
+impl Proposal {
+    // description hash a proposal has to carry to approve issuing amount new whole tokens
+    pub fn issue_new_shares_hash(amount: u64) -> [u8; 32] {
+        hashv(&[b"issue_new_shares", &amount.to_le_bytes()]).to_bytes()
+    }
+}
+
+impl Sealed for Proposal {}
+impl IsInitialized for Proposal {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Proposal {
+    const LEN: usize = 140;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Proposal::LEN];
+
+        let (
+            is_initialized,
+            user_token_mint,
+            proposer,
+            index,
+            description_hash,
+            voting_ends_at,
+            quorum,
+            threshold_bps,
+            yes_votes,
+            no_votes,
+            status,
+        ) = array_refs![src, 1, 32, 32, 8, 32, 8, 8, 2, 8, 8, 1];
+
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(Proposal {
+            is_initialized,
+            user_token_mint: Pubkey::new_from_array(*user_token_mint),
+            proposer: Pubkey::new_from_array(*proposer),
+            index: u64::from_le_bytes(*index),
+            description_hash: *description_hash,
+            voting_ends_at: i64::from_le_bytes(*voting_ends_at),
+            quorum: u64::from_le_bytes(*quorum),
+            threshold_bps: u16::from_le_bytes(*threshold_bps),
+            yes_votes: u64::from_le_bytes(*yes_votes),
+            no_votes: u64::from_le_bytes(*no_votes),
+            status: ProposalStatus::from_u8(status[0]).ok_or(ProgramError::InvalidAccountData)?,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Proposal::LEN];
+
+        let (
+            is_initialized_dst,
+            user_token_mint_dst,
+            proposer_dst,
+            index_dst,
+            description_hash_dst,
+            voting_ends_at_dst,
+            quorum_dst,
+            threshold_bps_dst,
+            yes_votes_dst,
+            no_votes_dst,
+            status_dst,
+        ) = mut_array_refs![dst, 1, 32, 32, 8, 32, 8, 8, 2, 8, 8, 1];
+
+        let Proposal {
+            is_initialized,
+            user_token_mint,
+            proposer,
+            index,
+            description_hash,
+            voting_ends_at,
+            quorum,
+            threshold_bps,
+            yes_votes,
+            no_votes,
+            status,
+        } = self;
+
+        is_initialized_dst[0] = *is_initialized as u8;
+        user_token_mint_dst.copy_from_slice(user_token_mint.as_ref());
+        proposer_dst.copy_from_slice(proposer.as_ref());
+        *index_dst = index.to_le_bytes();
+        *description_hash_dst = *description_hash;
+        *voting_ends_at_dst = voting_ends_at.to_le_bytes();
+        *quorum_dst = quorum.to_le_bytes();
+        *threshold_bps_dst = threshold_bps.to_le_bytes();
+        *yes_votes_dst = yes_votes.to_le_bytes();
+        *no_votes_dst = no_votes.to_le_bytes();
+        status_dst[0] = *status as u8;
+    }
+}
+
+// one per voter and proposal, closed once the escrowed tokens are withdrawn
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct VoteRecord {
+    pub is_initialized: bool,
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub amount: u64,
+    pub in_favor: bool,
+}
+
+impl Sealed for VoteRecord {}
+impl IsInitialized for VoteRecord {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for VoteRecord {
+    const LEN: usize = 74;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, VoteRecord::LEN];
+
+        let (is_initialized, proposal, voter, amount, in_favor) = array_refs![src, 1, 32, 32, 8, 1];
+
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let in_favor = match in_favor {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(VoteRecord {
+            is_initialized,
+            proposal: Pubkey::new_from_array(*proposal),
+            voter: Pubkey::new_from_array(*voter),
+            amount: u64::from_le_bytes(*amount),
+            in_favor,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, VoteRecord::LEN];
+
+        let (is_initialized_dst, proposal_dst, voter_dst, amount_dst, in_favor_dst) =
+            mut_array_refs![dst, 1, 32, 32, 8, 1];
+
+        let VoteRecord {
+            is_initialized,
+            proposal,
+            voter,
+            amount,
+            in_favor,
+        } = self;
+
+        is_initialized_dst[0] = *is_initialized as u8;
+        proposal_dst.copy_from_slice(proposal.as_ref());
+        voter_dst.copy_from_slice(voter.as_ref());
+        *amount_dst = amount.to_le_bytes();
+        in_favor_dst[0] = *in_favor as u8;
+    }
+}
+
+// the issuer's retained share, released linearly per interval after the cliff
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Vesting {
+    pub is_initialized: bool,
+    pub user_token_mint: Pubkey,
+    pub beneficiary: Pubkey,
+    pub start_time: i64,
+    // seconds after start_time
+    pub cliff: u64,
+    pub duration: u64,
+    pub release_interval: u64,
+    pub total_amount: u64,
+    pub released_amount: u64,
+}
+
+impl Vesting {
+    // amount unlocked at unix timestamp now, released or not
+    pub fn vested_amount(&self, now: i64) -> Option<u64> {
+        let elapsed = now.checked_sub(self.start_time)?.max(0) as u64;
+
+        if elapsed < self.cliff {
+            return Some(0);
+        }
+
+        if elapsed >= self.duration {
+            return Some(self.total_amount);
+        }
+
+        let elapsed = elapsed - elapsed % self.release_interval;
+
+        let vested = (self.total_amount as u128)
+            .checked_mul(elapsed as u128)?
+            .checked_div(self.duration as u128)?;
+
+        u64::try_from(vested).ok()
+    }
+}
+
+impl Sealed for Vesting {}
+impl IsInitialized for Vesting {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Vesting {
+    const LEN: usize = 113;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Vesting::LEN];
+
+        let (
+            is_initialized,
+            user_token_mint,
+            beneficiary,
+            start_time,
+            cliff,
+            duration,
+            release_interval,
+            total_amount,
+            released_amount,
+        ) = array_refs![src, 1, 32, 32, 8, 8, 8, 8, 8, 8];
+
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(Vesting {
+            is_initialized,
+            user_token_mint: Pubkey::new_from_array(*user_token_mint),
+            beneficiary: Pubkey::new_from_array(*beneficiary),
+            start_time: i64::from_le_bytes(*start_time),
+            cliff: u64::from_le_bytes(*cliff),
+            duration: u64::from_le_bytes(*duration),
+            release_interval: u64::from_le_bytes(*release_interval),
+            total_amount: u64::from_le_bytes(*total_amount),
+            released_amount: u64::from_le_bytes(*released_amount),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Vesting::LEN];
+
+        let (
+            is_initialized_dst,
+            user_token_mint_dst,
+            beneficiary_dst,
+            start_time_dst,
+            cliff_dst,
+            duration_dst,
+            release_interval_dst,
+            total_amount_dst,
+            released_amount_dst,
+        ) = mut_array_refs![dst, 1, 32, 32, 8, 8, 8, 8, 8, 8];
+
+        let Vesting {
+            is_initialized,
+            user_token_mint,
+            beneficiary,
+            start_time,
+            cliff,
+            duration,
+            release_interval,
+            total_amount,
+            released_amount,
+        } = self;
+
+        is_initialized_dst[0] = *is_initialized as u8;
+        user_token_mint_dst.copy_from_slice(user_token_mint.as_ref());
+        beneficiary_dst.copy_from_slice(beneficiary.as_ref());
+        *start_time_dst = start_time.to_le_bytes();
+        *cliff_dst = cliff.to_le_bytes();
+        *duration_dst = duration.to_le_bytes();
+        *release_interval_dst = release_interval.to_le_bytes();
+        *total_amount_dst = total_amount.to_le_bytes();
+        *released_amount_dst = released_amount.to_le_bytes();
+    }
+}
+
+// a change of the share count by numerator / denominator, holders convert their position
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Split {
+    pub is_initialized: bool,
+    pub user_token_mint: Pubkey,
+    pub index: u64,
+    pub numerator: u64,
+    pub denominator: u64,
+    // tokens held outside the pool and the vesting account at the split
+    pub eligible_supply: u64,
+}
+
+impl Split {
+    // balance after the split, fractions of a base unit are dropped
+    pub fn convert(&self, amount: u64) -> Option<u64> {
+        let converted = (amount as u128)
+            .checked_mul(self.numerator as u128)?
+            .checked_div(self.denominator as u128)?;
+
+        u64::try_from(converted).ok()
+    }
+
+    // price per whole token after the split
+    pub fn convert_price(&self, price: u64) -> Option<u64> {
+        let converted = (price as u128)
+            .checked_mul(self.denominator as u128)?
+            .checked_div(self.numerator as u128)?;
+
+        u64::try_from(converted).ok()
+    }
+}
+
+impl Sealed for Split {}
+impl IsInitialized for Split {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Split {
+    const LEN: usize = 65;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Split::LEN];
+
+        let (is_initialized, user_token_mint, index, numerator, denominator, eligible_supply) =
+            array_refs![src, 1, 32, 8, 8, 8, 8];
+
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(Split {
+            is_initialized,
+            user_token_mint: Pubkey::new_from_array(*user_token_mint),
+            index: u64::from_le_bytes(*index),
+            numerator: u64::from_le_bytes(*numerator),
+            denominator: u64::from_le_bytes(*denominator),
+            eligible_supply: u64::from_le_bytes(*eligible_supply),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Split::LEN];
+
+        let (
+            is_initialized_dst,
+            user_token_mint_dst,
+            index_dst,
+            numerator_dst,
+            denominator_dst,
+            eligible_supply_dst,
+        ) = mut_array_refs![dst, 1, 32, 8, 8, 8, 8];
+
+        let Split {
+            is_initialized,
+            user_token_mint,
+            index,
+            numerator,
+            denominator,
+            eligible_supply,
+        } = self;
+
+        is_initialized_dst[0] = *is_initialized as u8;
+        user_token_mint_dst.copy_from_slice(user_token_mint.as_ref());
+        *index_dst = index.to_le_bytes();
+        *numerator_dst = numerator.to_le_bytes();
+        *denominator_dst = denominator.to_le_bytes();
+        *eligible_supply_dst = eligible_supply.to_le_bytes();
+    }
+}
+
+// one per listing, the account holds the remaining budget in lamports on top of its rent
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Buyback {
+    pub is_initialized: bool,
+    pub user_token_mint: Pubkey,
+    pub max_price: u64,
+    // bought tokens are burned, otherwise they go back to the pool
+    pub burn: bool,
+    pub budget: u64,
+    pub bought: u64,
+}
+
+impl Sealed for Buyback {}
+impl IsInitialized for Buyback {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Buyback {
+    const LEN: usize = 58;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Buyback::LEN];
+
+        let (is_initialized, user_token_mint, max_price, burn, budget, bought) =
+            array_refs![src, 1, 32, 8, 1, 8, 8];
+
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let burn = match burn {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(Buyback {
+            is_initialized,
+            user_token_mint: Pubkey::new_from_array(*user_token_mint),
+            max_price: u64::from_le_bytes(*max_price),
+            burn,
+            budget: u64::from_le_bytes(*budget),
+            bought: u64::from_le_bytes(*bought),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Buyback::LEN];
+
+        let (
+            is_initialized_dst,
+            user_token_mint_dst,
+            max_price_dst,
+            burn_dst,
+            budget_dst,
+            bought_dst,
+        ) = mut_array_refs![dst, 1, 32, 8, 1, 8, 8];
+
+        let Buyback {
+            is_initialized,
+            user_token_mint,
+            max_price,
+            burn,
+            budget,
+            bought,
+        } = self;
+
+        is_initialized_dst[0] = *is_initialized as u8;
+        user_token_mint_dst.copy_from_slice(user_token_mint.as_ref());
+        *max_price_dst = max_price.to_le_bytes();
+        burn_dst[0] = *burn as u8;
+        *budget_dst = budget.to_le_bytes();
+        *bought_dst = bought.to_le_bytes();
+    }
+}
+
+// one per delisted listing, the account holds the settlement pool in lamports on top of its rent
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Settlement {
+    pub is_initialized: bool,
+    pub user_token_mint: Pubkey,
+    pub redemption_window: u64,
+    // zero until the issuer funds the settlement
+    pub redeem_ends_at: i64,
+    pub total_amount: u64,
+    pub eligible_supply: u64,
+    pub redeemed_supply: u64,
+    pub paid_amount: u64,
+    // splits the listing went through, holders redeem balances converted through all of them
+    pub split_count: u64,
+}
+
+impl Settlement {
+    // lamports for quantity of the eligible supply, rounded down so that payouts never exceed the pool
+    pub fn payout(&self, quantity: u64) -> Option<u64> {
+        let payout = (self.total_amount as u128)
+            .checked_mul(quantity as u128)?
+            .checked_div(self.eligible_supply as u128)?;
+
+        u64::try_from(payout).ok()
+    }
+}
+
+impl Sealed for Settlement {}
+impl IsInitialized for Settlement {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Settlement {
+    const LEN: usize = 89;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Settlement::LEN];
+
+        let (
+            is_initialized,
+            user_token_mint,
+            redemption_window,
+            redeem_ends_at,
+            total_amount,
+            eligible_supply,
+            redeemed_supply,
+            paid_amount,
+            split_count,
+        ) = array_refs![src, 1, 32, 8, 8, 8, 8, 8, 8, 8];
+
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(Settlement {
+            is_initialized,
+            user_token_mint: Pubkey::new_from_array(*user_token_mint),
+            redemption_window: u64::from_le_bytes(*redemption_window),
+            redeem_ends_at: i64::from_le_bytes(*redeem_ends_at),
+            total_amount: u64::from_le_bytes(*total_amount),
+            eligible_supply: u64::from_le_bytes(*eligible_supply),
+            redeemed_supply: u64::from_le_bytes(*redeemed_supply),
+            paid_amount: u64::from_le_bytes(*paid_amount),
+            split_count: u64::from_le_bytes(*split_count),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Settlement::LEN];
+
+        let (
+            is_initialized_dst,
+            user_token_mint_dst,
+            redemption_window_dst,
+            redeem_ends_at_dst,
+            total_amount_dst,
+            eligible_supply_dst,
+            redeemed_supply_dst,
+            paid_amount_dst,
+            split_count_dst,
+        ) = mut_array_refs![dst, 1, 32, 8, 8, 8, 8, 8, 8, 8];
+
+        let Settlement {
+            is_initialized,
+            user_token_mint,
+            redemption_window,
+            redeem_ends_at,
+            total_amount,
+            eligible_supply,
+            redeemed_supply,
+            paid_amount,
+            split_count,
+        } = self;
+
+        is_initialized_dst[0] = *is_initialized as u8;
+        user_token_mint_dst.copy_from_slice(user_token_mint.as_ref());
+        *redemption_window_dst = redemption_window.to_le_bytes();
+        *redeem_ends_at_dst = redeem_ends_at.to_le_bytes();
+        *total_amount_dst = total_amount.to_le_bytes();
+        *eligible_supply_dst = eligible_supply.to_le_bytes();
+        *redeemed_supply_dst = redeemed_supply.to_le_bytes();
+        *paid_amount_dst = paid_amount.to_le_bytes();
+        *split_count_dst = split_count.to_le_bytes();
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum OfferingStatus {
+    Open,
+    // the soft cap was met, subscribers claim their tokens
+    Succeeded,
+    // the soft cap was missed, subscribers claim refunds
+    Failed,
+}
+
+impl OfferingStatus {
+    pub fn from_u8(status: u8) -> Option<Self> {
+        match status {
+            0 => Some(OfferingStatus::Open),
+            1 => Some(OfferingStatus::Succeeded),
+            2 => Some(OfferingStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+// one per listing, the account holds the raised lamports on top of its rent until it is finalized
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Offering {
+    pub is_initialized: bool,
+    pub user_token_mint: Pubkey,
+    // lamports per whole token
+    pub price: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    // caps are in lamports raised
+    pub per_wallet_max: u64,
+    pub soft_cap: u64,
+    pub hard_cap: u64,
+    pub raised: u64,
+    pub sold: u64,
+    pub status: OfferingStatus,
+}
+
+impl Sealed for Offering {}
+impl IsInitialized for Offering {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Offering {
+    const LEN: usize = 98;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Offering::LEN];
+
+        let (
+            is_initialized,
+            user_token_mint,
+            price,
+            start_time,
+            end_time,
+            per_wallet_max,
+            soft_cap,
+            hard_cap,
+            raised,
+            sold,
+            status,
+        ) = array_refs![src, 1, 32, 8, 8, 8, 8, 8, 8, 8, 8, 1];
+
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(Offering {
+            is_initialized,
+            user_token_mint: Pubkey::new_from_array(*user_token_mint),
+            price: u64::from_le_bytes(*price),
+            start_time: i64::from_le_bytes(*start_time),
+            end_time: i64::from_le_bytes(*end_time),
+            per_wallet_max: u64::from_le_bytes(*per_wallet_max),
+            soft_cap: u64::from_le_bytes(*soft_cap),
+            hard_cap: u64::from_le_bytes(*hard_cap),
+            raised: u64::from_le_bytes(*raised),
+            sold: u64::from_le_bytes(*sold),
+            status: OfferingStatus::from_u8(status[0]).ok_or(ProgramError::InvalidAccountData)?,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Offering::LEN];
+
+        let (
+            is_initialized_dst,
+            user_token_mint_dst,
+            price_dst,
+            start_time_dst,
+            end_time_dst,
+            per_wallet_max_dst,
+            soft_cap_dst,
+            hard_cap_dst,
+            raised_dst,
+            sold_dst,
+            status_dst,
+        ) = mut_array_refs![dst, 1, 32, 8, 8, 8, 8, 8, 8, 8, 8, 1];
+
+        let Offering {
+            is_initialized,
+            user_token_mint,
+            price,
+            start_time,
+            end_time,
+            per_wallet_max,
+            soft_cap,
+            hard_cap,
+            raised,
+            sold,
+            status,
+        } = self;
+
+        is_initialized_dst[0] = *is_initialized as u8;
+        user_token_mint_dst.copy_from_slice(user_token_mint.as_ref());
+        *price_dst = price.to_le_bytes();
+        *start_time_dst = start_time.to_le_bytes();
+        *end_time_dst = end_time.to_le_bytes();
+        *per_wallet_max_dst = per_wallet_max.to_le_bytes();
+        *soft_cap_dst = soft_cap.to_le_bytes();
+        *hard_cap_dst = hard_cap.to_le_bytes();
+        *raised_dst = raised.to_le_bytes();
+        *sold_dst = sold.to_le_bytes();
+        status_dst[0] = *status as u8;
+    }
+}
+
+// one per subscriber and offering, closed once the subscriber claims tokens or a refund
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Subscription {
+    pub is_initialized: bool,
+    pub offering: Pubkey,
+    pub subscriber: Pubkey,
+    pub quantity: u64,
+    pub paid: u64,
+}
+
+impl Sealed for Subscription {}
+impl IsInitialized for Subscription {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Subscription {
+    const LEN: usize = 81;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Subscription::LEN];
+
+        let (is_initialized, offering, subscriber, quantity, paid) =
+            array_refs![src, 1, 32, 32, 8, 8];
+
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(Subscription {
+            is_initialized,
+            offering: Pubkey::new_from_array(*offering),
+            subscriber: Pubkey::new_from_array(*subscriber),
+            quantity: u64::from_le_bytes(*quantity),
+            paid: u64::from_le_bytes(*paid),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Subscription::LEN];
+
+        let (is_initialized_dst, offering_dst, subscriber_dst, quantity_dst, paid_dst) =
+            mut_array_refs![dst, 1, 32, 32, 8, 8];
+
+        let Subscription {
+            is_initialized,
+            offering,
+            subscriber,
+            quantity,
+            paid,
+        } = self;
+
+        is_initialized_dst[0] = *is_initialized as u8;
+        offering_dst.copy_from_slice(offering.as_ref());
+        subscriber_dst.copy_from_slice(subscriber.as_ref());
+        *quantity_dst = quantity.to_le_bytes();
+        *paid_dst = paid.to_le_bytes();
     }
 }
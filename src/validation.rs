@@ -1 +1,131 @@
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    program_option::COption, program_pack::Pack, pubkey::Pubkey, system_program, sysvar,
+};
 
+use spl_associated_token_account::get_associated_token_address;
+
+use crate::{error::DecenseError, state::PlatformState};
+
+pub fn assert_signer(account: &AccountInfo) -> ProgramResult {
+    if !account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    Ok(())
+}
+
+pub fn assert_writable(account: &AccountInfo) -> ProgramResult {
+    if !account.is_writable {
+        return Err(DecenseError::AccountNotWritable.into());
+    }
+
+    Ok(())
+}
+
+pub fn assert_owned_by(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if account.owner != owner {
+        return Err(DecenseError::InvalidAccountOwner.into());
+    }
+
+    Ok(())
+}
+
+pub fn assert_keys_equal(actual: &Pubkey, expected: &Pubkey) -> ProgramResult {
+    if actual != expected {
+        return Err(DecenseError::AccountKeyMismatch.into());
+    }
+
+    Ok(())
+}
+
+pub fn assert_program_id(account: &AccountInfo, program_id: &Pubkey) -> ProgramResult {
+    if account.key != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    Ok(())
+}
+
+pub fn assert_token_program(account: &AccountInfo) -> ProgramResult {
+    assert_program_id(account, &spl_token::id())
+}
+
+pub fn assert_system_program(account: &AccountInfo) -> ProgramResult {
+    assert_program_id(account, &system_program::id())
+}
+
+pub fn assert_associated_token_program(account: &AccountInfo) -> ProgramResult {
+    assert_program_id(account, &spl_associated_token_account::id())
+}
+
+pub fn assert_rent_sysvar(account: &AccountInfo) -> ProgramResult {
+    if !sysvar::rent::check_id(account.key) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    Ok(())
+}
+
+// account has to be the pda derived from seeds, returns its bump seed
+pub fn assert_pda(
+    account: &AccountInfo,
+    seeds: &[&[u8]],
+    program_id: &Pubkey,
+) -> Result<u8, ProgramError> {
+    let (pda, bump_seed) = Pubkey::find_program_address(seeds, program_id);
+
+    if pda != *account.key {
+        return Err(DecenseError::InvalidPDA.into());
+    }
+
+    Ok(bump_seed)
+}
+
+// account has to be wallet's associated token account for mint
+pub fn assert_associated_token_account(
+    account: &AccountInfo,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+) -> ProgramResult {
+    assert_keys_equal(account.key, &get_associated_token_address(wallet, mint))
+}
+
+// the program's platform state, the pda every listing is bound to
+pub fn assert_platform_state(account: &AccountInfo, program_id: &Pubkey) -> ProgramResult {
+    assert_owned_by(account, program_id)?;
+    assert_pda(account, &[b"platform"], program_id)?;
+
+    Ok(())
+}
+
+// admin has to sign and be the admin recorded in the platform state
+pub fn assert_platform_admin(admin: &AccountInfo, platform_state: &PlatformState) -> ProgramResult {
+    assert_signer(admin)?;
+
+    if *admin.key != platform_state.admin {
+        return Err(DecenseError::Unauthorized.into());
+    }
+
+    Ok(())
+}
+
+// nothing trades while the admin paused the platform
+pub fn assert_not_paused(platform_state: &PlatformState) -> ProgramResult {
+    if platform_state.paused {
+        return Err(DecenseError::PlatformPaused.into());
+    }
+
+    Ok(())
+}
+
+// only authority can mint, the listing pda once the issuer handed it over
+pub fn assert_mint_authority(mint: &AccountInfo, authority: &Pubkey) -> ProgramResult {
+    let unpacked_mint = spl_token::state::Mint::unpack(&mint.try_borrow_data()?)?;
+
+    if unpacked_mint.mint_authority != COption::Some(*authority) {
+        return Err(DecenseError::MintAuthorityNotHandedOver.into());
+    }
+
+    Ok(())
+}
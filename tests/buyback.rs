@@ -0,0 +1,291 @@
+mod common;
+
+use common::{assert_custom_error, Harness};
+use decense::{
+    error::DecenseError,
+    instruction::{
+        buyback, close_buyback, get_buyback_address, get_buyer_state_address,
+        get_listing_pda_address, get_order_book_address, get_user_state_address, place_order,
+        sell_to_buyback,
+    },
+    state::{Buyback, BuyerState, OrderBook, UserState},
+};
+use solana_sdk::{
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use spl_associated_token_account::get_associated_token_address;
+
+#[tokio::test]
+async fn buyback_burns_asks_and_holder_sales() {
+    let issuer = Keypair::new();
+    let asker = Keypair::new();
+    let holder = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness =
+        Harness::start(&[issuer.pubkey(), asker.pubkey(), holder.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+    let rent = harness.banks_client.get_rent().await.unwrap();
+
+    let platform_state = harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+    harness
+        .buy(&asker, &issuer.pubkey(), &mint.pubkey(), &treasury, 20_000)
+        .await;
+    harness
+        .buy(&holder, &issuer.pubkey(), &mint.pubkey(), &treasury, 20_000)
+        .await;
+
+    let user_state_address = get_user_state_address(&mint.pubkey(), &program_id);
+    let before: UserState = harness.unpack(user_state_address).await;
+    let price = before.cmp;
+
+    // one ask at half the price and one above what the buyback pays
+    harness
+        .process(
+            &[
+                place_order(
+                    &program_id,
+                    &asker.pubkey(),
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    &platform_state,
+                    1,
+                    price / 2,
+                    10_000,
+                ),
+                place_order(
+                    &program_id,
+                    &asker.pubkey(),
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    &platform_state,
+                    1,
+                    price * 2,
+                    10_000,
+                ),
+            ],
+            &[&asker],
+        )
+        .await;
+
+    let order_book: OrderBook = harness
+        .unpack(get_order_book_address(&mint.pubkey(), &program_id))
+        .await;
+    let ask_basis = order_book.asks[0].cost_basis;
+
+    let asker_before = harness.lamports(asker.pubkey()).await;
+    harness
+        .process(
+            &[buyback(
+                &program_id,
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                &platform_state,
+                price / 2 + price,
+                price,
+                true,
+                &[asker.pubkey(), asker.pubkey()],
+            )],
+            &[&issuer],
+        )
+        .await;
+
+    assert_eq!(
+        harness.lamports(asker.pubkey()).await,
+        asker_before + price / 2
+    );
+
+    // the asker realizes the sale against the cost the ask took out of its position
+    let asker_state: BuyerState = harness
+        .unpack(get_buyer_state_address(
+            &mint.pubkey(),
+            &asker.pubkey(),
+            &program_id,
+        ))
+        .await;
+    assert_eq!(
+        asker_state.realized_pnl,
+        (price / 2) as i64 - ask_basis as i64
+    );
+
+    let order_book: OrderBook = harness
+        .unpack(get_order_book_address(&mint.pubkey(), &program_id))
+        .await;
+    assert_eq!(order_book.asks.len(), 1);
+    assert_eq!(order_book.asks[0].price, price * 2);
+
+    let buyback_address = get_buyback_address(&mint.pubkey(), &program_id);
+    let buyback_state: Buyback = harness.unpack(buyback_address).await;
+    assert_eq!(buyback_state.budget, price);
+    assert_eq!(buyback_state.bought, 10_000);
+    assert_eq!(
+        harness.lamports(buyback_address).await,
+        rent.minimum_balance(Buyback::LEN) + price
+    );
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.supply, before.supply - 1);
+    assert_eq!(user_state.cmp, price / 2);
+
+    // holders sell at the buyback's limit price
+    let holder_before = harness.lamports(holder.pubkey()).await;
+    let sell = sell_to_buyback(
+        &program_id,
+        &holder.pubkey(),
+        &mint.pubkey(),
+        &platform_state,
+        10_000,
+    );
+    harness
+        .process(std::slice::from_ref(&sell), &[&holder])
+        .await;
+
+    assert_eq!(
+        harness.lamports(holder.pubkey()).await,
+        holder_before + price
+    );
+
+    let holder_ata = get_associated_token_address(&holder.pubkey(), &mint.pubkey());
+    assert_eq!(harness.token_balance(holder_ata).await, 10_000);
+
+    let holder_state: BuyerState = harness
+        .unpack(get_buyer_state_address(
+            &mint.pubkey(),
+            &holder.pubkey(),
+            &program_id,
+        ))
+        .await;
+    assert_eq!(holder_state.current_holding_in_tokens, 10_000);
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.supply, before.supply - 2);
+    assert_eq!(user_state.cmp, price);
+
+    // the budget is spent
+    harness.refresh_blockhash().await;
+    assert_custom_error(
+        harness.try_process(&[sell], &[&holder]).await,
+        DecenseError::InsufficientVaultBalance,
+    );
+
+    let issuer_before = harness.lamports(issuer.pubkey()).await;
+    harness
+        .process(
+            &[close_buyback(&program_id, &issuer.pubkey(), &mint.pubkey())],
+            &[&issuer],
+        )
+        .await;
+
+    assert_eq!(
+        harness.lamports(issuer.pubkey()).await,
+        issuer_before + rent.minimum_balance(Buyback::LEN)
+    );
+    assert!(harness
+        .banks_client
+        .get_account(buyback_address)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn buyback_can_return_tokens_to_the_pool() {
+    let issuer = Keypair::new();
+    let holder = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness = Harness::start(&[issuer.pubkey(), holder.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+
+    let platform_state = harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+    harness
+        .buy(&holder, &issuer.pubkey(), &mint.pubkey(), &treasury, 10_000)
+        .await;
+
+    let user_state_address = get_user_state_address(&mint.pubkey(), &program_id);
+    let before: UserState = harness.unpack(user_state_address).await;
+
+    assert_custom_error(
+        harness
+            .try_process(
+                &[buyback(
+                    &program_id,
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    &platform_state,
+                    before.cmp,
+                    0,
+                    false,
+                    &[],
+                )],
+                &[&issuer],
+            )
+            .await,
+        DecenseError::InvalidBuyback,
+    );
+
+    // a limit price that does not divide into whole base units of a single token
+    let max_price = before.cmp / 10_000 * 10_000 + 1;
+
+    // no order book yet, so the buyback only waits for holders
+    harness
+        .process(
+            &[buyback(
+                &program_id,
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                &platform_state,
+                before.cmp * 2,
+                max_price,
+                false,
+                &[],
+            )],
+            &[&issuer],
+        )
+        .await;
+
+    let pda_ata = get_associated_token_address(
+        &get_listing_pda_address(&mint.pubkey(), &program_id),
+        &mint.pubkey(),
+    );
+    let pool_before = harness.token_balance(pda_ata).await;
+
+    let sell = |quantity| {
+        sell_to_buyback(
+            &program_id,
+            &holder.pubkey(),
+            &mint.pubkey(),
+            &platform_state,
+            quantity,
+        )
+    };
+
+    // the market price is what the seller was paid, not the limit
+    harness.process(&[sell(1)], &[&holder]).await;
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.cmp, max_price - 1);
+
+    harness.process(&[sell(9_999)], &[&holder]).await;
+
+    assert_eq!(harness.token_balance(pda_ata).await, pool_before + 10_000);
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.supply, before.supply);
+    assert_eq!(user_state.holders, before.holders - 1);
+
+    let buyback_state: Buyback = harness
+        .unpack(get_buyback_address(&mint.pubkey(), &program_id))
+        .await;
+    assert_eq!(
+        buyback_state.budget,
+        before.cmp * 2 - max_price / 10_000 - max_price * 9_999 / 10_000
+    );
+    assert_eq!(buyback_state.bought, 10_000);
+}
@@ -0,0 +1,214 @@
+mod common;
+
+use common::{assert_custom_error, Harness};
+use decense::{
+    error::DecenseError,
+    instruction::{
+        configure_circuit_breaker, exchange, get_platform_state_address, get_user_state_address,
+        halt_trading, resume_trading,
+    },
+    state::{HaltReason, UserState},
+};
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+// buys one token with no slippage bounds
+fn buy(
+    harness: &Harness,
+    buyer: &Keypair,
+    issuer: &Keypair,
+    mint: &Keypair,
+    treasury: &Pubkey,
+) -> Instruction {
+    exchange(
+        &harness.program_id,
+        &buyer.pubkey(),
+        &issuer.pubkey(),
+        &mint.pubkey(),
+        &get_platform_state_address(&harness.program_id),
+        treasury,
+        None,
+        10_000,
+        u64::MAX,
+        0,
+    )
+}
+
+#[tokio::test]
+async fn issuer_and_admin_halt_and_resume_trading() {
+    let issuer = Keypair::new();
+    let buyer = Keypair::new();
+    let outsider = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness =
+        Harness::start(&[issuer.pubkey(), buyer.pubkey(), outsider.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+    let admin = harness.payer.pubkey();
+
+    let platform_state = harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+    let user_state_address = get_user_state_address(&mint.pubkey(), &program_id);
+
+    // only the admin and the issuer can halt
+    assert_custom_error(
+        harness
+            .try_process(
+                &[halt_trading(
+                    &program_id,
+                    &outsider.pubkey(),
+                    &platform_state,
+                    &mint.pubkey(),
+                )],
+                &[&outsider],
+            )
+            .await,
+        DecenseError::Unauthorized,
+    );
+
+    harness
+        .process(
+            &[halt_trading(
+                &program_id,
+                &issuer.pubkey(),
+                &platform_state,
+                &mint.pubkey(),
+            )],
+            &[&issuer],
+        )
+        .await;
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.halt_reason(), Some(HaltReason::Issuer));
+
+    let buy = buy(&harness, &buyer, &issuer, &mint, &treasury);
+    assert_custom_error(
+        harness
+            .try_process(std::slice::from_ref(&buy), &[&buyer])
+            .await,
+        DecenseError::TradingHalted,
+    );
+
+    // the admin's halt replaces the issuer's and only the admin can lift it
+    harness
+        .process(
+            &[halt_trading(
+                &program_id,
+                &admin,
+                &platform_state,
+                &mint.pubkey(),
+            )],
+            &[],
+        )
+        .await;
+
+    assert_custom_error(
+        harness
+            .try_process(
+                &[resume_trading(
+                    &program_id,
+                    &issuer.pubkey(),
+                    &platform_state,
+                    &mint.pubkey(),
+                )],
+                &[&issuer],
+            )
+            .await,
+        DecenseError::Unauthorized,
+    );
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.halt_reason(), Some(HaltReason::Admin));
+
+    harness
+        .process(
+            &[resume_trading(
+                &program_id,
+                &admin,
+                &platform_state,
+                &mint.pubkey(),
+            )],
+            &[],
+        )
+        .await;
+
+    harness.refresh_blockhash().await;
+    harness.process(&[buy], &[&buyer]).await;
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.halt_reason, 0);
+    assert_eq!(user_state.holders, 1);
+}
+
+#[tokio::test]
+async fn circuit_breaker_halts_until_the_cooldown_ends() {
+    let issuer = Keypair::new();
+    let buyer = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness = Harness::start(&[issuer.pubkey(), buyer.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+    let admin = harness.payer.pubkey();
+
+    let platform_state = harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+    let user_state_address = get_user_state_address(&mint.pubkey(), &program_id);
+
+    // at most 5% within an hour, then ten minutes of cooldown
+    harness
+        .process(
+            &[configure_circuit_breaker(
+                &program_id,
+                &admin,
+                &platform_state,
+                &mint.pubkey(),
+                500,
+                3_600,
+                600,
+            )],
+            &[],
+        )
+        .await;
+
+    let buy = buy(&harness, &buyer, &issuer, &mint, &treasury);
+
+    // the first token moves the price about 4%, the second one past 8%
+    harness.process(std::slice::from_ref(&buy), &[&buyer]).await;
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.halt_reason, 0);
+
+    harness.refresh_blockhash().await;
+    harness.process(std::slice::from_ref(&buy), &[&buyer]).await;
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.halt_reason(), Some(HaltReason::CircuitBreaker));
+    assert_eq!(
+        Some(user_state.cmp),
+        user_state.pricing_curve().unwrap().spot_price(480_000)
+    );
+
+    harness.refresh_blockhash().await;
+    assert_custom_error(
+        harness
+            .try_process(std::slice::from_ref(&buy), &[&buyer])
+            .await,
+        DecenseError::TradingHalted,
+    );
+
+    harness.advance_clock(600).await;
+    harness.refresh_blockhash().await;
+    harness.process(&[buy], &[&buyer]).await;
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.halt_reason, 0);
+    // the buy after the cooldown opened a new window at the price that tripped the breaker
+    assert_eq!(
+        Some(user_state.breaker_reference_price),
+        user_state.pricing_curve().unwrap().spot_price(480_000)
+    );
+}
@@ -0,0 +1,242 @@
+#![allow(dead_code)]
+
+use decense::{
+    error::DecenseError,
+    instruction::{exchange, get_platform_state_address, initialize_platform, initialize_user},
+    processor::Processor,
+};
+use solana_program_test::{
+    processor, BanksClient, BanksClientError, ProgramTest, ProgramTestContext,
+};
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    hash::Hash,
+    instruction::{Instruction, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program,
+    transaction::{Transaction, TransactionError},
+};
+use spl_token::state::{Account as TokenAccount, Mint};
+
+pub fn assert_custom_error(result: Result<(), BanksClientError>, error: DecenseError) {
+    match result.unwrap_err().unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+            assert_eq!(code, error as u32)
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+pub struct ListingTerms {
+    // cliff, duration and release interval of the issuer's share
+    pub vesting: [u64; 3],
+    pub user_treasury_percentage: u64,
+    pub liquidate_percentage: u64,
+}
+
+impl Default for ListingTerms {
+    fn default() -> Self {
+        Self {
+            vesting: [0; 3],
+            user_treasury_percentage: 50,
+            liquidate_percentage: 50,
+        }
+    }
+}
+
+pub struct Harness {
+    pub context: ProgramTestContext,
+    pub banks_client: BanksClient,
+    pub payer: Keypair,
+    pub recent_blockhash: Hash,
+    pub program_id: Pubkey,
+}
+
+impl Harness {
+    pub async fn start(wallets: &[Pubkey]) -> Self {
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "decense",
+            program_id,
+            processor!(Processor::unpack_and_process_instruction),
+        );
+        program_test.prefer_bpf(false);
+
+        for wallet in wallets {
+            program_test.add_account(
+                *wallet,
+                Account::new(100 * LAMPORTS_PER_SOL, 0, &system_program::id()),
+            );
+        }
+
+        let context = program_test.start_with_context().await;
+
+        Self {
+            banks_client: context.banks_client.clone(),
+            payer: context.payer.insecure_clone(),
+            recent_blockhash: context.last_blockhash,
+            context,
+            program_id,
+        }
+    }
+
+    pub async fn process(&mut self, instructions: &[Instruction], signers: &[&Keypair]) {
+        self.try_process(instructions, signers).await.unwrap();
+    }
+
+    pub async fn try_process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            self.recent_blockhash,
+        );
+
+        self.banks_client.process_transaction(transaction).await
+    }
+
+    // lets an identical transaction be sent again
+    pub async fn refresh_blockhash(&mut self) {
+        loop {
+            let blockhash = self.banks_client.get_latest_blockhash().await.unwrap();
+            if blockhash != self.recent_blockhash {
+                self.recent_blockhash = blockhash;
+                return;
+            }
+
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+    }
+
+    // moves the on-chain unix timestamp forward without producing slots
+    pub async fn advance_clock(&mut self, seconds: i64) {
+        let mut clock: Clock = self.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp += seconds;
+        self.context.set_sysvar(&clock);
+    }
+
+    pub async fn lamports(&mut self, address: Pubkey) -> u64 {
+        self.banks_client.get_balance(address).await.unwrap()
+    }
+
+    pub async fn unpack<T: Pack + IsInitialized>(&mut self, address: Pubkey) -> T {
+        let account = self
+            .banks_client
+            .get_account(address)
+            .await
+            .unwrap()
+            .unwrap();
+        T::unpack(&account.data).unwrap()
+    }
+
+    pub async fn initialize_platform(&mut self, treasury: &Pubkey) -> Pubkey {
+        let admin = self.payer.pubkey();
+        self.process(
+            &[initialize_platform(&self.program_id, &admin, treasury)],
+            &[],
+        )
+        .await;
+
+        get_platform_state_address(&self.program_id)
+    }
+
+    // lists 100 tokens valued at 100 units of the quote currency, half of them go to the pool and
+    // half of every buy stays in the vault
+    pub async fn list(
+        &mut self,
+        issuer: &Keypair,
+        mint: &Keypair,
+        treasury: &Pubkey,
+        quote_mint: Option<&Pubkey>,
+    ) {
+        self.list_with_terms(issuer, mint, treasury, quote_mint, ListingTerms::default())
+            .await;
+    }
+
+    pub async fn list_with_terms(
+        &mut self,
+        issuer: &Keypair,
+        mint: &Keypair,
+        treasury: &Pubkey,
+        quote_mint: Option<&Pubkey>,
+        terms: ListingTerms,
+    ) {
+        let rent = self.banks_client.get_rent().await.unwrap();
+        let platform_state = get_platform_state_address(&self.program_id);
+
+        self.process(
+            &[
+                system_instruction::create_account(
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    rent.minimum_balance(Mint::LEN),
+                    Mint::LEN as u64,
+                    &spl_token::id(),
+                ),
+                initialize_user(
+                    &self.program_id,
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    &platform_state,
+                    treasury,
+                    quote_mint,
+                    100,
+                    100,
+                    0,
+                    0,
+                    terms.vesting[0],
+                    terms.vesting[1],
+                    terms.vesting[2],
+                    terms.user_treasury_percentage,
+                    terms.liquidate_percentage,
+                ),
+            ],
+            &[issuer, mint],
+        )
+        .await;
+    }
+
+    pub async fn token_balance(&mut self, address: Pubkey) -> u64 {
+        self.unpack::<TokenAccount>(address).await.amount
+    }
+
+    // buys quantity from a lamport priced pool with no slippage bounds
+    pub async fn buy(
+        &mut self,
+        buyer: &Keypair,
+        issuer: &Pubkey,
+        mint: &Pubkey,
+        treasury: &Pubkey,
+        quantity: u64,
+    ) {
+        let platform_state = get_platform_state_address(&self.program_id);
+
+        self.process(
+            &[exchange(
+                &self.program_id,
+                &buyer.pubkey(),
+                issuer,
+                mint,
+                &platform_state,
+                treasury,
+                None,
+                quantity,
+                u64::MAX,
+                0,
+            )],
+            &[buyer],
+        )
+        .await;
+    }
+}
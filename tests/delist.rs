@@ -0,0 +1,339 @@
+mod common;
+
+use common::{assert_custom_error, Harness, ListingTerms};
+use decense::{
+    error::DecenseError,
+    instruction::{
+        close_listing, delist, deposit_settlement, exchange, get_buyer_state_address,
+        get_listing_pda_address, get_settlement_address, get_user_state_address, get_vault_address,
+        place_order, redeem_settlement, release_vested, resume_trading,
+    },
+    state::{BuyerState, HaltReason, Settlement, UserState},
+};
+use solana_sdk::{
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::{Account as TokenAccount, Mint};
+
+#[tokio::test]
+async fn holders_redeem_the_settlement_before_the_listing_closes() {
+    let issuer = Keypair::new();
+    let buyer = Keypair::new();
+    let outsider = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness =
+        Harness::start(&[issuer.pubkey(), buyer.pubkey(), outsider.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+    let admin = harness.payer.pubkey();
+    let rent = harness.banks_client.get_rent().await.unwrap();
+
+    let platform_state = harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+    harness
+        .buy(&buyer, &issuer.pubkey(), &mint.pubkey(), &treasury, 20_000)
+        .await;
+    // half of it rests in the order book when the listing is delisted
+    harness
+        .process(
+            &[place_order(
+                &program_id,
+                &buyer.pubkey(),
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                &platform_state,
+                1,
+                1_000_000_000,
+                10_000,
+            )],
+            &[&buyer],
+        )
+        .await;
+
+    let user_state_address = get_user_state_address(&mint.pubkey(), &program_id);
+    let settlement_address = get_settlement_address(&mint.pubkey(), &program_id);
+
+    // only the admin and the issuer can delist
+    assert_custom_error(
+        harness
+            .try_process(
+                &[delist(
+                    &program_id,
+                    &outsider.pubkey(),
+                    &platform_state,
+                    &mint.pubkey(),
+                    1_000,
+                )],
+                &[&outsider],
+            )
+            .await,
+        DecenseError::Unauthorized,
+    );
+
+    harness
+        .process(
+            &[delist(
+                &program_id,
+                &admin,
+                &platform_state,
+                &mint.pubkey(),
+                1_000,
+            )],
+            &[],
+        )
+        .await;
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.halt_reason(), Some(HaltReason::Delisted));
+
+    // trading stays halted for good
+    harness.refresh_blockhash().await;
+    assert_custom_error(
+        harness
+            .try_process(
+                &[exchange(
+                    &program_id,
+                    &buyer.pubkey(),
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    &platform_state,
+                    &treasury,
+                    None,
+                    10_000,
+                    u64::MAX,
+                    0,
+                )],
+                &[&buyer],
+            )
+            .await,
+        DecenseError::TradingHalted,
+    );
+    assert_custom_error(
+        harness
+            .try_process(
+                &[resume_trading(
+                    &program_id,
+                    &admin,
+                    &platform_state,
+                    &mint.pubkey(),
+                )],
+                &[],
+            )
+            .await,
+        DecenseError::ListingDelisted,
+    );
+
+    let close = close_listing(
+        &program_id,
+        &admin,
+        &platform_state,
+        &issuer.pubkey(),
+        &mint.pubkey(),
+    );
+    assert_custom_error(
+        harness.try_process(std::slice::from_ref(&close), &[]).await,
+        DecenseError::RedemptionNotOpen,
+    );
+
+    // the buyer's tokens, the escrowed ask included, and the issuer's half share 5.2 SOL and the
+    // liquidity vault, only the pool is not eligible
+    let vault_address = get_vault_address(&mint.pubkey(), &program_id);
+    let liquidity = harness.lamports(vault_address).await;
+    assert!(liquidity > 0);
+
+    let deposit = deposit_settlement(&program_id, &issuer.pubkey(), &mint.pubkey(), 5_200_000_000);
+    harness
+        .process(std::slice::from_ref(&deposit), &[&issuer])
+        .await;
+
+    let total_amount = 5_200_000_000 + liquidity;
+    let settlement: Settlement = harness.unpack(settlement_address).await;
+    assert_eq!(settlement.eligible_supply, 520_000);
+    assert_eq!(settlement.total_amount, total_amount);
+    assert!(harness
+        .banks_client
+        .get_account(vault_address)
+        .await
+        .unwrap()
+        .is_none());
+
+    harness.refresh_blockhash().await;
+    assert_custom_error(
+        harness.try_process(&[deposit], &[&issuer]).await,
+        DecenseError::SettlementAlreadyFunded,
+    );
+    assert_custom_error(
+        harness.try_process(std::slice::from_ref(&close), &[]).await,
+        DecenseError::RedemptionInProgress,
+    );
+
+    let buyer_before = harness.lamports(buyer.pubkey()).await;
+    harness
+        .process(
+            &[redeem_settlement(
+                &program_id,
+                &buyer.pubkey(),
+                &mint.pubkey(),
+                10_000,
+            )],
+            &[&buyer],
+        )
+        .await;
+
+    let payout = total_amount * 10_000 / 520_000;
+    assert_eq!(
+        harness.lamports(buyer.pubkey()).await,
+        buyer_before + payout
+    );
+
+    let buyer_ata = get_associated_token_address(&buyer.pubkey(), &mint.pubkey());
+    assert_eq!(harness.token_balance(buyer_ata).await, 0);
+
+    let buyer_state: BuyerState = harness
+        .unpack(get_buyer_state_address(
+            &mint.pubkey(),
+            &buyer.pubkey(),
+            &program_id,
+        ))
+        .await;
+    // the other half left the position with the ask
+    assert_eq!(buyer_state.current_holding_in_tokens, 0);
+
+    // the issuer let the window pass without redeeming its own half
+    harness.advance_clock(1_000).await;
+    assert_custom_error(
+        harness
+            .try_process(
+                &[redeem_settlement(
+                    &program_id,
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    500_000,
+                )],
+                &[&issuer],
+            )
+            .await,
+        DecenseError::RedemptionNotOpen,
+    );
+
+    let pda_ata = get_associated_token_address(
+        &get_listing_pda_address(&mint.pubkey(), &program_id),
+        &mint.pubkey(),
+    );
+    let issuer_before = harness.lamports(issuer.pubkey()).await;
+
+    harness.refresh_blockhash().await;
+    harness.process(&[close], &[]).await;
+
+    assert_eq!(
+        harness.lamports(issuer.pubkey()).await,
+        issuer_before + total_amount - payout
+            + rent.minimum_balance(Settlement::LEN)
+            + rent.minimum_balance(UserState::LEN)
+            + rent.minimum_balance(TokenAccount::LEN)
+    );
+
+    for address in [user_state_address, settlement_address, pda_ata] {
+        assert!(harness
+            .banks_client
+            .get_account(address)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    // the pool was burned, the issuer's unredeemed half and the escrowed ask are all that is left
+    let mint_state: Mint = harness.unpack(mint.pubkey()).await;
+    assert_eq!(mint_state.supply, 510_000);
+}
+
+#[tokio::test]
+async fn tokens_released_after_the_delisting_are_redeemed_too() {
+    let issuer = Keypair::new();
+    let buyer = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness = Harness::start(&[issuer.pubkey(), buyer.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+    let rent = harness.banks_client.get_rent().await.unwrap();
+
+    let platform_state = harness.initialize_platform(&treasury).await;
+    harness
+        .list_with_terms(
+            &issuer,
+            &mint,
+            &treasury,
+            None,
+            ListingTerms {
+                vesting: [100, 1_000, 100],
+                ..ListingTerms::default()
+            },
+        )
+        .await;
+    harness
+        .buy(&buyer, &issuer.pubkey(), &mint.pubkey(), &treasury, 10_000)
+        .await;
+
+    harness
+        .process(
+            &[
+                delist(
+                    &program_id,
+                    &issuer.pubkey(),
+                    &platform_state,
+                    &mint.pubkey(),
+                    10_000,
+                ),
+                deposit_settlement(&program_id, &issuer.pubkey(), &mint.pubkey(), 5_100_000_000),
+            ],
+            &[&issuer],
+        )
+        .await;
+
+    // the issuer's whole share is still vesting, and counts all the same
+    let settlement_address = get_settlement_address(&mint.pubkey(), &program_id);
+    let settlement: Settlement = harness.unpack(settlement_address).await;
+    assert_eq!(settlement.eligible_supply, 510_000);
+
+    harness.advance_clock(1_000).await;
+    harness
+        .process(
+            &[release_vested(
+                &program_id,
+                &issuer.pubkey(),
+                &mint.pubkey(),
+            )],
+            &[&issuer],
+        )
+        .await;
+
+    for (holder, quantity) in [(&buyer, 10_000), (&issuer, 500_000)] {
+        let before = harness.lamports(holder.pubkey()).await;
+        harness
+            .process(
+                &[redeem_settlement(
+                    &program_id,
+                    &holder.pubkey(),
+                    &mint.pubkey(),
+                    quantity,
+                )],
+                &[holder],
+            )
+            .await;
+
+        assert_eq!(
+            harness.lamports(holder.pubkey()).await,
+            before + settlement.total_amount * quantity / 510_000
+        );
+    }
+
+    // every eligible token was redeemed, rounding dust at most is left
+    let settlement: Settlement = harness.unpack(settlement_address).await;
+    assert_eq!(settlement.redeemed_supply, settlement.eligible_supply);
+    assert!(harness.lamports(settlement_address).await - rent.minimum_balance(Settlement::LEN) < 2);
+}
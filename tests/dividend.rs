@@ -0,0 +1,283 @@
+mod common;
+
+use common::{assert_custom_error, Harness};
+use decense::{
+    error::DecenseError,
+    instruction::{
+        claim_dividend, declare_dividend, get_dividend_address, get_dividend_claim_address,
+        get_user_state_address, reclaim_dividend,
+    },
+    snapshot,
+    state::{Dividend, DividendClaim, UserState},
+};
+use solana_sdk::{
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+#[tokio::test]
+async fn holders_claim_their_share_once() {
+    let issuer = Keypair::new();
+    let buyer = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness = Harness::start(&[issuer.pubkey(), buyer.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+    let rent = harness.banks_client.get_rent().await.unwrap();
+
+    harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+    harness
+        .buy(&buyer, &issuer.pubkey(), &mint.pubkey(), &treasury, 10_000)
+        .await;
+
+    // snapshot of the buyer's token and the issuer's half of the supply
+    let leaves = [
+        snapshot::leaf(&buyer.pubkey(), 10_000),
+        snapshot::leaf(&issuer.pubkey(), 500_000),
+    ];
+    let root = snapshot::root(&leaves).unwrap();
+
+    // 0.1 SOL per token
+    harness
+        .process(
+            &[declare_dividend(
+                &program_id,
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                0,
+                None,
+                100_000_000,
+                510_000,
+                86_400,
+                root,
+            )],
+            &[&issuer],
+        )
+        .await;
+
+    let dividend_address = get_dividend_address(&mint.pubkey(), 0, &program_id);
+    let dividend: Dividend = harness.unpack(dividend_address).await;
+    assert_eq!(dividend.total_amount, 5_100_000_000);
+    assert_eq!(dividend.snapshot_root, root);
+    assert_eq!(
+        harness.lamports(dividend_address).await,
+        rent.minimum_balance(Dividend::LEN) + 5_100_000_000
+    );
+
+    let user_state: UserState = harness
+        .unpack(get_user_state_address(&mint.pubkey(), &program_id))
+        .await;
+    assert_eq!(user_state.dividend_count, 1);
+
+    let buyer_before = harness.lamports(buyer.pubkey()).await;
+    let claim = claim_dividend(
+        &program_id,
+        &buyer.pubkey(),
+        &mint.pubkey(),
+        0,
+        None,
+        10_000,
+        snapshot::proof(&leaves, 0),
+    );
+    harness
+        .process(std::slice::from_ref(&claim), &[&buyer])
+        .await;
+
+    assert_eq!(
+        harness.lamports(buyer.pubkey()).await,
+        buyer_before + 100_000_000 - rent.minimum_balance(DividendClaim::LEN)
+    );
+
+    let claim_record: DividendClaim = harness
+        .unpack(get_dividend_claim_address(
+            &dividend_address,
+            &buyer.pubkey(),
+            &program_id,
+        ))
+        .await;
+    assert_eq!(claim_record.holder, buyer.pubkey());
+    assert_eq!(claim_record.amount, 100_000_000);
+
+    // claiming again fails
+    harness.refresh_blockhash().await;
+    assert_custom_error(
+        harness.try_process(&[claim], &[&buyer]).await,
+        DecenseError::DividendAlreadyClaimed,
+    );
+
+    // so does claiming more than the snapshot recorded
+    assert_custom_error(
+        harness
+            .try_process(
+                &[claim_dividend(
+                    &program_id,
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    0,
+                    None,
+                    510_000,
+                    snapshot::proof(&leaves, 1),
+                )],
+                &[&issuer],
+            )
+            .await,
+        DecenseError::InvalidSnapshotProof,
+    );
+
+    harness
+        .process(
+            &[claim_dividend(
+                &program_id,
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                0,
+                None,
+                500_000,
+                snapshot::proof(&leaves, 1),
+            )],
+            &[&issuer],
+        )
+        .await;
+
+    let dividend: Dividend = harness.unpack(dividend_address).await;
+    assert_eq!(dividend.claimed_amount, dividend.total_amount);
+}
+
+#[tokio::test]
+async fn issuer_reclaims_what_was_not_claimed_in_time() {
+    let issuer = Keypair::new();
+    let buyer = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness = Harness::start(&[issuer.pubkey(), buyer.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+    let rent = harness.banks_client.get_rent().await.unwrap();
+
+    harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+    harness
+        .buy(&buyer, &issuer.pubkey(), &mint.pubkey(), &treasury, 10_000)
+        .await;
+
+    let leaves = [
+        snapshot::leaf(&buyer.pubkey(), 10_000),
+        snapshot::leaf(&issuer.pubkey(), 500_000),
+    ];
+
+    harness
+        .process(
+            &[declare_dividend(
+                &program_id,
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                0,
+                None,
+                100_000_000,
+                510_000,
+                3_600,
+                snapshot::root(&leaves).unwrap(),
+            )],
+            &[&issuer],
+        )
+        .await;
+
+    let dividend_address = get_dividend_address(&mint.pubkey(), 0, &program_id);
+    let reclaim = reclaim_dividend(&program_id, &issuer.pubkey(), &mint.pubkey(), 0, None);
+
+    // holders have until the deadline
+    assert_custom_error(
+        harness
+            .try_process(std::slice::from_ref(&reclaim), &[&issuer])
+            .await,
+        DecenseError::DividendNotExpired,
+    );
+
+    harness
+        .process(
+            &[claim_dividend(
+                &program_id,
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                0,
+                None,
+                500_000,
+                snapshot::proof(&leaves, 1),
+            )],
+            &[&issuer],
+        )
+        .await;
+
+    harness.advance_clock(3_600).await;
+
+    assert_custom_error(
+        harness
+            .try_process(
+                &[claim_dividend(
+                    &program_id,
+                    &buyer.pubkey(),
+                    &mint.pubkey(),
+                    0,
+                    None,
+                    10_000,
+                    snapshot::proof(&leaves, 0),
+                )],
+                &[&buyer],
+            )
+            .await,
+        DecenseError::DividendExpired,
+    );
+
+    // the buyer's 0.1 SOL and the rent go back to the issuer
+    let issuer_before = harness.lamports(issuer.pubkey()).await;
+    harness.refresh_blockhash().await;
+    harness.process(&[reclaim], &[&issuer]).await;
+
+    assert_eq!(
+        harness.lamports(issuer.pubkey()).await,
+        issuer_before + 100_000_000 + rent.minimum_balance(Dividend::LEN)
+    );
+    assert!(harness
+        .banks_client
+        .get_account(dividend_address)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn snapshot_can_not_count_the_pool() {
+    let issuer = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness = Harness::start(&[issuer.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+
+    harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+
+    // only the issuer's half of the supply is outside the pool
+    assert_custom_error(
+        harness
+            .try_process(
+                &[declare_dividend(
+                    &program_id,
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    0,
+                    None,
+                    1,
+                    500_001,
+                    86_400,
+                    [0; 32],
+                )],
+                &[&issuer],
+            )
+            .await,
+        DecenseError::InvalidDividend,
+    );
+}
@@ -0,0 +1,404 @@
+mod common;
+
+use common::{assert_custom_error, Harness};
+use decense::{
+    error::DecenseError,
+    instruction::{
+        cast_vote, create_proposal, finalize_proposal, get_listing_pda_address,
+        get_platform_state_address, get_proposal_address, get_user_state_address,
+        get_vote_record_address, issue_new_shares, withdraw_vote,
+    },
+    state::{Proposal, ProposalStatus, UserState},
+};
+use solana_sdk::{
+    program_option::COption,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::Mint;
+
+const VOTING_PERIOD: u64 = 3600;
+
+#[tokio::test]
+async fn holders_vote_with_escrowed_tokens() {
+    let issuer = Keypair::new();
+    let buyer = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness = Harness::start(&[issuer.pubkey(), buyer.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+
+    harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+    harness
+        .buy(&buyer, &issuer.pubkey(), &mint.pubkey(), &treasury, 10_000)
+        .await;
+
+    // 21 tokens have to vote and half of them in favour
+    harness
+        .process(
+            &[create_proposal(
+                &program_id,
+                &buyer.pubkey(),
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                0,
+                VOTING_PERIOD,
+                210_000,
+                5000,
+                [7; 32],
+            )],
+            &[&buyer],
+        )
+        .await;
+
+    let user_state: UserState = harness
+        .unpack(get_user_state_address(&mint.pubkey(), &program_id))
+        .await;
+    assert_eq!(user_state.proposal_count, 1);
+
+    harness
+        .process(
+            &[
+                cast_vote(&program_id, &issuer.pubkey(), &mint.pubkey(), 0, 1, 200_000),
+                cast_vote(&program_id, &buyer.pubkey(), &mint.pubkey(), 0, 0, 10_000),
+            ],
+            &[&issuer, &buyer],
+        )
+        .await;
+
+    let proposal_address = get_proposal_address(&mint.pubkey(), 0, &program_id);
+    let escrow = get_associated_token_address(&proposal_address, &mint.pubkey());
+    let buyer_ata = get_associated_token_address(&buyer.pubkey(), &mint.pubkey());
+    assert_eq!(harness.token_balance(escrow).await, 210_000);
+    assert_eq!(harness.token_balance(buyer_ata).await, 0);
+
+    // one vote per holder
+    assert_custom_error(
+        harness
+            .try_process(
+                &[cast_vote(
+                    &program_id,
+                    &buyer.pubkey(),
+                    &mint.pubkey(),
+                    0,
+                    1,
+                    1,
+                )],
+                &[&buyer],
+            )
+            .await,
+        DecenseError::VoteAlreadyCast,
+    );
+
+    // tokens stay locked and the result stays open until the period ends
+    assert_custom_error(
+        harness
+            .try_process(&[finalize_proposal(&program_id, &mint.pubkey(), 0)], &[])
+            .await,
+        DecenseError::VotingInProgress,
+    );
+    assert_custom_error(
+        harness
+            .try_process(
+                &[withdraw_vote(
+                    &program_id,
+                    &buyer.pubkey(),
+                    &mint.pubkey(),
+                    0,
+                )],
+                &[&buyer],
+            )
+            .await,
+        DecenseError::VotingInProgress,
+    );
+
+    harness.advance_clock(VOTING_PERIOD as i64).await;
+
+    // the same finalize and withdraw transactions are sent again
+    harness.refresh_blockhash().await;
+
+    harness
+        .process(&[finalize_proposal(&program_id, &mint.pubkey(), 0)], &[])
+        .await;
+
+    let proposal: Proposal = harness.unpack(proposal_address).await;
+    assert_eq!(proposal.yes_votes, 200_000);
+    assert_eq!(proposal.no_votes, 10_000);
+    assert_eq!(proposal.status, ProposalStatus::Passed);
+    assert_eq!(proposal.description_hash, [7; 32]);
+
+    harness
+        .process(
+            &[withdraw_vote(
+                &program_id,
+                &buyer.pubkey(),
+                &mint.pubkey(),
+                0,
+            )],
+            &[&buyer],
+        )
+        .await;
+
+    assert_eq!(harness.token_balance(buyer_ata).await, 10_000);
+    assert_eq!(harness.token_balance(escrow).await, 200_000);
+
+    let vote_record = get_vote_record_address(&proposal_address, &buyer.pubkey(), &program_id);
+    assert!(harness
+        .banks_client
+        .get_account(vote_record)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn proposal_without_quorum_is_rejected() {
+    let issuer = Keypair::new();
+    let buyer = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness = Harness::start(&[issuer.pubkey(), buyer.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+
+    harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+    harness
+        .buy(&buyer, &issuer.pubkey(), &mint.pubkey(), &treasury, 10_000)
+        .await;
+
+    // a threshold above 100% can never pass
+    assert_custom_error(
+        harness
+            .try_process(
+                &[create_proposal(
+                    &program_id,
+                    &buyer.pubkey(),
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    0,
+                    VOTING_PERIOD,
+                    0,
+                    10_001,
+                    [0; 32],
+                )],
+                &[&buyer],
+            )
+            .await,
+        DecenseError::InvalidProposal,
+    );
+
+    harness
+        .process(
+            &[create_proposal(
+                &program_id,
+                &buyer.pubkey(),
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                0,
+                VOTING_PERIOD,
+                100_000,
+                5000,
+                [0; 32],
+            )],
+            &[&buyer],
+        )
+        .await;
+
+    harness
+        .process(
+            &[cast_vote(
+                &program_id,
+                &buyer.pubkey(),
+                &mint.pubkey(),
+                0,
+                1,
+                10_000,
+            )],
+            &[&buyer],
+        )
+        .await;
+
+    harness.advance_clock(VOTING_PERIOD as i64).await;
+
+    harness
+        .process(&[finalize_proposal(&program_id, &mint.pubkey(), 0)], &[])
+        .await;
+
+    let proposal: Proposal = harness
+        .unpack(get_proposal_address(&mint.pubkey(), 0, &program_id))
+        .await;
+    assert_eq!(proposal.status, ProposalStatus::Rejected);
+
+    // late votes are refused once the period is over
+    assert_custom_error(
+        harness
+            .try_process(
+                &[cast_vote(
+                    &program_id,
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    0,
+                    1,
+                    200_000,
+                )],
+                &[&issuer],
+            )
+            .await,
+        DecenseError::VotingClosed,
+    );
+}
+
+#[tokio::test]
+async fn passed_proposal_issues_new_shares_once() {
+    let issuer = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness = Harness::start(&[issuer.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+    let platform_state = get_platform_state_address(&program_id);
+
+    harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+
+    // the listing pda holds the mint authority and nobody can freeze holders
+    let unpacked_mint: Mint = harness.unpack(mint.pubkey()).await;
+    assert_eq!(
+        unpacked_mint.mint_authority,
+        COption::Some(get_listing_pda_address(&mint.pubkey(), &program_id))
+    );
+    assert_eq!(unpacked_mint.freeze_authority, COption::None);
+
+    // one proposal asks for more than a single issue may add, one for the most it may and
+    // one for more than all issues together may add
+    for (index, amount) in [(0, 25), (1, 20), (2, 15)] {
+        harness
+            .process(
+                &[
+                    create_proposal(
+                        &program_id,
+                        &issuer.pubkey(),
+                        &issuer.pubkey(),
+                        &mint.pubkey(),
+                        index,
+                        VOTING_PERIOD,
+                        100_000,
+                        5000,
+                        Proposal::issue_new_shares_hash(amount),
+                    ),
+                    cast_vote(
+                        &program_id,
+                        &issuer.pubkey(),
+                        &mint.pubkey(),
+                        index,
+                        1,
+                        100_000,
+                    ),
+                ],
+                &[&issuer],
+            )
+            .await;
+    }
+
+    let admin = harness.payer.pubkey();
+    let issue_with = |admin, index, amount| {
+        issue_new_shares(
+            &program_id,
+            &issuer.pubkey(),
+            &admin,
+            &mint.pubkey(),
+            &platform_state,
+            index,
+            amount,
+        )
+    };
+    let issue = |index, amount| issue_with(admin, index, amount);
+
+    // nothing is approved while the vote is open
+    assert_custom_error(
+        harness.try_process(&[issue(1, 20)], &[&issuer]).await,
+        DecenseError::IssueNotApproved,
+    );
+
+    harness.advance_clock(VOTING_PERIOD as i64).await;
+    harness
+        .process(
+            &[
+                finalize_proposal(&program_id, &mint.pubkey(), 0),
+                finalize_proposal(&program_id, &mint.pubkey(), 1),
+                finalize_proposal(&program_id, &mint.pubkey(), 2),
+            ],
+            &[],
+        )
+        .await;
+
+    assert_custom_error(
+        harness.try_process(&[issue(0, 25)], &[&issuer]).await,
+        DecenseError::IssueLimitExceeded,
+    );
+    assert_custom_error(
+        harness.try_process(&[issue(1, 19)], &[&issuer]).await,
+        DecenseError::IssueNotApproved,
+    );
+
+    // the issuer cannot approve dilution without the platform
+    assert_custom_error(
+        harness
+            .try_process(&[issue_with(issuer.pubkey(), 1, 20)], &[&issuer])
+            .await,
+        DecenseError::Unauthorized,
+    );
+
+    let issuer_ata = get_associated_token_address(&issuer.pubkey(), &mint.pubkey());
+    let pda_ata = get_associated_token_address(
+        &get_listing_pda_address(&mint.pubkey(), &program_id),
+        &mint.pubkey(),
+    );
+    let issuer_before = harness.token_balance(issuer_ata).await;
+    let pool_before = harness.token_balance(pda_ata).await;
+
+    harness.refresh_blockhash().await;
+    harness.process(&[issue(1, 20)], &[&issuer]).await;
+
+    // the new shares are sold from the pool, the issuer receives none
+    assert_eq!(harness.token_balance(issuer_ata).await, issuer_before);
+    assert_eq!(harness.token_balance(pda_ata).await - pool_before, 200_000);
+    let unpacked_mint: Mint = harness.unpack(mint.pubkey()).await;
+    assert_eq!(unpacked_mint.supply, 1_200_000);
+
+    let user_state: UserState = harness
+        .unpack(get_user_state_address(&mint.pubkey(), &program_id))
+        .await;
+    assert_eq!(user_state.supply, 120);
+    assert_eq!(user_state.market_valuation, 120);
+    assert_eq!(user_state.issued_supply, 20);
+    assert_eq!(
+        Some(user_state.cmp),
+        user_state
+            .pricing_curve()
+            .unwrap()
+            .spot_price(pool_before + 200_000)
+    );
+
+    let proposal: Proposal = harness
+        .unpack(get_proposal_address(&mint.pubkey(), 1, &program_id))
+        .await;
+    assert_eq!(proposal.status, ProposalStatus::Executed);
+
+    // a proposal issues once
+    harness.refresh_blockhash().await;
+    assert_custom_error(
+        harness.try_process(&[issue(1, 20)], &[&issuer]).await,
+        DecenseError::IssueNotApproved,
+    );
+
+    // 15 more fits a single issue of 120 shares, but 35 in total exceeds 30% of the 100 listed
+    assert_custom_error(
+        harness.try_process(&[issue(2, 15)], &[&issuer]).await,
+        DecenseError::IssueLimitExceeded,
+    );
+}
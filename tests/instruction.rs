@@ -0,0 +1,256 @@
+use decense::instruction::{
+    exchange, get_buyer_state_address, get_listing_pda_address, get_user_state_address,
+    get_vault_address, match_orders, send_receive_token, DecenseInstruction,
+};
+use solana_program::{pubkey::Pubkey, system_program};
+use spl_associated_token_account::get_associated_token_address;
+
+#[test]
+fn every_instruction_round_trips_through_pack() {
+    let instructions = [
+        DecenseInstruction::InitializePlatform,
+        DecenseInstruction::InitializeUser {
+            market_valuation: 1_000,
+            supply: 100,
+            pricing_curve: 2,
+            curve_parameter: 50,
+            vesting_cliff: 86_400,
+            vesting_duration: 31_536_000,
+            vesting_release_interval: 2_592_000,
+            user_treasury_percentage: 40,
+            liquidate_percentage: 25,
+        },
+        DecenseInstruction::Exchange {
+            quantity: 10_000,
+            max_total_cost: u64::MAX,
+            min_tokens_out: 1,
+        },
+        DecenseInstruction::SendRecieveToken {
+            action: 1,
+            amount: 5,
+        },
+        DecenseInstruction::SellToPool {
+            quantity: 10_000,
+            min_sol_out: 7,
+        },
+        DecenseInstruction::PlaceOrder {
+            side: 1,
+            price: 3,
+            quantity: 4,
+        },
+        DecenseInstruction::CancelOrder {
+            side: 0,
+            sequence: 9,
+        },
+        DecenseInstruction::MatchOrders { limit: 2 },
+        DecenseInstruction::UpdatePlatformConfig {
+            listing_fee: 0,
+            buy_fee_bps: 100,
+            sell_fee_bps: 200,
+        },
+        DecenseInstruction::TransferAdmin,
+        DecenseInstruction::AcceptAdmin,
+        DecenseInstruction::DeclareDividend {
+            amount_per_token: 1_000,
+            eligible_supply: 500_000,
+            claim_period: 86_400,
+            snapshot_root: [7; 32],
+        },
+        DecenseInstruction::ClaimDividend {
+            amount: 10_000,
+            proof: vec![[1; 32], [2; 32]],
+        },
+        DecenseInstruction::CreateProposal {
+            voting_period: 3600,
+            quorum: 210_000,
+            threshold_bps: 5000,
+            description_hash: [9; 32],
+        },
+        DecenseInstruction::CastVote {
+            support: 1,
+            amount: 10_000,
+        },
+        DecenseInstruction::FinalizeProposal,
+        DecenseInstruction::WithdrawVote,
+        DecenseInstruction::ReleaseVested,
+        DecenseInstruction::ConfigureCircuitBreaker {
+            max_move_bps: 500,
+            window: 3_600,
+            cooldown: 600,
+        },
+        DecenseInstruction::HaltTrading,
+        DecenseInstruction::ResumeTrading,
+        DecenseInstruction::Pause,
+        DecenseInstruction::Unpause,
+        DecenseInstruction::MigrateAccount,
+        DecenseInstruction::IssueNewShares { amount: 20 },
+        DecenseInstruction::Split {
+            numerator: 3,
+            denominator: 2,
+        },
+        DecenseInstruction::ClaimSplit,
+        DecenseInstruction::Buyback {
+            budget: 1_000_000_000,
+            max_price: 500_000_000,
+            burn: 1,
+        },
+        DecenseInstruction::SellToBuyback { quantity: 10_000 },
+        DecenseInstruction::CloseBuyback,
+        DecenseInstruction::Delist {
+            redemption_window: 604_800,
+        },
+        DecenseInstruction::DepositSettlement {
+            amount: 5_000_000_000,
+        },
+        DecenseInstruction::RedeemSettlement { quantity: 10_000 },
+        DecenseInstruction::CloseListing,
+        DecenseInstruction::CreateOffering {
+            price: 100_000_000,
+            start_time: 1_700_000_000,
+            end_time: 1_700_086_400,
+            per_wallet_max: 1_500_000_000,
+            soft_cap: 1_000_000_000,
+            hard_cap: 2_000_000_000,
+        },
+        DecenseInstruction::Subscribe { quantity: 10_000 },
+        DecenseInstruction::FinalizeOffering,
+        DecenseInstruction::ClaimOffering,
+        DecenseInstruction::ReclaimDividend,
+    ];
+
+    for instruction in instructions {
+        assert_eq!(
+            DecenseInstruction::unpack_instruction(&instruction.pack()).unwrap(),
+            instruction
+        );
+    }
+}
+
+#[test]
+fn claim_dividend_rejects_a_truncated_proof() {
+    let mut data = DecenseInstruction::ClaimDividend {
+        amount: 10_000,
+        proof: vec![[1; 32]],
+    }
+    .pack();
+    data.pop();
+
+    assert!(DecenseInstruction::unpack_instruction(&data).is_err());
+}
+
+#[test]
+fn exchange_lists_accounts_in_processor_order() {
+    let program_id = Pubkey::new_unique();
+    let exchanger = Pubkey::new_unique();
+    let issuer = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let platform_state = Pubkey::new_unique();
+    let fee_recipient = Pubkey::new_unique();
+
+    let instruction = exchange(
+        &program_id,
+        &exchanger,
+        &issuer,
+        &mint,
+        &platform_state,
+        &fee_recipient,
+        None,
+        10_000,
+        u64::MAX,
+        0,
+    );
+
+    let pda = get_listing_pda_address(&mint, &program_id);
+    let keys: Vec<Pubkey> = instruction
+        .accounts
+        .iter()
+        .map(|meta| meta.pubkey)
+        .collect();
+    assert_eq!(
+        keys[..11],
+        [
+            exchanger,
+            get_buyer_state_address(&mint, &exchanger, &program_id),
+            get_associated_token_address(&exchanger, &mint),
+            issuer,
+            mint,
+            get_user_state_address(&mint, &program_id),
+            pda,
+            get_associated_token_address(&pda, &mint),
+            get_vault_address(&mint, &program_id),
+            platform_state,
+            fee_recipient,
+        ]
+    );
+    assert_eq!(keys[14], system_program::id());
+
+    let signers: Vec<Pubkey> = instruction
+        .accounts
+        .iter()
+        .filter(|meta| meta.is_signer)
+        .map(|meta| meta.pubkey)
+        .collect();
+    assert_eq!(signers, vec![exchanger]);
+}
+
+#[test]
+fn send_receive_token_requires_issuer_signature_only_for_pool_withdrawals() {
+    let program_id = Pubkey::new_unique();
+    let issuer = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let exchanger = Pubkey::new_unique();
+    let platform_state = Pubkey::new_unique();
+
+    let deposit = send_receive_token(
+        &program_id,
+        &issuer,
+        &mint,
+        &exchanger,
+        &platform_state,
+        0,
+        1,
+    );
+    assert!(!deposit.accounts[0].is_signer);
+
+    let withdrawal = send_receive_token(
+        &program_id,
+        &issuer,
+        &mint,
+        &exchanger,
+        &platform_state,
+        1,
+        1,
+    );
+    assert!(withdrawal.accounts[0].is_signer);
+}
+
+#[test]
+fn match_orders_appends_five_accounts_per_fill() {
+    let program_id = Pubkey::new_unique();
+    let issuer = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let platform_state = Pubkey::new_unique();
+    let fee_recipient = Pubkey::new_unique();
+    let fills = [
+        (Pubkey::new_unique(), Pubkey::new_unique()),
+        (Pubkey::new_unique(), Pubkey::new_unique()),
+    ];
+
+    let instruction = match_orders(
+        &program_id,
+        &issuer,
+        &mint,
+        &platform_state,
+        &fee_recipient,
+        &fills,
+    );
+
+    assert_eq!(instruction.accounts.len(), 11 + 5 * fills.len());
+    assert_eq!(instruction.accounts[8].pubkey, fee_recipient);
+    assert_eq!(instruction.accounts[16].pubkey, fills[1].0);
+    assert_eq!(instruction.accounts[19].pubkey, fills[1].1);
+    assert_eq!(
+        DecenseInstruction::unpack_instruction(&instruction.data).unwrap(),
+        DecenseInstruction::MatchOrders { limit: 2 }
+    );
+}
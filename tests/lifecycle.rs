@@ -0,0 +1,807 @@
+mod common;
+
+use common::{assert_custom_error, Harness, ListingTerms};
+use decense::{
+    error::DecenseError,
+    instruction::{
+        delist, exchange, get_buyer_state_address, get_listing_pda_address,
+        get_platform_state_address, get_user_state_address, get_vault_address, initialize_user,
+        match_orders, pause, place_order, sell_to_pool, send_receive_token, unpause,
+        update_platform_config,
+    },
+    state::{BuyerState, PlatformState, UserState},
+};
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account,
+};
+use spl_token::state::{Account as TokenAccount, Mint};
+
+#[tokio::test]
+async fn list_buy_and_move_tokens() {
+    let issuer = Keypair::new();
+    let buyer = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness = Harness::start(&[issuer.pubkey(), buyer.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+    let rent = harness.banks_client.get_rent().await.unwrap();
+
+    let admin = harness.payer.pubkey();
+    let platform_state_address = harness.initialize_platform(&treasury).await;
+    let platform_state: PlatformState = harness.unpack(platform_state_address).await;
+    assert!(platform_state.is_initialized);
+    assert_eq!(platform_state.admin, admin);
+    assert_eq!(platform_state.platform_treasury_sol_wallet, treasury);
+    assert_eq!(platform_state.fee_recipient, treasury);
+
+    let treasury_before = harness.lamports(treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+
+    assert_eq!(
+        harness.lamports(treasury).await - treasury_before,
+        platform_state.listing_fee
+    );
+
+    let user_state_address = get_user_state_address(&mint.pubkey(), &program_id);
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.user, issuer.pubkey());
+    assert_eq!(user_state.user_token_mint, mint.pubkey());
+    assert_eq!(user_state.supply, 100);
+    assert_eq!(user_state.cmp, LAMPORTS_PER_SOL);
+    assert_eq!(user_state.holders, 0);
+    assert_eq!(user_state.initial_pool, 500_000);
+
+    let pda = get_listing_pda_address(&mint.pubkey(), &program_id);
+    let pool_ata = get_associated_token_address(&pda, &mint.pubkey());
+    let issuer_ata = get_associated_token_address(&issuer.pubkey(), &mint.pubkey());
+    let buyer_ata = get_associated_token_address(&buyer.pubkey(), &mint.pubkey());
+    let buyer_state_address = get_buyer_state_address(&mint.pubkey(), &buyer.pubkey(), &program_id);
+
+    assert_eq!(harness.token_balance(pool_ata).await, 500_000);
+    assert_eq!(harness.token_balance(issuer_ata).await, 500_000);
+
+    // buy one token from the pool
+    let cost = user_state
+        .pricing_curve()
+        .unwrap()
+        .quote_buy(500_000, 10_000)
+        .unwrap();
+    let issuer_before = harness.lamports(issuer.pubkey()).await;
+    let buyer_before = harness.lamports(buyer.pubkey()).await;
+
+    harness
+        .process(
+            &[exchange(
+                &program_id,
+                &buyer.pubkey(),
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                &platform_state_address,
+                &treasury,
+                None,
+                10_000,
+                cost,
+                10_000,
+            )],
+            &[&buyer],
+        )
+        .await;
+
+    // half of the cost stays in the vault to back sells
+    assert_eq!(
+        harness.lamports(issuer.pubkey()).await - issuer_before,
+        cost - cost / 2
+    );
+    assert_eq!(
+        harness
+            .lamports(get_vault_address(&mint.pubkey(), &program_id))
+            .await,
+        rent.minimum_balance(0) + cost / 2
+    );
+    assert_eq!(
+        buyer_before - harness.lamports(buyer.pubkey()).await,
+        cost + rent.minimum_balance(BuyerState::LEN) + rent.minimum_balance(TokenAccount::LEN)
+    );
+    assert_eq!(harness.token_balance(buyer_ata).await, 10_000);
+    assert_eq!(harness.token_balance(pool_ata).await, 490_000);
+
+    let buyer_state: BuyerState = harness.unpack(buyer_state_address).await;
+    assert_eq!(buyer_state.buyer, buyer.pubkey());
+    assert_eq!(buyer_state.current_holding_in_tokens, 10_000);
+    assert_eq!(buyer_state.mint, mint.pubkey());
+    assert_eq!(buyer_state.cost_basis, cost);
+    assert_eq!(buyer_state.average_entry_price(), Some(cost));
+    assert_eq!(buyer_state.realized_pnl, 0);
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.holders, 1);
+    assert_eq!(
+        Some(user_state.cmp),
+        user_state.pricing_curve().unwrap().spot_price(490_000)
+    );
+
+    // return part of the holding to the pool
+    harness
+        .process(
+            &[send_receive_token(
+                &program_id,
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                &buyer.pubkey(),
+                &platform_state_address,
+                0,
+                4_000,
+            )],
+            &[&buyer],
+        )
+        .await;
+
+    assert_eq!(harness.token_balance(buyer_ata).await, 6_000);
+    assert_eq!(harness.token_balance(pool_ata).await, 494_000);
+    let buyer_state: BuyerState = harness.unpack(buyer_state_address).await;
+    assert_eq!(buyer_state.current_holding_in_tokens, 6_000);
+    // tokens given back for nothing realize their cost as a loss
+    assert_eq!(buyer_state.cost_basis, cost - cost * 4 / 10);
+    assert_eq!(buyer_state.realized_pnl, -((cost * 4 / 10) as i64));
+
+    // the issuer sends pool tokens to the buyer
+    harness
+        .process(
+            &[send_receive_token(
+                &program_id,
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                &buyer.pubkey(),
+                &platform_state_address,
+                1,
+                2_000,
+            )],
+            &[&issuer, &buyer],
+        )
+        .await;
+
+    assert_eq!(harness.token_balance(buyer_ata).await, 8_000);
+    assert_eq!(harness.token_balance(pool_ata).await, 492_000);
+    let buyer_state: BuyerState = harness.unpack(buyer_state_address).await;
+    assert_eq!(buyer_state.current_holding_in_tokens, 8_000);
+    // and tokens sent by the issuer lower the average entry price
+    assert_eq!(buyer_state.cost_basis, cost - cost * 4 / 10);
+    assert_eq!(
+        buyer_state.average_entry_price(),
+        Some((cost - cost * 4 / 10) * 10_000 / 8_000)
+    );
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.holders, 1);
+}
+
+#[tokio::test]
+async fn buy_and_sell_against_a_stablecoin_quoted_pool() {
+    let issuer = Keypair::new();
+    let buyer = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+    let quote_mint = Keypair::new();
+
+    let mut harness = Harness::start(&[issuer.pubkey(), buyer.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+    let rent = harness.banks_client.get_rent().await.unwrap();
+    let payer = harness.payer.pubkey();
+
+    // a 6 decimal stablecoin, the buyer holds 1000 of it
+    let buyer_quote = get_associated_token_address(&buyer.pubkey(), &quote_mint.pubkey());
+    let treasury_quote = get_associated_token_address(&treasury, &quote_mint.pubkey());
+    harness
+        .process(
+            &[
+                system_instruction::create_account(
+                    &payer,
+                    &quote_mint.pubkey(),
+                    rent.minimum_balance(Mint::LEN),
+                    Mint::LEN as u64,
+                    &spl_token::id(),
+                ),
+                spl_token::instruction::initialize_mint2(
+                    &spl_token::id(),
+                    &quote_mint.pubkey(),
+                    &payer,
+                    None,
+                    6,
+                )
+                .unwrap(),
+                create_associated_token_account(
+                    &payer,
+                    &buyer.pubkey(),
+                    &quote_mint.pubkey(),
+                    &spl_token::id(),
+                ),
+                create_associated_token_account(
+                    &payer,
+                    &treasury,
+                    &quote_mint.pubkey(),
+                    &spl_token::id(),
+                ),
+                spl_token::instruction::mint_to(
+                    &spl_token::id(),
+                    &quote_mint.pubkey(),
+                    &buyer_quote,
+                    &payer,
+                    &[],
+                    1_000_000_000,
+                )
+                .unwrap(),
+            ],
+            &[&quote_mint],
+        )
+        .await;
+
+    let platform_state = harness.initialize_platform(&treasury).await;
+    harness
+        .process(
+            &[update_platform_config(
+                &program_id,
+                &payer,
+                &platform_state,
+                &treasury,
+                LAMPORTS_PER_SOL,
+                100,
+                100,
+            )],
+            &[],
+        )
+        .await;
+
+    harness
+        .list(&issuer, &mint, &treasury, Some(&quote_mint.pubkey()))
+        .await;
+
+    let user_state_address = get_user_state_address(&mint.pubkey(), &program_id);
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.quote_mint, quote_mint.pubkey());
+    // one quote token per listed token, in quote base units
+    assert_eq!(user_state.cmp, 1_000_000);
+
+    let vault = get_vault_address(&mint.pubkey(), &program_id);
+    let quote_vault = get_associated_token_address(&vault, &quote_mint.pubkey());
+    assert_eq!(harness.token_balance(quote_vault).await, 0);
+
+    // buy one token, the cost is split between the quote vault and the issuer and the fee goes to
+    // the treasury
+    let curve = user_state.pricing_curve().unwrap();
+    let cost = curve.quote_buy(500_000, 10_000).unwrap();
+    let buy_fee = cost / 100;
+    let issuer_before = harness.lamports(issuer.pubkey()).await;
+
+    harness
+        .process(
+            &[exchange(
+                &program_id,
+                &buyer.pubkey(),
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                &platform_state,
+                &treasury,
+                Some(&quote_mint.pubkey()),
+                10_000,
+                cost + buy_fee,
+                10_000,
+            )],
+            &[&buyer],
+        )
+        .await;
+
+    let issuer_quote = get_associated_token_address(&issuer.pubkey(), &quote_mint.pubkey());
+    assert_eq!(harness.token_balance(quote_vault).await, cost / 2);
+    assert_eq!(harness.token_balance(issuer_quote).await, cost - cost / 2);
+    assert_eq!(harness.token_balance(treasury_quote).await, buy_fee);
+    assert_eq!(
+        harness.token_balance(buyer_quote).await,
+        1_000_000_000 - cost - buy_fee
+    );
+    assert_eq!(harness.lamports(issuer.pubkey()).await, issuer_before);
+
+    let buyer_ata = get_associated_token_address(&buyer.pubkey(), &mint.pubkey());
+    assert_eq!(harness.token_balance(buyer_ata).await, 10_000);
+
+    // sell a fifth of it back out of the quote vault
+    let proceeds = curve.quote_sell(490_000, 2_000).unwrap();
+    let sell_fee = proceeds / 100;
+
+    harness
+        .process(
+            &[sell_to_pool(
+                &program_id,
+                &buyer.pubkey(),
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                &platform_state,
+                &treasury,
+                Some(&quote_mint.pubkey()),
+                2_000,
+                proceeds - sell_fee,
+            )],
+            &[&buyer],
+        )
+        .await;
+
+    assert_eq!(
+        harness.token_balance(quote_vault).await,
+        cost / 2 - proceeds
+    );
+    assert_eq!(
+        harness.token_balance(treasury_quote).await,
+        buy_fee + sell_fee
+    );
+    assert_eq!(
+        harness.token_balance(buyer_quote).await,
+        1_000_000_000 - cost - buy_fee + proceeds - sell_fee
+    );
+    assert_eq!(harness.token_balance(buyer_ata).await, 8_000);
+
+    let buyer_state: BuyerState = harness
+        .unpack(get_buyer_state_address(
+            &mint.pubkey(),
+            &buyer.pubkey(),
+            &program_id,
+        ))
+        .await;
+    assert_eq!(buyer_state.current_holding_in_tokens, 8_000);
+
+    // the fifth sold took a fifth of the cost basis, fees included on both sides
+    let basis = cost + buy_fee;
+    assert_eq!(buyer_state.cost_basis, basis - basis / 5);
+    assert_eq!(
+        buyer_state.realized_pnl,
+        (proceeds - sell_fee) as i64 - (basis / 5) as i64
+    );
+
+    // settlements pay lamports, the quote vault would never reach the holders
+    assert_custom_error(
+        harness
+            .try_process(
+                &[delist(
+                    &program_id,
+                    &issuer.pubkey(),
+                    &platform_state,
+                    &mint.pubkey(),
+                    1_000,
+                )],
+                &[&issuer],
+            )
+            .await,
+        DecenseError::InvalidQuoteMint,
+    );
+}
+
+#[tokio::test]
+async fn listing_terms_size_the_split_and_the_liquidity() {
+    let issuer = Keypair::new();
+    let buyer = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness = Harness::start(&[issuer.pubkey(), buyer.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+
+    harness.initialize_platform(&treasury).await;
+
+    // the issuer keeps a fifth of the supply and every buy backs sells in full
+    harness
+        .list_with_terms(
+            &issuer,
+            &mint,
+            &treasury,
+            None,
+            ListingTerms {
+                user_treasury_percentage: 20,
+                liquidate_percentage: 100,
+                ..ListingTerms::default()
+            },
+        )
+        .await;
+
+    let user_state: UserState = harness
+        .unpack(get_user_state_address(&mint.pubkey(), &program_id))
+        .await;
+    assert_eq!(user_state.user_treasury_percentage, 20);
+    assert_eq!(user_state.liquidate_percentage, 100);
+    assert_eq!(user_state.initial_pool, 800_000);
+
+    let pda = get_listing_pda_address(&mint.pubkey(), &program_id);
+    let issuer_ata = get_associated_token_address(&issuer.pubkey(), &mint.pubkey());
+    assert_eq!(
+        harness
+            .token_balance(get_associated_token_address(&pda, &mint.pubkey()))
+            .await,
+        800_000
+    );
+    assert_eq!(harness.token_balance(issuer_ata).await, 200_000);
+
+    let vault = get_vault_address(&mint.pubkey(), &program_id);
+    let issuer_before = harness.lamports(issuer.pubkey()).await;
+    let vault_before = harness.lamports(vault).await;
+    let cost = user_state
+        .pricing_curve()
+        .unwrap()
+        .quote_buy(800_000, 10_000)
+        .unwrap();
+
+    harness
+        .buy(&buyer, &issuer.pubkey(), &mint.pubkey(), &treasury, 10_000)
+        .await;
+
+    assert_eq!(harness.lamports(issuer.pubkey()).await, issuer_before);
+    assert_eq!(harness.lamports(vault).await - vault_before, cost);
+
+    // the whole position can be sold back out of the vault
+    let platform_state = get_platform_state_address(&program_id);
+    harness
+        .process(
+            &[sell_to_pool(
+                &program_id,
+                &buyer.pubkey(),
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                &platform_state,
+                &treasury,
+                None,
+                10_000,
+                0,
+            )],
+            &[&buyer],
+        )
+        .await;
+
+    assert_eq!(
+        harness
+            .token_balance(get_associated_token_address(
+                &buyer.pubkey(),
+                &mint.pubkey()
+            ))
+            .await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn one_issuer_lists_two_companies() {
+    let issuer = Keypair::new();
+    let buyer = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let first_mint = Keypair::new();
+    let second_mint = Keypair::new();
+
+    let mut harness = Harness::start(&[issuer.pubkey(), buyer.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+
+    harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &first_mint, &treasury, None).await;
+    harness.list(&issuer, &second_mint, &treasury, None).await;
+
+    harness
+        .buy(
+            &buyer,
+            &issuer.pubkey(),
+            &first_mint.pubkey(),
+            &treasury,
+            10_000,
+        )
+        .await;
+    harness
+        .buy(
+            &buyer,
+            &issuer.pubkey(),
+            &second_mint.pubkey(),
+            &treasury,
+            20_000,
+        )
+        .await;
+
+    // each company keeps its own listing and the buyer a position in each
+    for (mint, quantity) in [(&first_mint, 10_000), (&second_mint, 20_000)] {
+        let user_state: UserState = harness
+            .unpack(get_user_state_address(&mint.pubkey(), &program_id))
+            .await;
+        assert_eq!(user_state.user, issuer.pubkey());
+        assert_eq!(user_state.user_token_mint, mint.pubkey());
+        assert_eq!(user_state.holders, 1);
+
+        let buyer_state: BuyerState = harness
+            .unpack(get_buyer_state_address(
+                &mint.pubkey(),
+                &buyer.pubkey(),
+                &program_id,
+            ))
+            .await;
+        assert_eq!(buyer_state.mint, mint.pubkey());
+        assert_eq!(buyer_state.current_holding_in_tokens, quantity);
+    }
+}
+
+#[tokio::test]
+async fn positions_only_sell_what_the_program_saw_them_acquire() {
+    let issuer = Keypair::new();
+    let buyer = Keypair::new();
+    let receiver = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness =
+        Harness::start(&[issuer.pubkey(), buyer.pubkey(), receiver.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+
+    let platform_state = harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+
+    // the issuer's position opens with the share it kept
+    let issuer_state: BuyerState = harness
+        .unpack(get_buyer_state_address(
+            &mint.pubkey(),
+            &issuer.pubkey(),
+            &program_id,
+        ))
+        .await;
+    assert_eq!(issuer_state.current_holding_in_tokens, 500_000);
+    assert_eq!(issuer_state.cost_basis, 0);
+
+    harness
+        .buy(&buyer, &issuer.pubkey(), &mint.pubkey(), &treasury, 10_000)
+        .await;
+    harness
+        .buy(
+            &receiver,
+            &issuer.pubkey(),
+            &mint.pubkey(),
+            &treasury,
+            10_000,
+        )
+        .await;
+
+    // a plain token transfer moves no position
+    let buyer_ata = get_associated_token_address(&buyer.pubkey(), &mint.pubkey());
+    let receiver_ata = get_associated_token_address(&receiver.pubkey(), &mint.pubkey());
+    harness
+        .process(
+            &[spl_token::instruction::transfer(
+                &spl_token::id(),
+                &buyer_ata,
+                &receiver_ata,
+                &buyer.pubkey(),
+                &[],
+                5_000,
+            )
+            .unwrap()],
+            &[&buyer],
+        )
+        .await;
+    assert_eq!(harness.token_balance(receiver_ata).await, 15_000);
+
+    let sell = |quantity| {
+        sell_to_pool(
+            &program_id,
+            &receiver.pubkey(),
+            &issuer.pubkey(),
+            &mint.pubkey(),
+            &platform_state,
+            &treasury,
+            None,
+            quantity,
+            0,
+        )
+    };
+    let give_back = send_receive_token(
+        &program_id,
+        &issuer.pubkey(),
+        &mint.pubkey(),
+        &receiver.pubkey(),
+        &platform_state,
+        0,
+        15_000,
+    );
+
+    for instruction in [sell(15_000), give_back] {
+        assert_custom_error(
+            harness.try_process(&[instruction], &[&receiver]).await,
+            DecenseError::PositionExceeded,
+        );
+    }
+
+    // what the position accounts for still sells
+    harness.process(&[sell(5_000)], &[&receiver]).await;
+
+    let receiver_state: BuyerState = harness
+        .unpack(get_buyer_state_address(
+            &mint.pubkey(),
+            &receiver.pubkey(),
+            &program_id,
+        ))
+        .await;
+    assert_eq!(receiver_state.current_holding_in_tokens, 5_000);
+    assert_eq!(harness.token_balance(receiver_ata).await, 10_000);
+}
+
+#[tokio::test]
+async fn paused_platform_refuses_listings_and_trades() {
+    let issuer = Keypair::new();
+    let late_issuer = Keypair::new();
+    let buyer = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+    let late_mint = Keypair::new();
+
+    let mut harness = Harness::start(&[
+        issuer.pubkey(),
+        late_issuer.pubkey(),
+        buyer.pubkey(),
+        treasury,
+    ])
+    .await;
+    let program_id = harness.program_id;
+    let admin = harness.payer.pubkey();
+    let rent = harness.banks_client.get_rent().await.unwrap();
+
+    let platform_state = harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+    harness
+        .buy(&buyer, &issuer.pubkey(), &mint.pubkey(), &treasury, 10_000)
+        .await;
+
+    let order = |price| {
+        place_order(
+            &program_id,
+            &buyer.pubkey(),
+            &issuer.pubkey(),
+            &mint.pubkey(),
+            &platform_state,
+            1,
+            price,
+            1_000,
+        )
+    };
+    // opens the order book, so that the crank below gets as far as the pause check
+    harness.process(&[order(u64::MAX / 2)], &[&buyer]).await;
+
+    harness
+        .process(&[pause(&program_id, &admin, &platform_state)], &[])
+        .await;
+
+    // the buy below is the same transaction as the one above
+    harness.refresh_blockhash().await;
+
+    let listing = [
+        system_instruction::create_account(
+            &late_issuer.pubkey(),
+            &late_mint.pubkey(),
+            rent.minimum_balance(Mint::LEN),
+            Mint::LEN as u64,
+            &spl_token::id(),
+        ),
+        initialize_user(
+            &program_id,
+            &late_issuer.pubkey(),
+            &late_mint.pubkey(),
+            &platform_state,
+            &treasury,
+            None,
+            100,
+            100,
+            0,
+            0,
+            0,
+            0,
+            0,
+            50,
+            50,
+        ),
+    ];
+    assert_custom_error(
+        harness
+            .try_process(&listing, &[&late_issuer, &late_mint])
+            .await,
+        DecenseError::PlatformPaused,
+    );
+
+    let buy = exchange(
+        &program_id,
+        &buyer.pubkey(),
+        &issuer.pubkey(),
+        &mint.pubkey(),
+        &platform_state,
+        &treasury,
+        None,
+        10_000,
+        u64::MAX,
+        0,
+    );
+    assert_custom_error(
+        harness
+            .try_process(std::slice::from_ref(&buy), &[&buyer])
+            .await,
+        DecenseError::PlatformPaused,
+    );
+
+    assert_custom_error(
+        harness
+            .try_process(
+                &[send_receive_token(
+                    &program_id,
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    &buyer.pubkey(),
+                    &platform_state,
+                    0,
+                    10_000,
+                )],
+                &[&buyer],
+            )
+            .await,
+        DecenseError::PlatformPaused,
+    );
+
+    // pool sells and the order book stop as well
+    assert_custom_error(
+        harness
+            .try_process(
+                &[sell_to_pool(
+                    &program_id,
+                    &buyer.pubkey(),
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    &platform_state,
+                    &treasury,
+                    None,
+                    1_000,
+                    0,
+                )],
+                &[&buyer],
+            )
+            .await,
+        DecenseError::PlatformPaused,
+    );
+    assert_custom_error(
+        harness.try_process(&[order(1)], &[&buyer]).await,
+        DecenseError::PlatformPaused,
+    );
+    assert_custom_error(
+        harness
+            .try_process(
+                &[match_orders(
+                    &program_id,
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    &platform_state,
+                    &treasury,
+                    &[],
+                )],
+                &[],
+            )
+            .await,
+        DecenseError::PlatformPaused,
+    );
+
+    harness
+        .process(&[unpause(&program_id, &admin, &platform_state)], &[])
+        .await;
+
+    harness.refresh_blockhash().await;
+    harness.process(&listing, &[&late_issuer, &late_mint]).await;
+    harness.process(&[buy], &[&buyer]).await;
+
+    let buyer_state: BuyerState = harness
+        .unpack(get_buyer_state_address(
+            &mint.pubkey(),
+            &buyer.pubkey(),
+            &program_id,
+        ))
+        .await;
+    // the resting ask took its tokens out of the position
+    assert_eq!(buyer_state.current_holding_in_tokens, 19_000);
+}
@@ -0,0 +1,549 @@
+mod common;
+
+use common::{assert_custom_error, Harness};
+use decense::{
+    error::DecenseError,
+    instruction::{
+        get_buyer_state_address, get_listing_pda_address, get_platform_state_address,
+        get_user_state_address, get_vault_address, migrate_listing, migrate_platform_state,
+        migrate_position, DecenseInstruction,
+    },
+    pricing::CurveKind,
+    state::{AccountType, BuyerState, PlatformState, UserState, Versioned},
+};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_program,
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+
+// drops the version byte, the account type and the reserved space
+fn legacy<T: Versioned>(data: &[u8]) -> Vec<u8> {
+    let mut legacy = vec![data[0]];
+    legacy.extend_from_slice(&data[3..T::LEGACY_LEN + 2]);
+    legacy
+}
+
+// what migrating a legacy account yields: fields added since then start out zeroed
+fn since_legacy<T: Versioned>(data: &[u8]) -> Vec<u8> {
+    let mut migrated = data.to_vec();
+    migrated[T::LEGACY_LEN + 2..].fill(0);
+    migrated
+}
+
+// version 1 had no account type yet
+fn version_1(data: &[u8]) -> Vec<u8> {
+    let mut version_1 = vec![data[0], 1];
+    version_1.extend_from_slice(&data[3..]);
+    version_1.push(0);
+    version_1
+}
+
+fn packed<T: Versioned>(state: T) -> Vec<u8> {
+    let mut data = vec![0; T::LEN];
+    T::pack(state, &mut data).unwrap();
+    data
+}
+
+fn assert_round_trips<T: Versioned + PartialEq + std::fmt::Debug + Copy>(
+    state: T,
+    program_id: &Pubkey,
+) {
+    let data = packed(state);
+    assert_eq!(data[1], T::VERSION);
+    assert_eq!(T::unpack(&data).unwrap(), state);
+
+    let legacy = legacy::<T>(&data);
+    assert_eq!(legacy.len(), T::LEGACY_LEN);
+    assert_eq!(
+        T::migrate(&legacy, program_id).unwrap(),
+        since_legacy::<T>(&data)
+    );
+
+    let version_1 = version_1(&data);
+    assert_eq!(version_1.len(), T::LEN);
+    assert_eq!(T::unpack(&version_1), Err(ProgramError::InvalidAccountData));
+    assert_eq!(T::migrate(&version_1, program_id).unwrap(), data);
+
+    // the current layout has nothing to migrate
+    assert_eq!(
+        T::migrate(&data, program_id),
+        Err(DecenseError::UnknownAccountLayout.into())
+    );
+
+    // a layout newer than the program is refused
+    let mut newer = data;
+    newer[1] = T::VERSION + 1;
+    assert_eq!(T::unpack(&newer), Err(ProgramError::InvalidAccountData));
+}
+
+// the layout the first deployment created, a prefix of the legacy one
+fn baseline<T: Versioned>(data: &[u8]) -> Vec<u8> {
+    legacy::<T>(data)[..T::BASELINE_LEN].to_vec()
+}
+
+fn platform_state() -> PlatformState {
+    PlatformState {
+        is_initialized: true,
+        platform_treasury_sol_wallet: Pubkey::new_unique(),
+        listing_fee: 1_000_000_000,
+        buy_fee_bps: 100,
+        sell_fee_bps: 200,
+        fee_recipient: Pubkey::new_unique(),
+        admin: Pubkey::new_unique(),
+        pending_admin: Pubkey::default(),
+        paused: true,
+    }
+}
+
+fn user_state(program_id: &Pubkey) -> UserState {
+    UserState {
+        is_initialized: true,
+        user: Pubkey::new_unique(),
+        market_valuation: 1_000,
+        supply: 101,
+        user_token_mint: Pubkey::new_unique(),
+        user_ata: Pubkey::new_unique(),
+        user_treasury_percentage: 50,
+        liquidate_percentage: 25,
+        pda_ata: Pubkey::new_unique(),
+        cmp: 10_000_000_000,
+        holders: 3,
+        pricing_curve: 2,
+        curve_parameter: 7,
+        base_price: 9_000_000_000,
+        initial_pool: 500_000,
+        quote_mint: Pubkey::new_unique(),
+        dividend_count: 2,
+        proposal_count: 1,
+        // migrated listings point at the program's platform state
+        platform_state: get_platform_state_address(program_id),
+        halt_reason: 3,
+        halted_until: -5,
+        breaker_max_move_bps: 500,
+        breaker_window: 3_600,
+        breaker_cooldown: 600,
+        breaker_window_start: 1_700_000_000,
+        breaker_reference_price: 9_000_000_000,
+        split_count: 1,
+        issued_supply: 0,
+    }
+}
+
+#[test]
+fn platform_state_round_trips_both_layouts() {
+    assert_round_trips(platform_state(), &Pubkey::new_unique());
+}
+
+#[test]
+fn user_state_round_trips_both_layouts() {
+    let program_id = Pubkey::new_unique();
+    assert_round_trips(user_state(&program_id), &program_id);
+}
+
+#[test]
+fn buyer_state_round_trips_both_layouts() {
+    assert_round_trips(
+        BuyerState {
+            is_initialized: true,
+            buyer: Pubkey::new_unique(),
+            current_holding_in_tokens: 10_000,
+            mint: Pubkey::new_unique(),
+            cost_basis: 1_010_000_000,
+            realized_pnl: -5_000,
+            split_count: 0,
+        },
+        &Pubkey::new_unique(),
+    );
+}
+
+#[test]
+fn platform_state_migrates_from_the_baseline_layout() {
+    let state = platform_state();
+    let baseline = baseline::<PlatformState>(&packed(state));
+    assert_eq!(baseline.len(), 33);
+
+    let migrated = PlatformState::migrate(&baseline, &Pubkey::new_unique()).unwrap();
+
+    // the baseline's fixed listing fee, no trading fee and no admin yet
+    assert_eq!(
+        PlatformState::unpack(&migrated).unwrap(),
+        PlatformState {
+            is_initialized: true,
+            platform_treasury_sol_wallet: state.platform_treasury_sol_wallet,
+            listing_fee: 1_000_000_000,
+            buy_fee_bps: 0,
+            sell_fee_bps: 0,
+            fee_recipient: state.platform_treasury_sol_wallet,
+            admin: Pubkey::default(),
+            pending_admin: Pubkey::default(),
+            paused: false,
+        }
+    );
+}
+
+#[test]
+fn user_state_migrates_from_the_baseline_layout() {
+    let program_id = Pubkey::new_unique();
+    let state = user_state(&program_id);
+    let baseline = baseline::<UserState>(&packed(state));
+    assert_eq!(baseline.len(), 163);
+
+    let migrated = UserState::unpack(&UserState::migrate(&baseline, &program_id).unwrap()).unwrap();
+
+    // the baseline fields carry over
+    assert_eq!(migrated.user, state.user);
+    assert_eq!(migrated.market_valuation, state.market_valuation);
+    assert_eq!(migrated.supply, state.supply);
+    assert_eq!(migrated.user_token_mint, state.user_token_mint);
+    assert_eq!(migrated.user_ata, state.user_ata);
+    assert_eq!(migrated.user_treasury_percentage, 50);
+    assert_eq!(migrated.liquidate_percentage, 25);
+    assert_eq!(migrated.pda_ata, state.pda_ata);
+    assert_eq!(migrated.cmp, state.cmp);
+    assert_eq!(migrated.holders, 3);
+
+    // trading continues at the last price on a flat curve over the baseline's half supply pool
+    let curve = migrated.pricing_curve().unwrap();
+    assert_eq!(curve.kind, CurveKind::Linear);
+    assert_eq!(curve.parameter, 0);
+    assert_eq!(curve.base_price, state.cmp);
+    assert_eq!(curve.initial_pool, 500_000);
+    assert_eq!(curve.spot_price(123_456), Some(state.cmp));
+
+    assert_eq!(migrated.quote_mint, Pubkey::default());
+    assert_eq!(
+        migrated.platform_state,
+        get_platform_state_address(&program_id)
+    );
+    assert_eq!(migrated.dividend_count, 0);
+    assert_eq!(migrated.halt_reason, 0);
+    assert_eq!(migrated.breaker_max_move_bps, 0);
+    assert_eq!(migrated.split_count, 0);
+}
+
+#[test]
+fn migration_refuses_uninitialized_legacy_data() {
+    let mut state = platform_state();
+    state.is_initialized = false;
+
+    let legacy = legacy::<PlatformState>(&packed(state));
+    assert_eq!(
+        PlatformState::migrate(&legacy, &Pubkey::new_unique()),
+        Err(ProgramError::UninitializedAccount)
+    );
+    assert_eq!(
+        PlatformState::migrate(&legacy[1..], &Pubkey::new_unique()),
+        Err(DecenseError::UnknownAccountLayout.into())
+    );
+}
+
+#[test]
+fn unpack_refuses_another_account_type() {
+    let mut data = packed(platform_state());
+    data[2] = AccountType::BuyerState as u8;
+
+    assert_eq!(
+        PlatformState::unpack(&data),
+        Err(DecenseError::AccountTypeMismatch.into())
+    );
+}
+
+// stores data at address the way an older program left it
+fn set_program_account(harness: &mut Harness, address: &Pubkey, data: Vec<u8>, owner: &Pubkey) {
+    harness.context.set_account(
+        address,
+        &AccountSharedData::from(Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: *owner,
+            executable: false,
+            rent_epoch: 0,
+        }),
+    );
+}
+
+fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Vec<u8> {
+    let mut data = vec![0; TokenAccount::LEN];
+    TokenAccount::pack(
+        TokenAccount {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            state: AccountState::Initialized,
+            ..TokenAccount::default()
+        },
+        &mut data,
+    )
+    .unwrap();
+    data
+}
+
+#[tokio::test]
+async fn baseline_accounts_are_migrated() {
+    let issuer = Keypair::new();
+    let buyer = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    let mut harness = Harness::start(&[issuer.pubkey(), buyer.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+    let admin = harness.payer.pubkey();
+
+    // a listing of 100 tokens at 1 sol each as the first deployment left it after one buy: the
+    // issuer holds the mint and freeze authority, half the supply went to a token account of the
+    // pda derived from the issuer's wallet and every state sits at an address derived from the
+    // wallet that created it
+    let baseline_platform_state =
+        Pubkey::create_with_seed(&admin, "DECENSE PLATFORM", &program_id).unwrap();
+    let baseline_user_state =
+        Pubkey::create_with_seed(&issuer.pubkey(), "DECENSE USER", &program_id).unwrap();
+    let baseline_buyer_state =
+        Pubkey::create_with_seed(&buyer.pubkey(), "DECENSE BUYER", &program_id).unwrap();
+    let (baseline_pda, _) = Pubkey::find_program_address(&[issuer.pubkey().as_ref()], &program_id);
+    let baseline_pda_ata = get_associated_token_address(&baseline_pda, &mint);
+    let issuer_ata = get_associated_token_address(&issuer.pubkey(), &mint);
+    let buyer_ata = get_associated_token_address(&buyer.pubkey(), &mint);
+
+    let mut mint_data = vec![0; Mint::LEN];
+    Mint::pack(
+        Mint {
+            mint_authority: COption::Some(issuer.pubkey()),
+            supply: 1_000_000,
+            decimals: 4,
+            is_initialized: true,
+            freeze_authority: COption::Some(issuer.pubkey()),
+        },
+        &mut mint_data,
+    )
+    .unwrap();
+    set_program_account(&mut harness, &mint, mint_data, &spl_token::id());
+
+    for (address, owner, amount) in [
+        (issuer_ata, issuer.pubkey(), 500_000),
+        (baseline_pda_ata, baseline_pda, 490_000),
+        (buyer_ata, buyer.pubkey(), 10_000),
+    ] {
+        let data = token_account(&mint, &owner, amount);
+        set_program_account(&mut harness, &address, data, &spl_token::id());
+    }
+
+    let mut platform_data = vec![1];
+    platform_data.extend_from_slice(treasury.as_ref());
+
+    let cmp: u64 = 1_000_000_000;
+    let mut user_data = vec![1];
+    user_data.extend_from_slice(issuer.pubkey().as_ref());
+    user_data.extend_from_slice(&100u64.to_le_bytes());
+    user_data.extend_from_slice(&100u64.to_le_bytes());
+    user_data.extend_from_slice(mint.as_ref());
+    user_data.extend_from_slice(issuer_ata.as_ref());
+    user_data.extend_from_slice(&[50, 50]);
+    user_data.extend_from_slice(baseline_pda_ata.as_ref());
+    user_data.extend_from_slice(&cmp.to_le_bytes());
+    user_data.extend_from_slice(&1u64.to_le_bytes());
+
+    let mut buyer_data = vec![1];
+    buyer_data.extend_from_slice(buyer.pubkey().as_ref());
+    buyer_data.extend_from_slice(&10_000u64.to_le_bytes());
+
+    for (address, data) in [
+        (baseline_platform_state, platform_data),
+        (baseline_user_state, user_data),
+        (baseline_buyer_state, buyer_data),
+    ] {
+        set_program_account(&mut harness, &address, data, &program_id);
+    }
+
+    harness
+        .process(
+            &[
+                migrate_platform_state(&program_id, &admin),
+                migrate_listing(&program_id, &issuer.pubkey(), &mint),
+                migrate_position(&program_id, &buyer.pubkey(), &mint),
+            ],
+            &[&issuer, &buyer],
+        )
+        .await;
+
+    // the old accounts are closed
+    for address in [
+        baseline_platform_state,
+        baseline_user_state,
+        baseline_buyer_state,
+        baseline_pda_ata,
+    ] {
+        assert!(harness
+            .banks_client
+            .get_account(address)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    // the platform moved to the program's platform state and its creator is the admin
+    let platform_state = get_platform_state_address(&program_id);
+    let migrated_platform_state: PlatformState = harness.unpack(platform_state).await;
+    assert_eq!(
+        migrated_platform_state,
+        PlatformState {
+            is_initialized: true,
+            platform_treasury_sol_wallet: treasury,
+            listing_fee: 1_000_000_000,
+            buy_fee_bps: 0,
+            sell_fee_bps: 0,
+            fee_recipient: treasury,
+            admin,
+            pending_admin: Pubkey::default(),
+            paused: false,
+        }
+    );
+
+    // the listing moved to the pdas of its mint, which now holds the pool and the mint authority
+    let pda = get_listing_pda_address(&mint, &program_id);
+    let pda_ata = get_associated_token_address(&pda, &mint);
+    let user_state = get_user_state_address(&mint, &program_id);
+    let migrated_user_state: UserState = harness.unpack(user_state).await;
+    assert_eq!(
+        migrated_user_state,
+        UserState {
+            is_initialized: true,
+            user: issuer.pubkey(),
+            market_valuation: 100,
+            supply: 100,
+            user_token_mint: mint,
+            user_ata: issuer_ata,
+            user_treasury_percentage: 50,
+            liquidate_percentage: 50,
+            pda_ata,
+            cmp,
+            holders: 1,
+            pricing_curve: CurveKind::Linear as u8,
+            curve_parameter: 0,
+            base_price: cmp,
+            initial_pool: 500_000,
+            quote_mint: Pubkey::default(),
+            dividend_count: 0,
+            proposal_count: 0,
+            platform_state,
+            halt_reason: 0,
+            halted_until: 0,
+            breaker_max_move_bps: 0,
+            breaker_window: 0,
+            breaker_cooldown: 0,
+            breaker_window_start: 0,
+            breaker_reference_price: 0,
+            split_count: 0,
+            issued_supply: 0,
+        }
+    );
+    assert_eq!(harness.token_balance(pda_ata).await, 490_000);
+
+    let migrated_mint: Mint = harness.unpack(mint).await;
+    assert_eq!(migrated_mint.mint_authority, COption::Some(pda));
+    assert_eq!(migrated_mint.freeze_authority, COption::None);
+
+    // the vault starts out rent exempt and empty
+    let vault = get_vault_address(&mint, &program_id);
+    assert_eq!(
+        harness.lamports(vault).await,
+        Rent::default().minimum_balance(0)
+    );
+
+    // the issuer's position holds what it kept
+    let issuer_state: BuyerState = harness
+        .unpack(get_buyer_state_address(
+            &mint,
+            &issuer.pubkey(),
+            &program_id,
+        ))
+        .await;
+    assert_eq!(issuer_state.current_holding_in_tokens, 500_000);
+
+    // the position moved to the listing with its holding and no known cost
+    let buyer_state = get_buyer_state_address(&mint, &buyer.pubkey(), &program_id);
+    let migrated_buyer_state: BuyerState = harness.unpack(buyer_state).await;
+    assert_eq!(
+        migrated_buyer_state,
+        BuyerState {
+            is_initialized: true,
+            buyer: buyer.pubkey(),
+            current_holding_in_tokens: 10_000,
+            mint,
+            cost_basis: 0,
+            realized_pnl: 0,
+            split_count: 0,
+        }
+    );
+
+    // current accounts have nothing to migrate
+    assert_custom_error(
+        harness
+            .try_process(
+                &[Instruction {
+                    program_id,
+                    accounts: vec![
+                        AccountMeta::new(admin, true),
+                        AccountMeta::new(buyer_state, false),
+                        AccountMeta::new_readonly(system_program::id(), false),
+                    ],
+                    data: DecenseInstruction::MigrateAccount.pack(),
+                }],
+                &[],
+            )
+            .await,
+        DecenseError::UnknownAccountLayout,
+    );
+
+    // the migrated listing keeps trading at its last price
+    let buyer_before = harness.lamports(buyer.pubkey()).await;
+    harness
+        .buy(&buyer, &issuer.pubkey(), &mint, &treasury, 10_000)
+        .await;
+    assert_eq!(harness.lamports(buyer.pubkey()).await, buyer_before - cmp);
+    assert_eq!(harness.token_balance(pda_ata).await, 480_000);
+
+    let buyer_state: BuyerState = harness.unpack(buyer_state).await;
+    assert_eq!(buyer_state.current_holding_in_tokens, 20_000);
+    assert_eq!(buyer_state.cost_basis, cmp);
+}
+
+#[tokio::test]
+async fn listings_after_the_baseline_can_not_move() {
+    let issuer = Keypair::new();
+
+    let mut harness = Harness::start(&[issuer.pubkey()]).await;
+    let program_id = harness.program_id;
+
+    // their vault, vesting and order book live under pdas of the issuer's wallet
+    let state = user_state(&program_id);
+    let legacy_user_state =
+        Pubkey::create_with_seed(&issuer.pubkey(), "DECENSE USER", &program_id).unwrap();
+    let data = legacy::<UserState>(&packed(UserState {
+        user: issuer.pubkey(),
+        ..state
+    }));
+    set_program_account(&mut harness, &legacy_user_state, data, &program_id);
+
+    assert_custom_error(
+        harness
+            .try_process(
+                &[migrate_listing(
+                    &program_id,
+                    &issuer.pubkey(),
+                    &state.user_token_mint,
+                )],
+                &[&issuer],
+            )
+            .await,
+        DecenseError::UnmigratableListing,
+    );
+}
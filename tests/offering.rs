@@ -0,0 +1,454 @@
+mod common;
+
+use common::{assert_custom_error, Harness};
+use decense::{
+    error::DecenseError,
+    instruction::{
+        claim_offering, create_offering, exchange, finalize_offering, get_buyer_state_address,
+        get_listing_pda_address, get_offering_address, get_subscription_address,
+        get_user_state_address, pause, resume_trading, subscribe, unpause,
+    },
+    state::{BuyerState, HaltReason, Offering, OfferingStatus, Subscription, UserState},
+};
+use solana_sdk::{
+    clock::Clock,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use spl_associated_token_account::get_associated_token_address;
+
+// 0.1 SOL per token
+const PRICE: u64 = 100_000_000;
+
+#[tokio::test]
+async fn successful_offering_pays_the_issuer_and_opens_trading() {
+    let issuer = Keypair::new();
+    let first = Keypair::new();
+    let second = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness =
+        Harness::start(&[issuer.pubkey(), first.pubkey(), second.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+
+    let platform_state = harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+
+    let now = harness
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap()
+        .unix_timestamp as u64;
+
+    let offering_ix = |hard_cap| {
+        create_offering(
+            &program_id,
+            &issuer.pubkey(),
+            &mint.pubkey(),
+            &platform_state,
+            PRICE,
+            now,
+            now + 1_000,
+            1_500_000_000,
+            1_000_000_000,
+            hard_cap,
+        )
+    };
+
+    // the pool only holds 50 tokens
+    assert_custom_error(
+        harness
+            .try_process(&[offering_ix(5_100_000_000)], &[&issuer])
+            .await,
+        DecenseError::InvalidOffering,
+    );
+
+    harness
+        .process(&[offering_ix(2_000_000_000)], &[&issuer])
+        .await;
+
+    let user_state_address = get_user_state_address(&mint.pubkey(), &program_id);
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.halt_reason(), Some(HaltReason::Offering));
+
+    // no secondary trading while the offering runs
+    assert_custom_error(
+        harness
+            .try_process(
+                &[exchange(
+                    &program_id,
+                    &first.pubkey(),
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    &platform_state,
+                    &treasury,
+                    None,
+                    10_000,
+                    u64::MAX,
+                    0,
+                )],
+                &[&first],
+            )
+            .await,
+        DecenseError::TradingHalted,
+    );
+    assert_custom_error(
+        harness
+            .try_process(
+                &[resume_trading(
+                    &program_id,
+                    &issuer.pubkey(),
+                    &platform_state,
+                    &mint.pubkey(),
+                )],
+                &[&issuer],
+            )
+            .await,
+        DecenseError::OfferingInProgress,
+    );
+
+    harness
+        .process(
+            &[subscribe(
+                &program_id,
+                &first.pubkey(),
+                &mint.pubkey(),
+                &platform_state,
+                100_000,
+            )],
+            &[&first],
+        )
+        .await;
+
+    assert_custom_error(
+        harness
+            .try_process(
+                &[subscribe(
+                    &program_id,
+                    &second.pubkey(),
+                    &mint.pubkey(),
+                    &platform_state,
+                    150_000,
+                )],
+                &[&second],
+            )
+            .await,
+        DecenseError::HardCapExceeded,
+    );
+    assert_custom_error(
+        harness
+            .try_process(
+                &[subscribe(
+                    &program_id,
+                    &first.pubkey(),
+                    &mint.pubkey(),
+                    &platform_state,
+                    60_000,
+                )],
+                &[&first],
+            )
+            .await,
+        DecenseError::WalletLimitExceeded,
+    );
+
+    harness
+        .process(
+            &[subscribe(
+                &program_id,
+                &second.pubkey(),
+                &mint.pubkey(),
+                &platform_state,
+                50_000,
+            )],
+            &[&second],
+        )
+        .await;
+
+    let offering_address = get_offering_address(&mint.pubkey(), &program_id);
+    let offering: Offering = harness.unpack(offering_address).await;
+    assert_eq!(offering.raised, 1_500_000_000);
+    assert_eq!(offering.sold, 150_000);
+
+    let finalize = finalize_offering(&program_id, &issuer.pubkey(), &mint.pubkey());
+    assert_custom_error(
+        harness
+            .try_process(std::slice::from_ref(&finalize), &[])
+            .await,
+        DecenseError::OfferingInProgress,
+    );
+
+    harness.advance_clock(1_000).await;
+    assert_custom_error(
+        harness
+            .try_process(
+                &[subscribe(
+                    &program_id,
+                    &second.pubkey(),
+                    &mint.pubkey(),
+                    &platform_state,
+                    10_000,
+                )],
+                &[&second],
+            )
+            .await,
+        DecenseError::OfferingNotOpen,
+    );
+
+    let issuer_before = harness.lamports(issuer.pubkey()).await;
+    harness.refresh_blockhash().await;
+    harness.process(&[finalize], &[]).await;
+
+    assert_eq!(
+        harness.lamports(issuer.pubkey()).await,
+        issuer_before + 1_500_000_000
+    );
+
+    let offering: Offering = harness.unpack(offering_address).await;
+    assert_eq!(offering.status, OfferingStatus::Succeeded);
+
+    let pda_ata = get_associated_token_address(
+        &get_listing_pda_address(&mint.pubkey(), &program_id),
+        &mint.pubkey(),
+    );
+    assert_eq!(harness.token_balance(pda_ata).await, 350_000);
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.halt_reason, 0);
+    assert_eq!(
+        Some(user_state.cmp),
+        user_state.pricing_curve().unwrap().spot_price(350_000)
+    );
+
+    let claim = claim_offering(&program_id, &first.pubkey(), &mint.pubkey());
+    harness
+        .process(std::slice::from_ref(&claim), &[&first])
+        .await;
+
+    let first_ata = get_associated_token_address(&first.pubkey(), &mint.pubkey());
+    assert_eq!(harness.token_balance(first_ata).await, 100_000);
+
+    let first_state: BuyerState = harness
+        .unpack(get_buyer_state_address(
+            &mint.pubkey(),
+            &first.pubkey(),
+            &program_id,
+        ))
+        .await;
+    assert_eq!(first_state.current_holding_in_tokens, 100_000);
+    assert_eq!(first_state.cost_basis, 1_000_000_000);
+
+    let subscription_address =
+        get_subscription_address(&offering_address, &first.pubkey(), &program_id);
+    assert!(harness
+        .banks_client
+        .get_account(subscription_address)
+        .await
+        .unwrap()
+        .is_none());
+
+    // secondary trading is open and prices off the pool the offering left
+    let cost = user_state
+        .pricing_curve()
+        .unwrap()
+        .quote_buy(350_000, 10_000)
+        .unwrap();
+    let issuer_before = harness.lamports(issuer.pubkey()).await;
+    harness
+        .process(
+            &[exchange(
+                &program_id,
+                &second.pubkey(),
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                &platform_state,
+                &treasury,
+                None,
+                10_000,
+                cost,
+                10_000,
+            )],
+            &[&second],
+        )
+        .await;
+
+    assert_eq!(
+        harness.lamports(issuer.pubkey()).await - issuer_before,
+        cost - cost / 2
+    );
+    assert_eq!(harness.token_balance(pda_ata).await, 340_000);
+
+    let second_state: BuyerState = harness
+        .unpack(get_buyer_state_address(
+            &mint.pubkey(),
+            &second.pubkey(),
+            &program_id,
+        ))
+        .await;
+    assert_eq!(second_state.cost_basis, cost);
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(
+        Some(user_state.cmp),
+        user_state.pricing_curve().unwrap().spot_price(340_000)
+    );
+    harness
+        .process(
+            &[claim_offering(
+                &program_id,
+                &second.pubkey(),
+                &mint.pubkey(),
+            )],
+            &[&second],
+        )
+        .await;
+
+    let second_ata = get_associated_token_address(&second.pubkey(), &mint.pubkey());
+    assert_eq!(harness.token_balance(second_ata).await, 60_000);
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.holders, 2);
+}
+
+#[tokio::test]
+async fn missed_soft_cap_refunds_subscribers() {
+    let issuer = Keypair::new();
+    let subscriber = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness = Harness::start(&[issuer.pubkey(), subscriber.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+    let rent = harness.banks_client.get_rent().await.unwrap();
+
+    let platform_state = harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+
+    let now = harness
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap()
+        .unix_timestamp as u64;
+
+    harness
+        .process(
+            &[create_offering(
+                &program_id,
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                &platform_state,
+                PRICE,
+                now,
+                now + 1_000,
+                1_500_000_000,
+                1_000_000_000,
+                2_000_000_000,
+            )],
+            &[&issuer],
+        )
+        .await;
+
+    let subscribe_ix = subscribe(
+        &program_id,
+        &subscriber.pubkey(),
+        &mint.pubkey(),
+        &platform_state,
+        50_000,
+    );
+
+    // the offering stops with the rest of the platform
+    let admin = harness.payer.pubkey();
+    harness
+        .process(&[pause(&program_id, &admin, &platform_state)], &[])
+        .await;
+    assert_custom_error(
+        harness
+            .try_process(std::slice::from_ref(&subscribe_ix), &[&subscriber])
+            .await,
+        DecenseError::PlatformPaused,
+    );
+    harness
+        .process(&[unpause(&program_id, &admin, &platform_state)], &[])
+        .await;
+
+    let subscriber_before = harness.lamports(subscriber.pubkey()).await;
+    harness.refresh_blockhash().await;
+    harness.process(&[subscribe_ix], &[&subscriber]).await;
+
+    let offering_address = get_offering_address(&mint.pubkey(), &program_id);
+    let subscription: Subscription = harness
+        .unpack(get_subscription_address(
+            &offering_address,
+            &subscriber.pubkey(),
+            &program_id,
+        ))
+        .await;
+    assert_eq!(subscription.quantity, 50_000);
+    assert_eq!(subscription.paid, 500_000_000);
+
+    assert_eq!(
+        harness.lamports(subscriber.pubkey()).await,
+        subscriber_before - 500_000_000 - rent.minimum_balance(Subscription::LEN)
+    );
+
+    harness.advance_clock(1_000).await;
+
+    let issuer_before = harness.lamports(issuer.pubkey()).await;
+    harness
+        .process(
+            &[finalize_offering(
+                &program_id,
+                &issuer.pubkey(),
+                &mint.pubkey(),
+            )],
+            &[],
+        )
+        .await;
+    assert_eq!(harness.lamports(issuer.pubkey()).await, issuer_before);
+
+    let offering: Offering = harness.unpack(offering_address).await;
+    assert_eq!(offering.status, OfferingStatus::Failed);
+
+    // nothing was sold, so the pool trades again right away
+    let user_state_address = get_user_state_address(&mint.pubkey(), &program_id);
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.halt_reason, 0);
+
+    harness
+        .process(
+            &[claim_offering(
+                &program_id,
+                &subscriber.pubkey(),
+                &mint.pubkey(),
+            )],
+            &[&subscriber],
+        )
+        .await;
+
+    assert_eq!(
+        harness.lamports(subscriber.pubkey()).await,
+        subscriber_before
+    );
+
+    let subscriber_ata = get_associated_token_address(&subscriber.pubkey(), &mint.pubkey());
+    assert!(harness
+        .banks_client
+        .get_account(subscriber_ata)
+        .await
+        .unwrap()
+        .is_none());
+
+    harness
+        .buy(
+            &subscriber,
+            &issuer.pubkey(),
+            &mint.pubkey(),
+            &treasury,
+            10_000,
+        )
+        .await;
+    assert_eq!(harness.token_balance(subscriber_ata).await, 10_000);
+}
@@ -0,0 +1,366 @@
+mod common;
+
+use common::{assert_custom_error, Harness};
+use decense::{
+    error::DecenseError,
+    instruction::{
+        cancel_order, get_bid_vault_address, get_buyer_state_address, get_order_book_address,
+        get_user_state_address, match_orders, place_order, update_platform_config,
+    },
+    state::{BuyerState, Order, OrderBook, UserState, MAX_ORDERS_PER_SIDE},
+};
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
+use solana_sdk::signature::{Keypair, Signer};
+use spl_associated_token_account::get_associated_token_address;
+
+fn order(price: u64, sequence: u64) -> Order {
+    Order {
+        owner: Pubkey::new_unique(),
+        price,
+        quantity: 10_000,
+        sequence,
+        cost_basis: 0,
+        fee_bps: 0,
+    }
+}
+
+fn empty_book() -> OrderBook {
+    OrderBook {
+        is_initialized: true,
+        user_token_mint: Pubkey::new_unique(),
+        next_sequence: 0,
+        bids: vec![],
+        asks: vec![],
+    }
+}
+
+#[test]
+fn bids_are_sorted_by_highest_price_then_sequence() {
+    let mut book = empty_book();
+    assert!(book.insert_bid(order(100, 0)));
+    assert!(book.insert_bid(order(200, 1)));
+    assert!(book.insert_bid(order(100, 2)));
+    assert!(book.insert_bid(order(150, 3)));
+
+    let sequences: Vec<u64> = book.bids.iter().map(|order| order.sequence).collect();
+    assert_eq!(sequences, vec![1, 3, 0, 2]);
+}
+
+#[test]
+fn asks_are_sorted_by_lowest_price_then_sequence() {
+    let mut book = empty_book();
+    assert!(book.insert_ask(order(100, 0)));
+    assert!(book.insert_ask(order(50, 1)));
+    assert!(book.insert_ask(order(100, 2)));
+    assert!(book.insert_ask(order(75, 3)));
+
+    let sequences: Vec<u64> = book.asks.iter().map(|order| order.sequence).collect();
+    assert_eq!(sequences, vec![1, 3, 0, 2]);
+}
+
+#[test]
+fn full_side_rejects_new_orders() {
+    let mut book = empty_book();
+    for sequence in 0..MAX_ORDERS_PER_SIDE as u64 {
+        assert!(book.insert_bid(order(100, sequence)));
+    }
+
+    assert!(!book.insert_bid(order(100, MAX_ORDERS_PER_SIDE as u64)));
+    assert!(book.insert_ask(order(100, MAX_ORDERS_PER_SIDE as u64)));
+}
+
+#[test]
+fn order_book_round_trips_through_pack() {
+    let mut book = empty_book();
+    book.next_sequence = 3;
+    book.insert_bid(order(100, 0));
+    book.insert_ask(order(120, 1));
+    book.insert_ask(order(110, 2));
+
+    let mut data = vec![0; OrderBook::LEN];
+    OrderBook::pack(book.clone(), &mut data).unwrap();
+
+    assert_eq!(OrderBook::unpack(&data).unwrap(), book);
+}
+
+#[test]
+fn fills_release_a_share_of_the_ask_cost_basis() {
+    let mut ask = order(100, 0);
+    ask.cost_basis = 999;
+
+    assert_eq!(ask.fill(4_000), Some(399));
+    assert_eq!((ask.quantity, ask.cost_basis), (6_000, 600));
+    assert_eq!(ask.fill(6_000), Some(600));
+    assert_eq!((ask.quantity, ask.cost_basis), (0, 0));
+    assert_eq!(ask.fill(1), None);
+}
+
+#[tokio::test]
+async fn cancelled_orders_return_to_their_owners() {
+    let issuer = Keypair::new();
+    let seller = Keypair::new();
+    let bidder = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness =
+        Harness::start(&[issuer.pubkey(), seller.pubkey(), bidder.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+    let rent = harness.banks_client.get_rent().await.unwrap();
+
+    let platform_state = harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+    harness
+        .buy(&seller, &issuer.pubkey(), &mint.pubkey(), &treasury, 20_000)
+        .await;
+
+    let seller_state_address =
+        get_buyer_state_address(&mint.pubkey(), &seller.pubkey(), &program_id);
+    let bidder_state_address =
+        get_buyer_state_address(&mint.pubkey(), &bidder.pubkey(), &program_id);
+    let order_book_address = get_order_book_address(&mint.pubkey(), &program_id);
+    let bid_vault = get_bid_vault_address(&mint.pubkey(), &program_id);
+    let seller_ata = get_associated_token_address(&seller.pubkey(), &mint.pubkey());
+
+    let position: BuyerState = harness.unpack(seller_state_address).await;
+    let price = harness
+        .unpack::<UserState>(get_user_state_address(&mint.pubkey(), &program_id))
+        .await
+        .cmp;
+
+    let order = |owner: &Keypair, side, price, quantity| {
+        place_order(
+            &program_id,
+            &owner.pubkey(),
+            &issuer.pubkey(),
+            &mint.pubkey(),
+            &platform_state,
+            side,
+            price,
+            quantity,
+        )
+    };
+
+    // only tokens the position accounts for can be offered
+    assert_custom_error(
+        harness
+            .try_process(&[order(&seller, 1, price, 20_001)], &[&seller])
+            .await,
+        DecenseError::PositionExceeded,
+    );
+
+    harness
+        .process(
+            &[
+                order(&seller, 1, price * 2, 10_000),
+                order(&bidder, 0, price / 2, 5_000),
+            ],
+            &[&seller, &bidder],
+        )
+        .await;
+
+    let order_book: OrderBook = harness.unpack(order_book_address).await;
+    let escrowed: BuyerState = harness.unpack(seller_state_address).await;
+    assert_eq!(escrowed.current_holding_in_tokens, 10_000);
+    assert_eq!(
+        escrowed.cost_basis + order_book.asks[0].cost_basis,
+        position.cost_basis
+    );
+    assert_eq!(harness.token_balance(seller_ata).await, 10_000);
+
+    // the bidder's position is opened with the bid
+    let bidder_state: BuyerState = harness.unpack(bidder_state_address).await;
+    assert_eq!(bidder_state.buyer, bidder.pubkey());
+    assert_eq!(bidder_state.current_holding_in_tokens, 0);
+
+    let bidder_before = harness.lamports(bidder.pubkey()).await;
+    harness
+        .process(
+            &[
+                cancel_order(
+                    &program_id,
+                    &seller.pubkey(),
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    1,
+                    order_book.asks[0].sequence,
+                ),
+                cancel_order(
+                    &program_id,
+                    &bidder.pubkey(),
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    0,
+                    order_book.bids[0].sequence,
+                ),
+            ],
+            &[&seller, &bidder],
+        )
+        .await;
+
+    let order_book: OrderBook = harness.unpack(order_book_address).await;
+    assert!(order_book.bids.is_empty() && order_book.asks.is_empty());
+
+    let restored: BuyerState = harness.unpack(seller_state_address).await;
+    assert_eq!(restored, position);
+    assert_eq!(harness.token_balance(seller_ata).await, 20_000);
+
+    assert_eq!(harness.lamports(bid_vault).await, rent.minimum_balance(0));
+    assert!(harness.lamports(bidder.pubkey()).await > bidder_before);
+}
+
+#[tokio::test]
+async fn matched_orders_update_positions_fees_and_holders() {
+    let issuer = Keypair::new();
+    let seller = Keypair::new();
+    let bidder = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness =
+        Harness::start(&[issuer.pubkey(), seller.pubkey(), bidder.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+    let admin = harness.payer.pubkey();
+    let rent = harness.banks_client.get_rent().await.unwrap();
+
+    let platform_state = harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+    harness
+        .buy(&seller, &issuer.pubkey(), &mint.pubkey(), &treasury, 20_000)
+        .await;
+
+    // one percent on either side of a trade
+    harness
+        .process(
+            &[update_platform_config(
+                &program_id,
+                &admin,
+                &platform_state,
+                &treasury,
+                1_000_000_000,
+                100,
+                100,
+            )],
+            &[],
+        )
+        .await;
+
+    let user_state_address = get_user_state_address(&mint.pubkey(), &program_id);
+    let seller_state_address =
+        get_buyer_state_address(&mint.pubkey(), &seller.pubkey(), &program_id);
+    let bidder_state_address =
+        get_buyer_state_address(&mint.pubkey(), &bidder.pubkey(), &program_id);
+    let order_book_address = get_order_book_address(&mint.pubkey(), &program_id);
+    let bid_vault = get_bid_vault_address(&mint.pubkey(), &program_id);
+    let seller_ata = get_associated_token_address(&seller.pubkey(), &mint.pubkey());
+    let bidder_ata = get_associated_token_address(&bidder.pubkey(), &mint.pubkey());
+
+    let listing: UserState = harness.unpack(user_state_address).await;
+    let price = listing.cmp;
+
+    let order = |owner: &Keypair, side, price, quantity| {
+        place_order(
+            &program_id,
+            &owner.pubkey(),
+            &issuer.pubkey(),
+            &mint.pubkey(),
+            &platform_state,
+            side,
+            price,
+            quantity,
+        )
+    };
+    let crank = |fills: &[(Pubkey, Pubkey)]| {
+        match_orders(
+            &program_id,
+            &issuer.pubkey(),
+            &mint.pubkey(),
+            &platform_state,
+            &treasury,
+            fills,
+        )
+    };
+
+    // the ask rests first and sets the price the bid above it trades at
+    harness
+        .process(&[order(&seller, 1, price, 10_000)], &[&seller])
+        .await;
+    harness
+        .process(&[order(&bidder, 0, price * 2, 4_000)], &[&bidder])
+        .await;
+
+    let ask_basis = harness.unpack::<OrderBook>(order_book_address).await.asks[0].cost_basis;
+
+    let seller_before = harness.lamports(seller.pubkey()).await;
+    let fees_before = harness.lamports(treasury).await;
+    harness
+        .process(&[crank(&[(bidder.pubkey(), seller.pubkey())])], &[])
+        .await;
+
+    let proceeds = price * 4_000 / 10_000;
+    let fee = proceeds / 100;
+
+    assert_eq!(harness.token_balance(bidder_ata).await, 4_000);
+    assert_eq!(
+        harness.lamports(seller.pubkey()).await,
+        seller_before + proceeds - fee
+    );
+    assert_eq!(harness.lamports(treasury).await, fees_before + 2 * fee);
+    // the rest of the bid's escrow went back to the bidder
+    assert_eq!(harness.lamports(bid_vault).await, rent.minimum_balance(0));
+
+    let bidder_state: BuyerState = harness.unpack(bidder_state_address).await;
+    assert_eq!(bidder_state.current_holding_in_tokens, 4_000);
+    assert_eq!(bidder_state.cost_basis, proceeds + fee);
+
+    let seller_state: BuyerState = harness.unpack(seller_state_address).await;
+    let released_basis = ask_basis * 4_000 / 10_000;
+    assert_eq!(seller_state.current_holding_in_tokens, 10_000);
+    assert_eq!(
+        seller_state.realized_pnl,
+        (proceeds - fee) as i64 - released_basis as i64
+    );
+
+    let order_book: OrderBook = harness.unpack(order_book_address).await;
+    assert!(order_book.bids.is_empty());
+    assert_eq!(order_book.asks[0].quantity, 6_000);
+    assert_eq!(order_book.asks[0].cost_basis, ask_basis - released_basis);
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.holders, listing.holders + 1);
+    assert_eq!(user_state.cmp, price);
+
+    // a pool buy moves the price away from the resting ask
+    harness
+        .buy(&bidder, &issuer.pubkey(), &mint.pubkey(), &treasury, 1_000)
+        .await;
+    let pool_price = harness.unpack::<UserState>(user_state_address).await.cmp;
+    assert!(pool_price > price);
+
+    // the seller's own bid crosses the rest of its ask
+    harness
+        .process(&[order(&seller, 0, price, 6_000)], &[&seller])
+        .await;
+    let fees_before = harness.lamports(treasury).await;
+    harness
+        .process(&[crank(&[(seller.pubkey(), seller.pubkey())])], &[])
+        .await;
+
+    let wash_proceeds = price * 6_000 / 10_000;
+    assert_eq!(
+        harness.lamports(treasury).await,
+        fees_before + 2 * (wash_proceeds / 100)
+    );
+    assert_eq!(harness.token_balance(seller_ata).await, 16_000);
+
+    let seller_state: BuyerState = harness.unpack(seller_state_address).await;
+    assert_eq!(seller_state.current_holding_in_tokens, 16_000);
+
+    let order_book: OrderBook = harness.unpack(order_book_address).await;
+    assert!(order_book.bids.is_empty() && order_book.asks.is_empty());
+
+    // a wash trade leaves the market price and the holder count alone
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.cmp, pool_price);
+    assert_eq!(user_state.holders, listing.holders + 1);
+}
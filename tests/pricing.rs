@@ -0,0 +1,270 @@
+use decense::pricing::{CurveKind, PricingCurve, TOKEN_UNIT};
+use proptest::prelude::*;
+
+fn curve(kind: CurveKind, parameter: u64) -> PricingCurve {
+    PricingCurve {
+        kind,
+        parameter,
+        base_price: 1_000_000_000,
+        initial_pool: 100 * TOKEN_UNIT,
+    }
+}
+
+fn any_curve() -> impl Strategy<Value = PricingCurve> {
+    // exponential curves get smaller pools so that quotes stay within u64 over the
+    // whole pool
+    let kind = prop_oneof![
+        (
+            Just(CurveKind::ConstantProduct),
+            Just(0u64),
+            TOKEN_UNIT..=10_000_000 * TOKEN_UNIT
+        ),
+        (
+            Just(CurveKind::Linear),
+            0..=1_000_000_000u64,
+            TOKEN_UNIT..=10_000_000 * TOKEN_UNIT
+        ),
+        (
+            Just(CurveKind::Exponential),
+            0..=10u64,
+            TOKEN_UNIT..=10_000 * TOKEN_UNIT
+        ),
+    ];
+
+    (kind, 1..=10_000_000_000u64).prop_map(|((kind, parameter, initial_pool), base_price)| {
+        PricingCurve {
+            kind,
+            parameter,
+            base_price,
+            initial_pool,
+        }
+    })
+}
+
+// a curve together with a pool balance between empty and twice the initial pool
+fn curve_and_pool() -> impl Strategy<Value = (PricingCurve, u64)> {
+    any_curve().prop_flat_map(|curve| {
+        let max_pool = curve.initial_pool * 2;
+        (Just(curve), 1..=max_pool)
+    })
+}
+
+#[test]
+fn spot_price_at_initial_pool_is_base_price() {
+    for kind in [
+        CurveKind::ConstantProduct,
+        CurveKind::Linear,
+        CurveKind::Exponential,
+    ] {
+        let curve = curve(kind, 10);
+        assert_eq!(curve.spot_price(curve.initial_pool), Some(curve.base_price));
+    }
+}
+
+#[test]
+fn constant_product_quote_matches_closed_form() {
+    let curve = curve(CurveKind::ConstantProduct, 0);
+
+    // 1e9 * 1e6 * (1e6 / 990000 - 1) / 1e4 = 1010101010.1
+    assert_eq!(
+        curve.quote_buy(curve.initial_pool, TOKEN_UNIT),
+        Some(1_010_101_011)
+    );
+    assert_eq!(
+        curve.quote_buy(curve.initial_pool, curve.initial_pool),
+        None
+    );
+}
+
+#[test]
+fn linear_quote_matches_closed_form() {
+    let curve = curve(CurveKind::Linear, 1_000_000);
+
+    // the price moves from 1e9 to 1e9 + 1e6 over the first token
+    assert_eq!(
+        curve.quote_buy(curve.initial_pool, TOKEN_UNIT),
+        Some(1_000_500_000)
+    );
+    assert_eq!(
+        curve.spot_price(curve.initial_pool - TOKEN_UNIT),
+        Some(1_001_000_000)
+    );
+
+    // beyond the initial pool the price stays at the base price
+    assert_eq!(
+        curve.quote_sell(curve.initial_pool, TOKEN_UNIT),
+        Some(1_000_000_000)
+    );
+}
+
+#[test]
+fn exponential_quote_matches_closed_form() {
+    let flat = curve(CurveKind::Exponential, 0);
+    assert_eq!(
+        flat.quote_buy(flat.initial_pool, TOKEN_UNIT),
+        Some(1_000_000_000)
+    );
+
+    // 100 bps per token over 10 tokens: 1e9 * 1e4 / 100 * (e^0.1 - 1) = 1051709180.76
+    let curve = curve(CurveKind::Exponential, 100);
+    assert_eq!(
+        curve.quote_buy(curve.initial_pool, 10 * TOKEN_UNIT),
+        Some(10_517_091_808)
+    );
+    assert_eq!(
+        curve.spot_price(curve.initial_pool - 10 * TOKEN_UNIT),
+        Some(1_105_170_918)
+    );
+}
+
+#[test]
+fn split_moves_prices_by_the_inverse_ratio() {
+    for (numerator, denominator) in [(3, 2), (2, 3), (7, 3)] {
+        for kind in [
+            CurveKind::ConstantProduct,
+            CurveKind::Linear,
+            CurveKind::Exponential,
+        ] {
+            // parameters every one of these ratios scales exactly
+            let curve = curve(kind, 36 * 49);
+            let split = curve.split(numerator, denominator).unwrap();
+
+            for pool in [curve.initial_pool, curve.initial_pool - 10 * TOKEN_UNIT] {
+                let price = curve.spot_price(pool).unwrap() as u128;
+                let split_pool = pool as u128 * numerator as u128 / denominator as u128;
+                let split_price = split.spot_price(split_pool as u64).unwrap() as u128;
+                let expected = price * denominator as u128 / numerator as u128;
+
+                // balances are floored, which may move the price by a basis point
+                assert!(
+                    split_price.abs_diff(expected) * 10_000 <= expected,
+                    "{kind:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn split_refuses_a_parameter_it_can_not_scale_exactly() {
+    assert_eq!(curve(CurveKind::Linear, 10).split(3, 2), None);
+    assert_eq!(curve(CurveKind::Exponential, 3).split(2, 1), None);
+
+    let split = curve(CurveKind::Linear, 9).split(3, 2).unwrap();
+    assert_eq!(split.parameter, 4);
+    assert_eq!(split.base_price, 666_666_666);
+    assert_eq!(split.initial_pool, 150 * TOKEN_UNIT);
+
+    // constant product curves have no parameter to scale
+    assert!(curve(CurveKind::ConstantProduct, 0).split(7, 3).is_some());
+}
+
+proptest! {
+    #[test]
+    fn spot_price_rises_as_the_pool_empties(
+        (curve, pool) in curve_and_pool(),
+        taken in 0..=u64::MAX,
+    ) {
+        let smaller_pool = (pool - taken % pool).max(1);
+
+        if let (Some(price), Some(higher_price)) =
+            (curve.spot_price(pool), curve.spot_price(smaller_pool))
+        {
+            prop_assert!(higher_price >= price);
+        }
+    }
+
+    #[test]
+    fn quotes_grow_with_quantity(
+        (curve, pool) in curve_and_pool(),
+        first in 0..=u64::MAX,
+        second in 0..=u64::MAX,
+    ) {
+        let (small, large) = if first % pool <= second % pool {
+            (first % pool, second % pool)
+        } else {
+            (second % pool, first % pool)
+        };
+
+        if let (Some(small_cost), Some(large_cost)) =
+            (curve.quote_buy(pool, small), curve.quote_buy(pool, large))
+        {
+            prop_assert!(small_cost <= large_cost);
+        }
+
+        if let (Some(small_proceeds), Some(large_proceeds)) =
+            (curve.quote_sell(pool, small), curve.quote_sell(pool, large))
+        {
+            prop_assert!(small_proceeds <= large_proceeds);
+        }
+    }
+
+    #[test]
+    fn buying_then_selling_never_profits(
+        (curve, pool) in curve_and_pool(),
+        quantity in 0..=u64::MAX,
+    ) {
+        let quantity = quantity % pool;
+
+        if let Some(cost) = curve.quote_buy(pool, quantity) {
+            let proceeds = curve.quote_sell(pool - quantity, quantity).unwrap();
+            prop_assert!(proceeds <= cost);
+        }
+    }
+
+    #[test]
+    fn selling_then_buying_back_never_profits(
+        (curve, pool) in curve_and_pool(),
+        quantity in 0..=u64::MAX,
+    ) {
+        let quantity = quantity % pool;
+
+        if let Some(proceeds) = curve.quote_sell(pool, quantity) {
+            if let Some(cost) = curve.quote_buy(pool + quantity, quantity) {
+                prop_assert!(proceeds <= cost);
+            }
+        }
+    }
+
+    #[test]
+    fn splitting_a_buy_never_costs_less(
+        (curve, pool) in curve_and_pool(),
+        first in 0..=u64::MAX,
+        second in 0..=u64::MAX,
+    ) {
+        let first = first % pool;
+        let second = second % (pool - first);
+
+        if let (Some(first_cost), Some(second_cost), Some(total_cost)) = (
+            curve.quote_buy(pool, first),
+            curve.quote_buy(pool - first, second),
+            curve.quote_buy(pool, first + second),
+        ) {
+            prop_assert!(first_cost as u128 + second_cost as u128 >= total_cost as u128);
+        }
+    }
+
+    #[test]
+    fn constant_product_quotes_near_u64_max_pools_do_not_overflow(
+        initial_pool in u64::MAX / 2..=u64::MAX,
+        pool in u64::MAX / 2..=u64::MAX,
+        base_price in 1..=10_000_000_000u64,
+        quantity in 0..=u64::MAX,
+    ) {
+        let curve = PricingCurve {
+            kind: CurveKind::ConstantProduct,
+            parameter: 0,
+            base_price,
+            initial_pool,
+        };
+
+        curve.spot_price(pool);
+        curve.quote_sell(pool, quantity);
+
+        if let Some(cost) = curve.quote_buy(pool, quantity % pool) {
+            if let Some(proceeds) = curve.quote_sell(pool - quantity % pool, quantity % pool) {
+                prop_assert!(proceeds <= cost);
+            }
+        }
+    }
+}
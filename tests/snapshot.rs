@@ -0,0 +1,41 @@
+use decense::snapshot::{leaf, proof, root, verify};
+use solana_program::pubkey::Pubkey;
+
+fn leaves(count: u64) -> (Vec<Pubkey>, Vec<[u8; 32]>) {
+    let holders: Vec<Pubkey> = (0..count).map(|_| Pubkey::new_unique()).collect();
+    let leaves = holders
+        .iter()
+        .zip(1..)
+        .map(|(holder, balance)| leaf(holder, balance * 10_000))
+        .collect();
+
+    (holders, leaves)
+}
+
+#[test]
+fn every_holder_can_prove_its_balance() {
+    for count in 1..=9 {
+        let (_, leaves) = leaves(count);
+        let root = root(&leaves).unwrap();
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            assert!(verify(&root, *leaf, &proof(&leaves, index)));
+        }
+    }
+}
+
+#[test]
+fn proofs_do_not_verify_other_balances() {
+    let (holders, leaves) = leaves(5);
+    let root = root(&leaves).unwrap();
+
+    let proof = proof(&leaves, 2);
+    assert!(verify(&root, leaf(&holders[2], 30_000), &proof));
+    assert!(!verify(&root, leaf(&holders[2], 30_001), &proof));
+    assert!(!verify(&root, leaf(&holders[3], 30_000), &proof));
+}
+
+#[test]
+fn empty_snapshot_has_no_root() {
+    assert_eq!(root(&[]), None);
+}
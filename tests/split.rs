@@ -0,0 +1,344 @@
+mod common;
+
+use common::{assert_custom_error, Harness, ListingTerms};
+use decense::{
+    error::DecenseError,
+    instruction::{
+        claim_split, get_buyer_state_address, get_listing_pda_address, get_split_address,
+        get_user_state_address, get_vesting_address, place_order, release_vested, sell_to_pool,
+        split,
+    },
+    state::{BuyerState, Split, UserState, Vesting},
+};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use spl_associated_token_account::get_associated_token_address;
+
+#[tokio::test]
+async fn forward_split_pays_out_new_shares_from_the_vault() {
+    let issuer = Keypair::new();
+    let buyer = Keypair::new();
+    let fresh = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness =
+        Harness::start(&[issuer.pubkey(), buyer.pubkey(), fresh.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+
+    let platform_state = harness.initialize_platform(&treasury).await;
+    harness.list(&issuer, &mint, &treasury, None).await;
+    // an odd amount, so that the conversion has to round
+    harness
+        .buy(&buyer, &issuer.pubkey(), &mint.pubkey(), &treasury, 10_001)
+        .await;
+
+    let user_state_address = get_user_state_address(&mint.pubkey(), &program_id);
+    let before: UserState = harness.unpack(user_state_address).await;
+
+    // three new shares for every two
+    harness
+        .process(
+            &[split(
+                &program_id,
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                &platform_state,
+                0,
+                3,
+                2,
+            )],
+            &[&issuer],
+        )
+        .await;
+
+    let split_address = get_split_address(&mint.pubkey(), 0, &program_id);
+    let split_record: Split = harness.unpack(split_address).await;
+    // everything outside the pool and the vesting account
+    assert_eq!(split_record.eligible_supply, 510_001);
+
+    let vault = get_associated_token_address(&split_address, &mint.pubkey());
+    let pda_ata = get_associated_token_address(
+        &get_listing_pda_address(&mint.pubkey(), &program_id),
+        &mint.pubkey(),
+    );
+    assert_eq!(harness.token_balance(vault).await, 255_000);
+    assert_eq!(harness.token_balance(pda_ata).await, 734_998);
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.supply, 150);
+    assert_eq!(user_state.market_valuation, before.market_valuation);
+    assert_eq!(user_state.initial_pool, 750_000);
+    assert_eq!(user_state.base_price, before.base_price * 2 / 3);
+    assert_eq!(user_state.cmp, before.cmp * 2 / 3);
+    assert_eq!(user_state.split_count, 1);
+
+    let claim = claim_split(&program_id, &buyer.pubkey(), &mint.pubkey(), 0);
+    harness
+        .process(std::slice::from_ref(&claim), &[&buyer])
+        .await;
+
+    // 15_001.5 rounds down
+    let buyer_ata = get_associated_token_address(&buyer.pubkey(), &mint.pubkey());
+    assert_eq!(harness.token_balance(buyer_ata).await, 15_001);
+
+    let buyer_state: BuyerState = harness
+        .unpack(get_buyer_state_address(
+            &mint.pubkey(),
+            &buyer.pubkey(),
+            &program_id,
+        ))
+        .await;
+    assert_eq!(buyer_state.current_holding_in_tokens, 15_001);
+    assert_eq!(buyer_state.split_count, 1);
+
+    harness.refresh_blockhash().await;
+    assert_custom_error(
+        harness.try_process(&[claim], &[&buyer]).await,
+        DecenseError::SplitAlreadyClaimed,
+    );
+
+    // unconverted tokens moved to a wallet whose position opened after the split
+    harness
+        .buy(&fresh, &issuer.pubkey(), &mint.pubkey(), &treasury, 10_000)
+        .await;
+
+    let issuer_ata = get_associated_token_address(&issuer.pubkey(), &mint.pubkey());
+    let fresh_ata = get_associated_token_address(&fresh.pubkey(), &mint.pubkey());
+    harness
+        .process(
+            &[spl_token::instruction::transfer(
+                &spl_token::id(),
+                &issuer_ata,
+                &fresh_ata,
+                &issuer.pubkey(),
+                &[],
+                100_000,
+            )
+            .unwrap()],
+            &[&issuer],
+        )
+        .await;
+
+    // they neither sell at the new price per share nor convert a second time
+    assert_custom_error(
+        harness
+            .try_process(
+                &[sell_to_pool(
+                    &program_id,
+                    &fresh.pubkey(),
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    &platform_state,
+                    &treasury,
+                    None,
+                    110_000,
+                    0,
+                )],
+                &[&fresh],
+            )
+            .await,
+        DecenseError::PositionExceeded,
+    );
+    assert_custom_error(
+        harness
+            .try_process(
+                &[claim_split(&program_id, &fresh.pubkey(), &mint.pubkey(), 0)],
+                &[&fresh],
+            )
+            .await,
+        DecenseError::SplitAlreadyClaimed,
+    );
+
+    // the issuer's position still converts what it acquired
+    harness
+        .process(
+            &[claim_split(
+                &program_id,
+                &issuer.pubkey(),
+                &mint.pubkey(),
+                0,
+            )],
+            &[&issuer],
+        )
+        .await;
+
+    assert_eq!(harness.token_balance(issuer_ata).await, 650_000);
+    let issuer_state: BuyerState = harness
+        .unpack(get_buyer_state_address(
+            &mint.pubkey(),
+            &issuer.pubkey(),
+            &program_id,
+        ))
+        .await;
+    assert_eq!(issuer_state.current_holding_in_tokens, 750_000);
+    // the vault held exactly what the positions convert to
+    assert_eq!(harness.token_balance(vault).await, 0);
+
+    // the pool keeps trading on the rescaled curve
+    harness.refresh_blockhash().await;
+    harness
+        .buy(&buyer, &issuer.pubkey(), &mint.pubkey(), &treasury, 10_000)
+        .await;
+    assert_eq!(harness.token_balance(buyer_ata).await, 25_001);
+}
+
+#[tokio::test]
+async fn reverse_split_burns_shares_and_rescales_vesting() {
+    let issuer = Keypair::new();
+    let buyer = Keypair::new();
+    let late = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness =
+        Harness::start(&[issuer.pubkey(), buyer.pubkey(), late.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+
+    let platform_state = harness.initialize_platform(&treasury).await;
+    harness
+        .list_with_terms(
+            &issuer,
+            &mint,
+            &treasury,
+            None,
+            ListingTerms {
+                vesting: [100, 1_000, 100],
+                ..ListingTerms::default()
+            },
+        )
+        .await;
+    harness
+        .buy(&buyer, &issuer.pubkey(), &mint.pubkey(), &treasury, 10_001)
+        .await;
+
+    let user_state_address = get_user_state_address(&mint.pubkey(), &program_id);
+    let before: UserState = harness.unpack(user_state_address).await;
+
+    // the issuer's share sits in the vesting account, which the split converts itself
+    let split_ix = |numerator, denominator| {
+        split(
+            &program_id,
+            &issuer.pubkey(),
+            &mint.pubkey(),
+            &platform_state,
+            0,
+            numerator,
+            denominator,
+        )
+    };
+
+    assert_custom_error(
+        harness.try_process(&[split_ix(1, 1)], &[&issuer]).await,
+        DecenseError::InvalidSplit,
+    );
+
+    harness.process(&[split_ix(2, 3)], &[&issuer]).await;
+
+    let pda_ata = get_associated_token_address(
+        &get_listing_pda_address(&mint.pubkey(), &program_id),
+        &mint.pubkey(),
+    );
+    let vesting_address = get_vesting_address(&mint.pubkey(), &program_id);
+    let vesting_ata = get_associated_token_address(&vesting_address, &mint.pubkey());
+    assert_eq!(harness.token_balance(pda_ata).await, 326_666);
+    assert_eq!(harness.token_balance(vesting_ata).await, 333_333);
+
+    let vesting: Vesting = harness.unpack(vesting_address).await;
+    assert_eq!(vesting.total_amount, 333_333);
+    assert_eq!(vesting.released_amount, 0);
+
+    let user_state: UserState = harness.unpack(user_state_address).await;
+    assert_eq!(user_state.supply, 66);
+    assert_eq!(user_state.market_valuation, before.market_valuation);
+    assert_eq!(user_state.cmp, before.cmp * 3 / 2);
+
+    let sell = |seller: &Keypair, quantity| {
+        sell_to_pool(
+            &program_id,
+            &seller.pubkey(),
+            &issuer.pubkey(),
+            &mint.pubkey(),
+            &platform_state,
+            &treasury,
+            None,
+            quantity,
+            0,
+        )
+    };
+
+    // unconverted shares would trade at the new price per share
+    assert_custom_error(
+        harness
+            .try_process(&[sell(&buyer, 10_001)], &[&buyer])
+            .await,
+        DecenseError::SplitNotClaimed,
+    );
+    assert_custom_error(
+        harness
+            .try_process(
+                &[place_order(
+                    &program_id,
+                    &buyer.pubkey(),
+                    &issuer.pubkey(),
+                    &mint.pubkey(),
+                    &platform_state,
+                    1,
+                    1,
+                    10_001,
+                )],
+                &[&buyer],
+            )
+            .await,
+        DecenseError::SplitNotClaimed,
+    );
+
+    // a position opened after the split has nothing to convert
+    harness
+        .buy(&late, &issuer.pubkey(), &mint.pubkey(), &treasury, 10_000)
+        .await;
+    harness.process(&[sell(&late, 4_000)], &[&late]).await;
+
+    harness
+        .process(
+            &[claim_split(&program_id, &buyer.pubkey(), &mint.pubkey(), 0)],
+            &[&buyer],
+        )
+        .await;
+
+    // 6_667.33 rounds down, the rest is burned
+    let buyer_ata = get_associated_token_address(&buyer.pubkey(), &mint.pubkey());
+    assert_eq!(harness.token_balance(buyer_ata).await, 6_667);
+
+    let buyer_state: BuyerState = harness
+        .unpack(get_buyer_state_address(
+            &mint.pubkey(),
+            &buyer.pubkey(),
+            &program_id,
+        ))
+        .await;
+    assert_eq!(buyer_state.current_holding_in_tokens, 6_667);
+    assert_eq!(buyer_state.split_count, 1);
+
+    harness.process(&[sell(&buyer, 2_000)], &[&buyer]).await;
+    assert_eq!(harness.token_balance(buyer_ata).await, 4_667);
+
+    // the whole converted balance still vests
+    harness.advance_clock(1_000).await;
+    harness
+        .process(
+            &[release_vested(
+                &program_id,
+                &issuer.pubkey(),
+                &mint.pubkey(),
+            )],
+            &[&issuer],
+        )
+        .await;
+
+    let issuer_ata = get_associated_token_address(&issuer.pubkey(), &mint.pubkey());
+    assert_eq!(harness.token_balance(issuer_ata).await, 333_333);
+    assert_eq!(harness.token_balance(vesting_ata).await, 0);
+}
@@ -0,0 +1,745 @@
+use decense::{
+    error::DecenseError,
+    instruction::{get_buyer_state_address, get_platform_state_address, get_user_state_address},
+    processor::Processor,
+    state::{AccountType, BuyerState, PlatformState, UserState},
+    validation::{
+        assert_associated_token_account, assert_owned_by, assert_pda, assert_program_id,
+        assert_signer,
+    },
+};
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey,
+    system_program, sysvar,
+};
+use spl_associated_token_account::get_associated_token_address;
+
+struct TestAccount {
+    key: Pubkey,
+    is_signer: bool,
+    is_writable: bool,
+    lamports: u64,
+    data: Vec<u8>,
+    owner: Pubkey,
+}
+
+impl TestAccount {
+    fn new(key: Pubkey, owner: Pubkey) -> Self {
+        Self {
+            key,
+            is_signer: false,
+            is_writable: true,
+            lamports: 1_000_000_000,
+            data: vec![],
+            owner,
+        }
+    }
+
+    fn signer(mut self) -> Self {
+        self.is_signer = true;
+        self
+    }
+
+    fn with_data(mut self, data: Vec<u8>) -> Self {
+        self.data = data;
+        self
+    }
+
+    fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            self.is_signer,
+            self.is_writable,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            false,
+            0,
+        )
+    }
+}
+
+fn process(
+    program_id: &Pubkey,
+    accounts: &mut [TestAccount],
+    data: &[u8],
+) -> Result<(), ProgramError> {
+    let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+    Processor::unpack_and_process_instruction(program_id, &infos, data)
+}
+
+fn instruction_data(tag: u8, args: &[u64]) -> Vec<u8> {
+    let mut data = vec![tag];
+    for arg in args {
+        data.extend_from_slice(&arg.to_le_bytes());
+    }
+    data
+}
+
+struct Listing {
+    program_id: Pubkey,
+    admin: Pubkey,
+    platform_state: Pubkey,
+    treasury: Pubkey,
+    issuer: Pubkey,
+    mint: Pubkey,
+    state: Pubkey,
+    pda: Pubkey,
+    pda_ata: Pubkey,
+}
+
+impl Listing {
+    fn new() -> Self {
+        let program_id = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let issuer = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let (pda, _) = Pubkey::find_program_address(&[mint.as_ref()], &program_id);
+
+        Self {
+            program_id,
+            admin,
+            platform_state: get_platform_state_address(&program_id),
+            treasury: Pubkey::new_unique(),
+            issuer,
+            mint,
+            state: get_user_state_address(&mint, &program_id),
+            pda,
+            pda_ata: get_associated_token_address(&pda, &mint),
+        }
+    }
+
+    fn platform_data(&self) -> Vec<u8> {
+        let mut data = vec![0; PlatformState::LEN];
+        PlatformState::pack(
+            PlatformState {
+                is_initialized: true,
+                platform_treasury_sol_wallet: self.treasury,
+                listing_fee: 1_000_000_000,
+                buy_fee_bps: 100,
+                sell_fee_bps: 100,
+                fee_recipient: self.treasury,
+                admin: self.admin,
+                pending_admin: Pubkey::default(),
+                paused: false,
+            },
+            &mut data,
+        )
+        .unwrap();
+        data
+    }
+
+    // platform state followed by the fee recipient
+    fn platform_accounts(&self) -> Vec<TestAccount> {
+        vec![
+            TestAccount::new(self.platform_state, self.program_id).with_data(self.platform_data()),
+            TestAccount::new(self.treasury, system_program::id()),
+        ]
+    }
+
+    fn state_data(&self) -> Vec<u8> {
+        let mut data = vec![0; UserState::LEN];
+        UserState::pack(
+            UserState {
+                is_initialized: true,
+                user: self.issuer,
+                market_valuation: 1_000,
+                supply: 100,
+                user_token_mint: self.mint,
+                user_ata: get_associated_token_address(&self.issuer, &self.mint),
+                user_treasury_percentage: 50,
+                liquidate_percentage: 50,
+                pda_ata: self.pda_ata,
+                cmp: 10_000_000_000,
+                holders: 0,
+                pricing_curve: 0,
+                curve_parameter: 0,
+                base_price: 10_000_000_000,
+                initial_pool: 500_000,
+                quote_mint: Pubkey::default(),
+                dividend_count: 0,
+                proposal_count: 0,
+                platform_state: self.platform_state,
+                halt_reason: 0,
+                halted_until: 0,
+                breaker_max_move_bps: 0,
+                breaker_window: 0,
+                breaker_cooldown: 0,
+                breaker_window_start: 0,
+                breaker_reference_price: 0,
+                split_count: 0,
+                issued_supply: 0,
+            },
+            &mut data,
+        )
+        .unwrap();
+        data
+    }
+
+    fn buyer_state(&self, buyer: &Pubkey) -> Pubkey {
+        get_buyer_state_address(&self.mint, buyer, &self.program_id)
+    }
+
+    fn vault(&self) -> Pubkey {
+        Pubkey::find_program_address(&[self.mint.as_ref(), b"vault"], &self.program_id).0
+    }
+
+    fn exchange_accounts(&self, exchanger: Pubkey) -> Vec<TestAccount> {
+        let mut accounts = vec![
+            TestAccount::new(exchanger, system_program::id()).signer(),
+            TestAccount::new(self.buyer_state(&exchanger), self.program_id),
+            TestAccount::new(
+                get_associated_token_address(&exchanger, &self.mint),
+                spl_token::id(),
+            ),
+            TestAccount::new(self.issuer, system_program::id()),
+            TestAccount::new(self.mint, spl_token::id()),
+            TestAccount::new(self.state, self.program_id).with_data(self.state_data()),
+            TestAccount::new(self.pda, system_program::id()),
+            TestAccount::new(self.pda_ata, spl_token::id()),
+            TestAccount::new(self.vault(), system_program::id()),
+            TestAccount::new(spl_token::id(), Pubkey::default()),
+            TestAccount::new(sysvar::rent::id(), sysvar::id()),
+            TestAccount::new(spl_associated_token_account::id(), Pubkey::default()),
+            TestAccount::new(system_program::id(), Pubkey::default()),
+        ];
+        accounts.splice(9..9, self.platform_accounts());
+        accounts
+    }
+
+    fn sell_to_pool_accounts(&self, seller: Pubkey) -> Vec<TestAccount> {
+        let mut seller_state_data = vec![0; BuyerState::LEN];
+        BuyerState::pack(
+            BuyerState {
+                is_initialized: true,
+                buyer: seller,
+                current_holding_in_tokens: 10,
+                mint: self.mint,
+                cost_basis: 1_000,
+                realized_pnl: 0,
+                split_count: 0,
+            },
+            &mut seller_state_data,
+        )
+        .unwrap();
+
+        let mut accounts = vec![
+            TestAccount::new(seller, system_program::id()).signer(),
+            TestAccount::new(self.buyer_state(&seller), self.program_id)
+                .with_data(seller_state_data),
+            TestAccount::new(
+                get_associated_token_address(&seller, &self.mint),
+                spl_token::id(),
+            ),
+            TestAccount::new(self.issuer, system_program::id()),
+            TestAccount::new(self.mint, spl_token::id()),
+            TestAccount::new(self.state, self.program_id).with_data(self.state_data()),
+            TestAccount::new(self.pda, system_program::id()),
+            TestAccount::new(self.pda_ata, spl_token::id()),
+            TestAccount::new(self.vault(), system_program::id()),
+            TestAccount::new(spl_token::id(), Pubkey::default()),
+            TestAccount::new(system_program::id(), Pubkey::default()),
+        ];
+        accounts.splice(9..9, self.platform_accounts());
+        accounts
+    }
+
+    fn send_receive_accounts(&self, exchanger: Pubkey) -> Vec<TestAccount> {
+        vec![
+            TestAccount::new(self.issuer, system_program::id()),
+            TestAccount::new(self.state, self.program_id).with_data(self.state_data()),
+            TestAccount::new(self.mint, spl_token::id()),
+            TestAccount::new(exchanger, system_program::id()).signer(),
+            TestAccount::new(self.buyer_state(&exchanger), self.program_id),
+            TestAccount::new(
+                get_associated_token_address(&exchanger, &self.mint),
+                spl_token::id(),
+            ),
+            TestAccount::new(self.pda, system_program::id()),
+            TestAccount::new(self.pda_ata, spl_token::id()),
+            TestAccount::new(self.platform_state, self.program_id).with_data(self.platform_data()),
+            TestAccount::new(spl_token::id(), Pubkey::default()),
+            TestAccount::new(system_program::id(), Pubkey::default()),
+        ]
+    }
+}
+
+#[test]
+fn assert_signer_rejects_non_signer() {
+    let mut account = TestAccount::new(Pubkey::new_unique(), system_program::id());
+    assert_eq!(
+        assert_signer(&account.info()),
+        Err(ProgramError::MissingRequiredSignature)
+    );
+
+    let mut account = account.signer();
+    assert_eq!(assert_signer(&account.info()), Ok(()));
+}
+
+#[test]
+fn assert_owned_by_rejects_foreign_owner() {
+    let program_id = Pubkey::new_unique();
+    let mut account = TestAccount::new(Pubkey::new_unique(), Pubkey::new_unique());
+    assert_eq!(
+        assert_owned_by(&account.info(), &program_id),
+        Err(DecenseError::InvalidAccountOwner.into())
+    );
+}
+
+#[test]
+fn assert_program_id_rejects_spoofed_program() {
+    let mut fake_token_program = TestAccount::new(Pubkey::new_unique(), Pubkey::default());
+    assert_eq!(
+        assert_program_id(&fake_token_program.info(), &spl_token::id()),
+        Err(ProgramError::IncorrectProgramId)
+    );
+}
+
+#[test]
+fn assert_pda_returns_bump_and_rejects_other_keys() {
+    let program_id = Pubkey::new_unique();
+    let issuer = Pubkey::new_unique();
+    let (pda, bump_seed) = Pubkey::find_program_address(&[issuer.as_ref()], &program_id);
+
+    let mut account = TestAccount::new(pda, system_program::id());
+    assert_eq!(
+        assert_pda(&account.info(), &[issuer.as_ref()], &program_id),
+        Ok(bump_seed)
+    );
+
+    let mut spoofed = TestAccount::new(Pubkey::new_unique(), system_program::id());
+    assert_eq!(
+        assert_pda(&spoofed.info(), &[issuer.as_ref()], &program_id),
+        Err(DecenseError::InvalidPDA.into())
+    );
+}
+
+#[test]
+fn assert_associated_token_account_rejects_other_token_accounts() {
+    let wallet = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    let mut ata = TestAccount::new(
+        get_associated_token_address(&wallet, &mint),
+        spl_token::id(),
+    );
+    assert_eq!(
+        assert_associated_token_account(&ata.info(), &wallet, &mint),
+        Ok(())
+    );
+
+    let mut spoofed = TestAccount::new(Pubkey::new_unique(), spl_token::id());
+    assert_eq!(
+        assert_associated_token_account(&spoofed.info(), &wallet, &mint),
+        Err(DecenseError::AccountKeyMismatch.into())
+    );
+}
+
+#[test]
+fn exchange_rejects_unsigned_exchanger() {
+    let listing = Listing::new();
+    let mut accounts = listing.exchange_accounts(Pubkey::new_unique());
+    accounts[0].is_signer = false;
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(2, &[1, u64::MAX, 0])
+        ),
+        Err(ProgramError::MissingRequiredSignature)
+    );
+}
+
+#[test]
+fn exchange_rejects_spoofed_token_program() {
+    let listing = Listing::new();
+    let mut accounts = listing.exchange_accounts(Pubkey::new_unique());
+    accounts[11].key = Pubkey::new_unique();
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(2, &[1, u64::MAX, 0])
+        ),
+        Err(ProgramError::IncorrectProgramId)
+    );
+}
+
+#[test]
+fn exchange_rejects_state_owned_by_other_program() {
+    let listing = Listing::new();
+    let mut accounts = listing.exchange_accounts(Pubkey::new_unique());
+    accounts[5].owner = Pubkey::new_unique();
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(2, &[1, u64::MAX, 0])
+        ),
+        Err(DecenseError::InvalidAccountOwner.into())
+    );
+}
+
+#[test]
+fn exchange_rejects_spoofed_pda() {
+    let listing = Listing::new();
+    let mut accounts = listing.exchange_accounts(Pubkey::new_unique());
+    accounts[6].key = Pubkey::new_unique();
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(2, &[1, u64::MAX, 0])
+        ),
+        Err(DecenseError::InvalidPDA.into())
+    );
+}
+
+#[test]
+fn exchange_rejects_pool_account_not_matching_state() {
+    let listing = Listing::new();
+    let mut accounts = listing.exchange_accounts(Pubkey::new_unique());
+    accounts[7].key = Pubkey::new_unique();
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(2, &[1, u64::MAX, 0])
+        ),
+        Err(DecenseError::AccountKeyMismatch.into())
+    );
+}
+
+#[test]
+fn exchange_rejects_mint_not_matching_state() {
+    let listing = Listing::new();
+    let exchanger = Pubkey::new_unique();
+    let fake_mint = Pubkey::new_unique();
+    let mut accounts = listing.exchange_accounts(exchanger);
+    accounts[2].key = get_associated_token_address(&exchanger, &fake_mint);
+    accounts[4].key = fake_mint;
+
+    // the listing accounts are derived from the mint, so another mint no longer finds them
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(2, &[1, u64::MAX, 0])
+        ),
+        Err(DecenseError::InvalidPDA.into())
+    );
+}
+
+#[test]
+fn send_receive_requires_issuer_signature_to_release_pool_tokens() {
+    let listing = Listing::new();
+    let mut accounts = listing.send_receive_accounts(Pubkey::new_unique());
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(3, &[1, 1])
+        ),
+        Err(ProgramError::MissingRequiredSignature)
+    );
+}
+
+#[test]
+fn sell_to_pool_rejects_spoofed_vault() {
+    let listing = Listing::new();
+    let mut accounts = listing.sell_to_pool_accounts(Pubkey::new_unique());
+    accounts[8].key = Pubkey::new_unique();
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(4, &[1, 0])
+        ),
+        Err(DecenseError::InvalidPDA.into())
+    );
+}
+
+#[test]
+fn sell_to_pool_rejects_position_of_another_wallet() {
+    let listing = Listing::new();
+    let mut accounts = listing.sell_to_pool_accounts(Pubkey::new_unique());
+    let other_position = listing
+        .sell_to_pool_accounts(Pubkey::new_unique())
+        .swap_remove(1);
+    accounts[1] = other_position;
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(4, &[1, 0])
+        ),
+        Err(DecenseError::InvalidPDA.into())
+    );
+}
+
+#[test]
+fn sell_to_pool_rejects_a_platform_other_than_the_programs() {
+    let listing = Listing::new();
+    let mut accounts = listing.sell_to_pool_accounts(Pubkey::new_unique());
+    // a platform the seller set up with no sell fee
+    accounts[9].key = Pubkey::new_unique();
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(4, &[1, 0])
+        ),
+        Err(DecenseError::InvalidPDA.into())
+    );
+}
+
+#[test]
+fn initialize_user_rejects_spoofed_treasury_wallet() {
+    let listing = Listing::new();
+
+    let mut accounts = vec![
+        TestAccount::new(listing.issuer, system_program::id()).signer(),
+        TestAccount::new(listing.mint, spl_token::id()).signer(),
+        TestAccount::new(listing.state, system_program::id()),
+        TestAccount::new(listing.platform_state, listing.program_id)
+            .with_data(listing.platform_data()),
+        TestAccount::new(Pubkey::new_unique(), system_program::id()),
+        TestAccount::new(listing.pda, system_program::id()),
+        TestAccount::new(
+            get_associated_token_address(&listing.issuer, &listing.mint),
+            system_program::id(),
+        ),
+        TestAccount::new(listing.pda_ata, system_program::id()),
+        TestAccount::new(listing.vault(), system_program::id()),
+        TestAccount::new(listing.buyer_state(&listing.issuer), system_program::id()),
+        TestAccount::new(spl_token::id(), Pubkey::default()),
+        TestAccount::new(sysvar::rent::id(), sysvar::id()),
+        TestAccount::new(spl_associated_token_account::id(), Pubkey::default()),
+        TestAccount::new(system_program::id(), Pubkey::default()),
+    ];
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(1, &[1_000, 100, 0, 0, 0, 0, 0, 50, 50])
+        ),
+        Err(DecenseError::InvalidTreasuryWallet.into())
+    );
+}
+
+#[test]
+fn update_platform_config_requires_platform_admin() {
+    let listing = Listing::new();
+    let mut accounts = listing.platform_accounts();
+    accounts.insert(
+        0,
+        TestAccount::new(Pubkey::new_unique(), system_program::id()).signer(),
+    );
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(8, &[0, 50, 50])
+        ),
+        Err(DecenseError::Unauthorized.into())
+    );
+
+    accounts[0] = TestAccount::new(listing.admin, system_program::id());
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(8, &[0, 50, 50])
+        ),
+        Err(ProgramError::MissingRequiredSignature)
+    );
+}
+
+#[test]
+fn update_platform_config_rejects_fees_above_100_percent() {
+    let listing = Listing::new();
+    let mut accounts = listing.platform_accounts();
+    accounts.insert(
+        0,
+        TestAccount::new(listing.admin, system_program::id()).signer(),
+    );
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(8, &[0, 10_001, 0])
+        ),
+        Err(DecenseError::InvalidFee.into())
+    );
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(8, &[0, 250, 300])
+        ),
+        Ok(())
+    );
+
+    let platform_state = PlatformState::unpack(&accounts[1].data).unwrap();
+    assert_eq!(platform_state.listing_fee, 0);
+    assert_eq!(platform_state.buy_fee_bps, 250);
+    assert_eq!(platform_state.sell_fee_bps, 300);
+}
+
+#[test]
+fn initialize_platform_rejects_initialized_state() {
+    let listing = Listing::new();
+    let mut accounts = vec![
+        TestAccount::new(Pubkey::new_unique(), system_program::id()).signer(),
+        TestAccount::new(listing.platform_state, listing.program_id)
+            .with_data(listing.platform_data()),
+        TestAccount::new(Pubkey::new_unique(), system_program::id()),
+        TestAccount::new(system_program::id(), Pubkey::default()),
+    ];
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(0, &[])
+        ),
+        Err(ProgramError::AccountAlreadyInitialized)
+    );
+    assert_eq!(accounts[1].data, listing.platform_data());
+}
+
+#[test]
+fn admin_handover_needs_both_steps() {
+    let listing = Listing::new();
+    let new_admin = Pubkey::new_unique();
+
+    let mut accounts = listing.platform_accounts();
+    accounts[1] = TestAccount::new(new_admin, system_program::id()).signer();
+    accounts.insert(
+        0,
+        TestAccount::new(listing.admin, system_program::id()).signer(),
+    );
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(9, &[])
+        ),
+        Ok(())
+    );
+
+    let platform_state = PlatformState::unpack(&accounts[1].data).unwrap();
+    assert_eq!(platform_state.admin, listing.admin);
+    assert_eq!(platform_state.pending_admin, new_admin);
+
+    // anyone other than the pending admin cannot accept
+    accounts.swap(0, 2);
+    accounts[0].key = Pubkey::new_unique();
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts[..2],
+            &instruction_data(10, &[])
+        ),
+        Err(DecenseError::Unauthorized.into())
+    );
+
+    accounts[0].key = new_admin;
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts[..2],
+            &instruction_data(10, &[])
+        ),
+        Ok(())
+    );
+
+    let platform_state = PlatformState::unpack(&accounts[1].data).unwrap();
+    assert_eq!(platform_state.admin, new_admin);
+    assert_eq!(platform_state.pending_admin, Pubkey::default());
+
+    // the previous admin lost its rights
+    let mut accounts = vec![
+        TestAccount::new(listing.admin, system_program::id()).signer(),
+        TestAccount::new(listing.platform_state, listing.program_id)
+            .with_data(accounts.swap_remove(1).data),
+        TestAccount::new(listing.treasury, system_program::id()),
+    ];
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(8, &[0, 0, 0])
+        ),
+        Err(DecenseError::Unauthorized.into())
+    );
+}
+
+#[test]
+fn pause_requires_platform_admin() {
+    let listing = Listing::new();
+    let mut accounts = listing.platform_accounts();
+    accounts[1] = TestAccount::new(Pubkey::new_unique(), system_program::id()).signer();
+    accounts.swap(0, 1);
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(21, &[])
+        ),
+        Err(DecenseError::Unauthorized.into())
+    );
+
+    accounts[0].key = listing.admin;
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(21, &[])
+        ),
+        Ok(())
+    );
+    assert!(PlatformState::unpack(&accounts[1].data).unwrap().paused);
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(22, &[])
+        ),
+        Ok(())
+    );
+    assert!(!PlatformState::unpack(&accounts[1].data).unwrap().paused);
+}
+
+#[test]
+fn sell_to_pool_rejects_listing_state_of_another_type() {
+    let listing = Listing::new();
+    let mut accounts = listing.sell_to_pool_accounts(Pubkey::new_unique());
+    accounts[5].data[2] = AccountType::BuyerState as u8;
+
+    assert_eq!(
+        process(
+            &listing.program_id,
+            &mut accounts,
+            &instruction_data(4, &[1, 0])
+        ),
+        Err(DecenseError::AccountTypeMismatch.into())
+    );
+}
@@ -0,0 +1,164 @@
+mod common;
+
+use common::{assert_custom_error, Harness, ListingTerms};
+use decense::{
+    error::DecenseError,
+    instruction::{get_buyer_state_address, get_vesting_address, initialize_user, release_vested},
+    state::{BuyerState, Vesting},
+};
+use solana_sdk::{
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::Mint;
+
+fn vesting() -> Vesting {
+    Vesting {
+        is_initialized: true,
+        user_token_mint: Pubkey::new_unique(),
+        beneficiary: Pubkey::new_unique(),
+        start_time: 1_000,
+        cliff: 100,
+        duration: 1_000,
+        release_interval: 100,
+        total_amount: 500_000,
+        released_amount: 0,
+    }
+}
+
+#[test]
+fn nothing_vests_before_the_cliff() {
+    let vesting = vesting();
+
+    assert_eq!(vesting.vested_amount(0), Some(0));
+    assert_eq!(vesting.vested_amount(1_099), Some(0));
+    assert_eq!(vesting.vested_amount(1_100), Some(50_000));
+}
+
+#[test]
+fn vesting_unlocks_whole_intervals_until_the_end() {
+    let vesting = vesting();
+
+    assert_eq!(vesting.vested_amount(1_250), Some(100_000));
+    assert_eq!(vesting.vested_amount(1_999), Some(450_000));
+    assert_eq!(vesting.vested_amount(2_000), Some(500_000));
+    assert_eq!(vesting.vested_amount(i64::MAX), Some(500_000));
+}
+
+#[tokio::test]
+async fn issuer_share_is_released_on_schedule() {
+    let issuer = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness = Harness::start(&[issuer.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+
+    harness.initialize_platform(&treasury).await;
+    harness
+        .list_with_terms(
+            &issuer,
+            &mint,
+            &treasury,
+            None,
+            ListingTerms {
+                vesting: [100, 1_000, 100],
+                ..ListingTerms::default()
+            },
+        )
+        .await;
+
+    let vesting_address = get_vesting_address(&mint.pubkey(), &program_id);
+    let vesting_ata = get_associated_token_address(&vesting_address, &mint.pubkey());
+    let issuer_ata = get_associated_token_address(&issuer.pubkey(), &mint.pubkey());
+    assert_eq!(harness.token_balance(vesting_ata).await, 500_000);
+    assert_eq!(harness.token_balance(issuer_ata).await, 0);
+
+    let release = release_vested(&program_id, &issuer.pubkey(), &mint.pubkey());
+
+    assert_custom_error(
+        harness
+            .try_process(std::slice::from_ref(&release), &[&issuer])
+            .await,
+        DecenseError::NothingToRelease,
+    );
+
+    harness.advance_clock(250).await;
+    harness.refresh_blockhash().await;
+    harness
+        .process(std::slice::from_ref(&release), &[&issuer])
+        .await;
+    assert_eq!(harness.token_balance(issuer_ata).await, 100_000);
+
+    harness.advance_clock(1_000).await;
+    harness.refresh_blockhash().await;
+    harness
+        .process(std::slice::from_ref(&release), &[&issuer])
+        .await;
+    assert_eq!(harness.token_balance(issuer_ata).await, 500_000);
+    assert_eq!(harness.token_balance(vesting_ata).await, 0);
+
+    let vesting: Vesting = harness.unpack(vesting_address).await;
+    assert_eq!(vesting.released_amount, 500_000);
+
+    // released tokens joined the issuer's position
+    let issuer_state: BuyerState = harness
+        .unpack(get_buyer_state_address(
+            &mint.pubkey(),
+            &issuer.pubkey(),
+            &program_id,
+        ))
+        .await;
+    assert_eq!(issuer_state.current_holding_in_tokens, 500_000);
+}
+
+#[tokio::test]
+async fn cliff_can_not_outlast_the_schedule() {
+    let issuer = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut harness = Harness::start(&[issuer.pubkey(), treasury]).await;
+    let program_id = harness.program_id;
+    let rent = harness.banks_client.get_rent().await.unwrap();
+
+    let platform_state = harness.initialize_platform(&treasury).await;
+
+    assert_custom_error(
+        harness
+            .try_process(
+                &[
+                    system_instruction::create_account(
+                        &issuer.pubkey(),
+                        &mint.pubkey(),
+                        rent.minimum_balance(Mint::LEN),
+                        Mint::LEN as u64,
+                        &spl_token::id(),
+                    ),
+                    initialize_user(
+                        &program_id,
+                        &issuer.pubkey(),
+                        &mint.pubkey(),
+                        &platform_state,
+                        &treasury,
+                        None,
+                        100,
+                        100,
+                        0,
+                        0,
+                        2_000,
+                        1_000,
+                        100,
+                        50,
+                        50,
+                    ),
+                ],
+                &[&issuer, &mint],
+            )
+            .await,
+        DecenseError::InvalidVestingSchedule,
+    );
+}